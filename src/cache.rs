@@ -0,0 +1,235 @@
+//! Content-hash cache for skipping already-formatted files.
+//!
+//! After a tool succeeds, the hash of each file it processed is recorded under a
+//! key derived from the tool's command and resolved args. On the next run, files
+//! whose content still matches are dropped before the tool is invoked. Changing a
+//! tool's flags changes its key, which invalidates its entries.
+//!
+//! The cache lives in the git directory unless `FFX_CACHE_DIR` points elsewhere.
+
+use crate::config::{Mode, Tool};
+use anyhow::{Context, Result};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::BTreeMap;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+
+/// Cache file name, stored in the git directory.
+pub const CACHE_FILE_NAME: &str = "ffx-cache.json";
+
+/// Environment variable naming a directory to keep caches in instead of the git
+/// directory (e.g. when `.git` is on a read-only mount in CI).
+pub const CACHE_DIR_ENV: &str = "FFX_CACHE_DIR";
+
+/// Where the cache for the repository at `repo_root` lives: `CACHE_FILE_NAME` in
+/// `git_dir`, or with a `cache_dir`, a file named after a hash of the repo root so
+/// several repositories can share the directory.
+pub fn cache_path(cache_dir: Option<&Path>, git_dir: &Path, repo_root: &Path) -> PathBuf {
+    match cache_dir {
+        Some(dir) => {
+            let mut hasher = DefaultHasher::new();
+            repo_root.hash(&mut hasher);
+            dir.join(format!("ffx-cache-{:016x}.json", hasher.finish()))
+        }
+        None => git_dir.join(CACHE_FILE_NAME),
+    }
+}
+
+/// File hashes from previous successful runs, grouped by tool key.
+#[derive(Debug)]
+pub struct Cache {
+    path: PathBuf,
+    entries: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+impl Cache {
+    /// Load the cache from `path`. A missing or unreadable cache starts empty.
+    pub fn load(path: &Path) -> Self {
+        let entries = fs::read_to_string(path)
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default();
+
+        Self {
+            path: path.to_path_buf(),
+            entries,
+        }
+    }
+
+    /// Write the cache back to disk, creating its directory if needed.
+    pub fn save(&self) -> Result<()> {
+        let contents =
+            serde_json::to_string_pretty(&self.entries).context("Failed to serialize cache")?;
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create cache directory: {}", dir.display()))?;
+        }
+        fs::write(&self.path, contents)
+            .with_context(|| format!("Failed to write cache file: {}", self.path.display()))
+    }
+
+    /// Number of files recorded across all tools.
+    pub fn len(&self) -> usize {
+        self.entries.values().map(BTreeMap::len).sum()
+    }
+
+    /// Whether no files are recorded.
+    pub fn is_empty(&self) -> bool {
+        self.entries.values().all(BTreeMap::is_empty)
+    }
+
+    /// Whether `file` is unchanged since `tool` last succeeded on it.
+    pub fn is_fresh(&self, tool: &Tool, mode: Mode, root: &Path, file: &Path) -> bool {
+        let Some(files) = self.entries.get(&tool_key(tool, mode)) else {
+            return false;
+        };
+
+        match (files.get(&path_key(file)), hash_file(&root.join(file))) {
+            (Some(cached), Some(current)) => *cached == current,
+            _ => false,
+        }
+    }
+
+    /// Record the current content hash of each file after `tool` succeeded on them.
+    pub fn record(&mut self, tool: &Tool, mode: Mode, root: &Path, files: &[&Path]) {
+        let entry = self.entries.entry(tool_key(tool, mode)).or_default();
+
+        for file in files {
+            if let Some(hash) = hash_file(&root.join(file)) {
+                entry.insert(path_key(file), hash);
+            }
+        }
+    }
+}
+
+/// Key identifying a tool invocation: name, command, and the args for `mode`.
+fn tool_key(tool: &Tool, mode: Mode) -> String {
+    let mut hasher = DefaultHasher::new();
+    tool.cmd.hash(&mut hasher);
+    tool.shell.hash(&mut hasher);
+    tool.get_args(mode).hash(&mut hasher);
+    format!("{}:{:016x}", tool.name, hasher.finish())
+}
+
+fn path_key(file: &Path) -> String {
+    file.to_string_lossy().into_owned()
+}
+
+/// Hash a file's contents, or `None` if it can't be read.
+///
+/// `DefaultHasher` isn't guaranteed stable across Rust releases; a changed
+/// algorithm only causes cache misses.
+pub fn hash_file(path: &Path) -> Option<String> {
+    let contents = fs::read(path).ok()?;
+    let mut hasher = DefaultHasher::new();
+    contents.hash(&mut hasher);
+    Some(format!("{:016x}", hasher.finish()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_tool(args: &[&str]) -> Tool {
+        Tool {
+            name: "fmt".to_string(),
+            cmd: "fmt".to_string(),
+            args: args.iter().map(|s| s.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_recorded_file_is_fresh_until_changed() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("a.rs"), "fn main() {}").unwrap();
+        let tool = make_tool(&["--write"]);
+        let file = Path::new("a.rs");
+
+        let mut cache = Cache::load(&dir.path().join(CACHE_FILE_NAME));
+        assert!(!cache.is_fresh(&tool, Mode::Format, dir.path(), file));
+
+        cache.record(&tool, Mode::Format, dir.path(), &[file]);
+        assert!(cache.is_fresh(&tool, Mode::Format, dir.path(), file));
+
+        fs::write(dir.path().join("a.rs"), "fn main() { }").unwrap();
+        assert!(!cache.is_fresh(&tool, Mode::Format, dir.path(), file));
+    }
+
+    #[test]
+    fn test_changing_args_invalidates_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("a.rs"), "fn main() {}").unwrap();
+        let file = Path::new("a.rs");
+
+        let mut cache = Cache::load(&dir.path().join(CACHE_FILE_NAME));
+        cache.record(&make_tool(&["--write"]), Mode::Format, dir.path(), &[file]);
+
+        assert!(!cache.is_fresh(
+            &make_tool(&["--write", "--strict"]),
+            Mode::Format,
+            dir.path(),
+            file
+        ));
+    }
+
+    #[test]
+    fn test_save_and_reload_round_trips() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("a.rs"), "fn main() {}").unwrap();
+        let tool = make_tool(&[]);
+        let file = Path::new("a.rs");
+        let cache_path = dir.path().join(CACHE_FILE_NAME);
+
+        let mut cache = Cache::load(&cache_path);
+        cache.record(&tool, Mode::Format, dir.path(), &[file]);
+        cache.save().unwrap();
+
+        let reloaded = Cache::load(&cache_path);
+        assert!(reloaded.is_fresh(&tool, Mode::Format, dir.path(), file));
+    }
+
+    #[test]
+    fn test_cache_path_honors_cache_dir() {
+        let git_dir = Path::new("/repo/.git");
+        assert_eq!(
+            cache_path(None, git_dir, Path::new("/repo")),
+            git_dir.join(CACHE_FILE_NAME)
+        );
+
+        let scratch = Path::new("/scratch");
+        let first = cache_path(Some(scratch), git_dir, Path::new("/repo"));
+        let second = cache_path(Some(scratch), git_dir, Path::new("/other"));
+        assert!(first.starts_with(scratch));
+        assert_ne!(first, second);
+    }
+
+    #[test]
+    fn test_save_creates_cache_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("a.rs"), "fn main() {}").unwrap();
+        let cache_path = dir.path().join("scratch/nested").join(CACHE_FILE_NAME);
+
+        let mut cache = Cache::load(&cache_path);
+        cache.record(
+            &make_tool(&[]),
+            Mode::Format,
+            dir.path(),
+            &[Path::new("a.rs")],
+        );
+        cache.save().unwrap();
+
+        assert_eq!(Cache::load(&cache_path).len(), 1);
+    }
+
+    #[test]
+    fn test_corrupt_cache_starts_empty() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache_path = dir.path().join(CACHE_FILE_NAME);
+        fs::write(&cache_path, "not json").unwrap();
+
+        let cache = Cache::load(&cache_path);
+        assert!(cache.is_empty());
+    }
+}
@@ -1,35 +1,109 @@
 //! Configuration parsing and validation for ffx.
 //!
-//! The config file (.ffx.yaml) defines which tools run on which file patterns.
+//! The config file (.fast-format-x.yaml, or .ffx.yaml) defines which tools run on which file patterns.
 
+use crate::matcher;
 use anyhow::{Context, Result};
+use regex::Regex;
+use serde::de::DeserializeOwned;
 use serde::Deserialize;
+use serde_json::Value;
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
-/// Root configuration structure matching .ffx.yaml schema.
+/// Exit code most formatters use in check mode to report files that need formatting.
+const DEFAULT_ISSUES_EXIT_CODE: i32 = 1;
+
+/// How long fetching a remote `extends` config may take, so an unreachable host
+/// can't hang every run (including the pre-commit hook).
+const REMOTE_CONFIG_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Which set of arguments a run passes to each tool.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Mode {
+    /// Format files in place (`args`)
+    #[default]
+    Format,
+    /// Verify formatting without modifying files (`check_args`)
+    Check,
+    /// Report proposed changes without writing them (`diff_args`)
+    Diff,
+}
+
+/// Oldest config schema version this build reads.
+pub const MIN_VERSION: u32 = 1;
+
+/// Newest config schema version this build reads. Version 2 lets a config declare
+/// that it relies on keys added after version 1 (e.g. `timeout`, `env`, `retries`),
+/// so older builds reject it instead of silently ignoring them.
+pub const MAX_VERSION: u32 = 2;
+
+/// Root configuration structure matching the .fast-format-x.yaml schema.
 #[derive(Debug, Deserialize)]
 pub struct Config {
-    /// Schema version (must be 1)
+    /// Schema version, between `MIN_VERSION` and `MAX_VERSION`
     pub version: u32,
 
     /// List of formatter tools to run
     pub tools: Vec<Tool>,
+
+    /// Skip files unchanged since a tool last succeeded on them (content-hash cache)
+    #[serde(default)]
+    pub cache: bool,
+
+    /// Exclude common build and dependency directories (target, node_modules, .git, vendor)
+    /// from every tool. Defaults to true.
+    #[serde(default = "default_true")]
+    pub default_excludes: bool,
+
+    /// Default `max_file_bytes` for tools that don't set their own
+    #[serde(default)]
+    pub max_file_bytes: Option<u64>,
+}
+
+fn default_true() -> bool {
+    true
 }
 
 /// A formatter tool configuration.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize)]
 pub struct Tool {
     /// Human-readable name for output (e.g., "rubocop", "prettier")
     pub name: String,
 
+    /// What the tool is for (e.g., "sorts Cargo.toml dependencies"), shown by
+    /// `--list` and `ffx doctor`. Purely informational.
+    #[serde(default)]
+    pub description: Option<String>,
+
     /// Glob patterns for files to include (e.g., "**/*.rb")
+    #[serde(default)]
     pub include: Vec<String>,
 
+    /// Skip this tool entirely, as if it weren't configured (e.g. from a local overlay)
+    #[serde(default)]
+    pub disabled: bool,
+
+    /// File extensions to include at any depth (e.g., `rs` for "**/*.rs"), merged with `include`
+    #[serde(default)]
+    pub extensions: Vec<String>,
+
     /// Glob patterns for files to exclude (e.g., "vendor/**")
     #[serde(default)]
     pub exclude: Vec<String>,
 
+    /// File (relative to repo root) with newline-delimited include patterns,
+    /// merged with `include`
+    #[serde(default)]
+    pub include_from: Option<String>,
+
+    /// File (relative to repo root) with newline-delimited exclude patterns,
+    /// merged with `exclude`
+    #[serde(default)]
+    pub exclude_from: Option<String>,
+
     /// Command to run (e.g., "bundle", "npx", "ktlint")
     pub cmd: String,
 
@@ -40,24 +114,178 @@ pub struct Tool {
     /// Arguments to use in check mode (--check flag). Falls back to args if not set.
     #[serde(default)]
     pub check_args: Option<Vec<String>>,
+
+    /// Arguments that print proposed changes without writing them (--diff flag).
+    /// Tools without diff_args are skipped in diff mode.
+    #[serde(default)]
+    pub diff_args: Option<Vec<String>>,
+
+    /// Always treat the command as successful, regardless of its exit code.
+    /// Useful for side-effect scripts (e.g., regenerating an index) that shouldn't gate a commit.
+    #[serde(default)]
+    pub ignore_exit_code: bool,
+
+    /// Report failures (with output and a ✗) without failing the run.
+    /// For informational linters a team is adopting gradually.
+    #[serde(default)]
+    pub allow_failure: bool,
+
+    /// Exit code the tool uses to report "files need formatting" rather than an error.
+    /// Defaults to 1. Used by `--check-level lenient` to tell issues apart from crashes.
+    #[serde(default)]
+    pub issues_exit_code: Option<i32>,
+
+    /// Exit codes that count as success (defaults to `[0]`), e.g. `[0, 1]` for a tool
+    /// that exits 1 after fixing files. In check mode `issues_exit_code` still fails.
+    #[serde(default)]
+    pub success_exit_codes: Option<Vec<i32>>,
+
+    /// Maximum seconds each batch may run before it is killed and marked failed
+    #[serde(default)]
+    pub timeout: Option<u64>,
+
+    /// Times to re-run a failed batch before reporting it (for flaky tools, e.g. cold
+    /// `npx` caches). Any failure is retried except the tool's issues-found exit code.
+    #[serde(default)]
+    pub retries: u32,
+
+    /// Invoke the command once per file instead of with byte-limited batches of files.
+    /// For tools that only accept a single path. Each invocation still runs in parallel,
+    /// but paying process startup per file is much slower than batching on large runs.
+    #[serde(default)]
+    pub per_file: bool,
+
+    /// Run `cmd` as a shell snippet (`sh -c` on Unix, `cmd /C` on Windows). Args and
+    /// file paths are appended, or substituted for `{files}` in `cmd`.
+    #[serde(default)]
+    pub shell: bool,
+
+    /// The tool can read source from stdin and write the formatted result to stdout
+    /// (as `args` invoke it), so `ffx --stdin` can use it for editor integration.
+    #[serde(default)]
+    pub stdin: bool,
+
+    /// Drop files that look binary (a NUL byte near the start) before running the tool.
+    /// For broad globs like `**/*` that could match images or other blobs.
+    #[serde(default)]
+    pub skip_binary: bool,
+
+    /// Skip files larger than this many bytes (e.g. minified bundles or lockfiles).
+    /// Falls back to the top-level `max_file_bytes`.
+    #[serde(default)]
+    pub max_file_bytes: Option<u64>,
+
+    /// Fail an `--all` run when this tool's patterns match no files, to catch
+    /// stale or mistyped globs.
+    #[serde(default)]
+    pub require_match: bool,
+
+    /// Maximum files per invocation, in addition to the byte limit on arguments.
+    /// For memory-bound tools that struggle with very large batches.
+    #[serde(default)]
+    pub max_files_per_batch: Option<usize>,
+
+    /// Maximum batches of this tool running at once, for memory-hungry tools.
+    /// The global `--jobs` pool still bounds overall concurrency.
+    #[serde(default)]
+    pub max_parallel: Option<usize>,
+
+    /// Directory (relative to repo root) to run the command from. File paths are
+    /// passed relative to it; matched files outside it are an error.
+    #[serde(default)]
+    pub working_dir: Option<String>,
+
+    /// Pass file paths as absolute paths (repo root joined with the file) instead of
+    /// relative ones, for tools that resolve paths against a different directory.
+    #[serde(default)]
+    pub absolute_paths: bool,
+
+    /// Directory (relative to repo root) that `include` and `exclude` patterns are
+    /// written relative to, e.g. `frontend` so `**/*.ts` means `frontend/**/*.ts`.
+    #[serde(default)]
+    pub base: Option<String>,
+
+    /// Regex with named captures `file` (required), `line`, and `message`, matched
+    /// against each line of a failing tool's output for `--github-annotations`
+    #[serde(default)]
+    pub annotation_pattern: Option<String>,
+
+    /// Run this tool alone, after all parallel tools finish, with its batches in sequence.
+    /// For tools that share state (e.g. a cache directory) and break when run concurrently.
+    #[serde(default)]
+    pub serial: bool,
+
+    /// Environment variables set for this tool's command, overriding inherited values
+    #[serde(default)]
+    pub env: HashMap<String, String>,
 }
 
 impl Tool {
-    /// Get the arguments to use, based on check mode.
-    /// Returns check_args if check mode is enabled and check_args is set,
-    /// otherwise returns args.
-    pub fn get_args(&self, check_mode: bool) -> &[String] {
-        if check_mode {
-            self.check_args.as_deref().unwrap_or(&self.args)
-        } else {
-            &self.args
+    /// All include patterns: `include` followed by a `**/*.<ext>` glob per extension.
+    pub fn include_patterns(&self) -> Vec<String> {
+        let from_extensions = self
+            .extensions
+            .iter()
+            .map(|ext| format!("**/*.{}", ext.trim_start_matches('.')));
+
+        self.include
+            .iter()
+            .cloned()
+            .chain(from_extensions)
+            .collect()
+    }
+
+    /// Get the arguments to use for `mode`.
+    /// Check mode returns check_args if set, otherwise args.
+    /// Diff mode returns diff_args; callers skip tools without them (see `supports`).
+    pub fn get_args(&self, mode: Mode) -> &[String] {
+        match mode {
+            Mode::Format => &self.args,
+            Mode::Check => self.check_args.as_deref().unwrap_or(&self.args),
+            Mode::Diff => self.diff_args.as_deref().unwrap_or_default(),
         }
     }
+
+    /// Whether the tool can run in `mode` (diff mode requires diff_args).
+    pub fn supports(&self, mode: Mode) -> bool {
+        mode != Mode::Diff || self.diff_args.is_some()
+    }
+
+    /// Get the per-batch timeout, if configured.
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout.map(Duration::from_secs)
+    }
+
+    /// Get the exit code that signals formatting issues (defaults to 1).
+    pub fn issues_exit_code(&self) -> i32 {
+        self.issues_exit_code.unwrap_or(DEFAULT_ISSUES_EXIT_CODE)
+    }
+
+    /// Whether exiting with `code` means success in `mode`: it is one of
+    /// `success_exit_codes`, except that in check mode the `issues_exit_code` always
+    /// fails, so `--check` still catches files that need formatting.
+    /// A `None` code (killed by a signal) is never success.
+    pub fn is_success_code(&self, code: Option<i32>, mode: Mode) -> bool {
+        let Some(code) = code else {
+            return false;
+        };
+        if mode == Mode::Check && code == self.issues_exit_code() {
+            return false;
+        }
+
+        self.success_exit_codes
+            .as_deref()
+            .unwrap_or(&[0])
+            .contains(&code)
+    }
 }
 
 impl Config {
     /// Load and parse config from a YAML file.
-    pub fn load(path: &Path) -> Result<Self> {
+    ///
+    /// Pattern files referenced by `include_from`/`exclude_from` are resolved
+    /// relative to `root` (the repo root).
+    pub fn load(path: &Path, root: &Path) -> Result<Self> {
         if !path.exists() {
             anyhow::bail!(
                 "Config file '{}' not found.\n\nRun 'ffx init' to create one.",
@@ -68,21 +296,114 @@ impl Config {
         let contents = fs::read_to_string(path)
             .with_context(|| format!("Failed to read config file: {}", path.display()))?;
 
-        let config: Config = serde_yaml::from_str(&contents)
-            .with_context(|| format!("Failed to parse config file: {}", path.display()))?;
+        let value: Value = parse(&path.to_string_lossy(), &contents)?;
+        let local = local_overlay_path(path).filter(|local| local.exists());
+        let mut config: Config = if let Some(local) = &local {
+            let source = ConfigSource::File(path.to_path_buf());
+            let merged = resolve_extends(value, &source, &mut vec![source.id()])?;
+            let merged = merge_configs(merged, load_local_overlay(local)?);
+            serde_json::from_value(merged)
+                .with_context(|| format!("Invalid config after applying {}", local.display()))?
+        } else if value.get("extends").is_some() {
+            let source = ConfigSource::File(path.to_path_buf());
+            let merged = resolve_extends(value, &source, &mut vec![source.id()])?;
+            serde_json::from_value(merged)
+                .with_context(|| format!("Invalid config after extends: {}", path.display()))?
+        } else {
+            // Parse directly so errors keep their line numbers
+            parse(&path.to_string_lossy(), &contents)?
+        };
 
+        config.expand_env_vars(|name| std::env::var(name).ok());
+        config.normalize_working_dirs();
+        config.load_pattern_files(root)?;
         config.validate()?;
 
         Ok(config)
     }
 
+    /// Expand `$VAR`/`${VAR}` in each tool's command, args, path fields, and `env`
+    /// values. Args and paths see the tool's own `env` before the process environment.
+    /// A `shell` tool's `cmd` is left for the shell to expand.
+    fn expand_env_vars(&mut self, lookup: impl Fn(&str) -> Option<String>) {
+        for tool in &mut self.tools {
+            for value in tool.env.values_mut() {
+                *value = expand_env(value, &lookup);
+            }
+
+            let env = tool.env.clone();
+            let lookup = |name: &str| env.get(name).cloned().or_else(|| lookup(name));
+
+            if !tool.shell {
+                tool.cmd = expand_env(&tool.cmd, lookup);
+            }
+            let args = tool
+                .args
+                .iter_mut()
+                .chain(tool.check_args.iter_mut().flatten())
+                .chain(tool.diff_args.iter_mut().flatten());
+            let paths = [
+                &mut tool.include_from,
+                &mut tool.exclude_from,
+                &mut tool.working_dir,
+                &mut tool.base,
+            ]
+            .into_iter()
+            .flatten();
+            for value in args.chain(paths) {
+                *value = expand_env(value, lookup);
+            }
+        }
+    }
+
+    /// Strip a leading `./` and trailing `/` from each `working_dir`, as `base` and
+    /// patterns are, so it prefixes the repo-relative paths git reports. `.` is the
+    /// repo root, which is also where tools without a `working_dir` run.
+    fn normalize_working_dirs(&mut self) {
+        for tool in &mut self.tools {
+            tool.working_dir = tool.working_dir.take().and_then(|dir| {
+                let dir = dir.strip_prefix("./").unwrap_or(&dir).trim_end_matches('/');
+                (!dir.is_empty() && dir != ".").then(|| dir.to_string())
+            });
+        }
+    }
+
+    /// Merge patterns from each tool's `include_from`/`exclude_from` files.
+    fn load_pattern_files(&mut self, root: &Path) -> Result<()> {
+        for tool in &mut self.tools {
+            if let Some(file) = &tool.include_from {
+                let patterns = read_pattern_file(&root.join(file))
+                    .with_context(|| format!("Tool '{}' include_from", tool.name))?;
+                tool.include.extend(patterns);
+            }
+            if let Some(file) = &tool.exclude_from {
+                let patterns = read_pattern_file(&root.join(file))
+                    .with_context(|| format!("Tool '{}' exclude_from", tool.name))?;
+                tool.exclude.extend(patterns);
+            }
+        }
+
+        Ok(())
+    }
+
     /// Validate the config after parsing.
     fn validate(&self) -> Result<()> {
         // Check version
-        if self.version != 1 {
+        if self.version > MAX_VERSION {
+            anyhow::bail!(
+                "Unsupported config version: {}. This ffx supports versions {}-{}; \
+                 run `ffx update` to get a release that supports it.",
+                self.version,
+                MIN_VERSION,
+                MAX_VERSION
+            );
+        }
+        if self.version < MIN_VERSION {
             anyhow::bail!(
-                "Unsupported config version: {}. Only version 1 is supported.",
-                self.version
+                "Unsupported config version: {}. Supported versions are {}-{}.",
+                self.version,
+                MIN_VERSION,
+                MAX_VERSION
             );
         }
 
@@ -91,34 +412,611 @@ impl Config {
             anyhow::bail!("Config must define at least one tool");
         }
 
+        // Tool names key status lines and results, so they must be unique
+        let mut seen = HashSet::new();
+        let duplicates: BTreeSet<&str> = self
+            .tools
+            .iter()
+            .map(|tool| tool.name.as_str())
+            .filter(|name| !seen.insert(*name))
+            .collect();
+        if !duplicates.is_empty() {
+            anyhow::bail!(
+                "Duplicate tool names: {}",
+                duplicates.into_iter().collect::<Vec<_>>().join(", ")
+            );
+        }
+
+        if self.max_file_bytes == Some(0) {
+            anyhow::bail!("max_file_bytes must be greater than 0");
+        }
+
         // Validate each tool
         for tool in &self.tools {
             if tool.name.is_empty() {
                 anyhow::bail!("Tool name cannot be empty");
             }
-            if tool.include.is_empty() {
+            if tool.include.is_empty() && tool.extensions.is_empty() {
                 anyhow::bail!(
-                    "Tool '{}' must have at least one include pattern",
+                    "Tool '{}' must have at least one include pattern or extension",
                     tool.name
                 );
             }
+            if let Some(ext) = tool
+                .extensions
+                .iter()
+                .find(|ext| ext.trim_start_matches('.').is_empty() || ext.contains('/'))
+            {
+                anyhow::bail!(
+                    "Tool '{}' has an invalid extension '{}' (use e.g. `rs` or `.rs`)",
+                    tool.name,
+                    ext
+                );
+            }
             if tool.cmd.is_empty() {
                 anyhow::bail!("Tool '{}' must have a cmd", tool.name);
             }
+            if !tool.shell && looks_like_command_line(&tool.cmd) {
+                let mut words = tool.cmd.split_whitespace();
+                let program = words.next().unwrap_or_default();
+                let args: Vec<&str> = words.collect();
+                anyhow::bail!(
+                    "Tool '{}' cmd '{}' looks like a command line, but cmd is run as a single \
+                     executable. Move flags into args (cmd: {}, args: [{}]) or set `shell: true`",
+                    tool.name,
+                    tool.cmd,
+                    program,
+                    args.join(", ")
+                );
+            }
+            // Compile globs now so a typo fails at load, not after files are collected
+            matcher::build_globset(&tool.include_patterns())
+                .with_context(|| format!("Tool '{}' has an invalid include pattern", tool.name))?;
+            matcher::build_globset(&tool.exclude)
+                .with_context(|| format!("Tool '{}' has an invalid exclude pattern", tool.name))?;
+            if tool.timeout == Some(0) {
+                anyhow::bail!("Tool '{}' timeout must be greater than 0", tool.name);
+            }
+
+            if tool.success_exit_codes.as_ref().is_some_and(Vec::is_empty) {
+                anyhow::bail!(
+                    "Tool '{}' success_exit_codes must list at least one exit code",
+                    tool.name
+                );
+            }
+            if tool.max_file_bytes == Some(0) {
+                anyhow::bail!("Tool '{}' max_file_bytes must be greater than 0", tool.name);
+            }
+            if tool.max_parallel == Some(0) {
+                anyhow::bail!("Tool '{}' max_parallel must be greater than 0", tool.name);
+            }
+            if tool.max_files_per_batch == Some(0) {
+                anyhow::bail!(
+                    "Tool '{}' max_files_per_batch must be greater than 0",
+                    tool.name
+                );
+            }
+            if let Some(pattern) = &tool.annotation_pattern {
+                let regex = Regex::new(pattern).with_context(|| {
+                    format!("Tool '{}' has an invalid annotation_pattern", tool.name)
+                })?;
+                if !regex.capture_names().any(|name| name == Some("file")) {
+                    anyhow::bail!(
+                        "Tool '{}' annotation_pattern must have a named `file` capture",
+                        tool.name
+                    );
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Whether `cmd` contains shell metacharacters, or spaces outside of a path like
+/// `/opt/My Tools/fmt`, suggesting it was meant to be split into cmd and args.
+fn looks_like_command_line(cmd: &str) -> bool {
+    const METACHARACTERS: &[char] = &[
+        '|', '&', ';', '<', '>', '(', ')', '$', '`', '"', '\'', '*', '?',
+    ];
+
+    let is_path = cmd.contains('/') || cmd.contains('\\');
+    cmd.contains(METACHARACTERS) || (!is_path && cmd.contains(char::is_whitespace))
+}
+
+/// Parse config contents as TOML when `name` ends in `.toml`, YAML otherwise.
+fn parse<T: DeserializeOwned>(name: &str, contents: &str) -> Result<T> {
+    if name.ends_with(".toml") {
+        toml::from_str(contents)
+            .with_context(|| format!("Failed to parse TOML config file: {}", name))
+    } else {
+        serde_yaml::from_str(contents)
+            .with_context(|| format!("Failed to parse YAML config file: {}", name))
+    }
+}
+
+/// Where a config in an `extends` chain came from.
+enum ConfigSource {
+    File(PathBuf),
+    Url(String),
+}
+
+impl ConfigSource {
+    /// Resolve an `extends` reference: URLs as-is, paths relative to this config's directory.
+    ///
+    /// Only `https://` URLs are accepted: a config names commands ffx runs, so fetching
+    /// one over plain HTTP would let anyone on the network path run code.
+    fn resolve(&self, reference: &str) -> Result<Self> {
+        if reference.starts_with("https://") {
+            return Ok(Self::Url(reference.to_string()));
+        }
+        if reference.starts_with("http://") {
+            anyhow::bail!(
+                "Config '{}' extends '{}' over plain HTTP; use an https:// URL",
+                self.name(),
+                reference
+            );
+        }
+
+        match self {
+            Self::File(path) => {
+                let dir = path.parent().unwrap_or(Path::new("."));
+                Ok(Self::File(dir.join(reference)))
+            }
+            Self::Url(url) => anyhow::bail!(
+                "Config '{}' extends '{}', but remote configs can only extend URLs",
+                url,
+                reference
+            ),
+        }
+    }
+
+    /// Identity used to detect cycles.
+    fn id(&self) -> String {
+        match self {
+            Self::File(path) => fs::canonicalize(path)
+                .unwrap_or_else(|_| path.clone())
+                .display()
+                .to_string(),
+            Self::Url(url) => url.clone(),
+        }
+    }
+
+    fn read(&self) -> Result<String> {
+        match self {
+            Self::File(path) => fs::read_to_string(path)
+                .with_context(|| format!("Failed to read extended config: {}", path.display())),
+            Self::Url(url) => ureq::get(url)
+                .config()
+                .https_only(true)
+                .timeout_global(Some(REMOTE_CONFIG_TIMEOUT))
+                .build()
+                .header("User-Agent", "ffx")
+                .call()
+                .with_context(|| format!("Failed to fetch extended config: {}", url))?
+                .body_mut()
+                .read_to_string()
+                .with_context(|| format!("Failed to read extended config: {}", url)),
+        }
+    }
+
+    fn name(&self) -> String {
+        match self {
+            Self::File(path) => path.display().to_string(),
+            Self::Url(url) => url.clone(),
+        }
+    }
+}
+
+/// Load the parent named by `value`'s `extends` key (recursively) and merge `value` onto it.
+///
+/// `chain` holds the ids of configs already being loaded, so cycles are reported.
+fn resolve_extends(
+    mut value: Value,
+    source: &ConfigSource,
+    chain: &mut Vec<String>,
+) -> Result<Value> {
+    let Some(reference) = value.as_object_mut().and_then(|map| map.remove("extends")) else {
+        return Ok(value);
+    };
+    let reference = reference
+        .as_str()
+        .with_context(|| format!("'extends' in {} must be a string", source.name()))?;
+
+    let parent_source = source.resolve(reference)?;
+    let parent_id = parent_source.id();
+    if chain.contains(&parent_id) {
+        chain.push(parent_id);
+        anyhow::bail!("Cyclic extends: {}", chain.join(" -> "));
+    }
+    chain.push(parent_id);
+
+    let parent: Value = parse(&parent_source.name(), &parent_source.read()?)?;
+    let parent = resolve_extends(parent, &parent_source, chain)?;
+
+    Ok(merge_configs(parent, value))
+}
+
+/// Personal overrides next to the config: `.fast-format-x.yaml` has
+/// `.fast-format-x.local.yaml`, meant to be gitignored.
+pub fn local_overlay_path(path: &Path) -> Option<PathBuf> {
+    let stem = path.file_stem()?.to_str()?;
+    let extension = path.extension()?.to_str()?;
+
+    Some(path.with_file_name(format!("{stem}.local.{extension}")))
+}
+
+/// Read a local overlay, which is merged like a child config but can't use `extends`.
+fn load_local_overlay(path: &Path) -> Result<Value> {
+    let contents = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read local config: {}", path.display()))?;
+    let overlay: Value = parse(&path.to_string_lossy(), &contents)?;
+    if overlay.get("extends").is_some() {
+        anyhow::bail!(
+            "Local config {} can't use 'extends'; set it in the shared config",
+            path.display()
+        );
+    }
+
+    Ok(overlay)
+}
+
+/// Overlay `child` onto `parent`: top-level keys replace the parent's, and tools are
+/// merged by name (child fields override the parent tool's; new tools are appended).
+fn merge_configs(parent: Value, child: Value) -> Value {
+    let (Value::Object(mut merged), Value::Object(child)) = (parent, child) else {
+        return Value::Null;
+    };
+
+    for (key, child_value) in child {
+        if key != "tools" {
+            merged.insert(key, child_value);
+            continue;
         }
 
-        Ok(())
+        let mut tools = match merged.remove("tools") {
+            Some(Value::Array(tools)) => tools,
+            _ => Vec::new(),
+        };
+        for child_tool in child_value.as_array().cloned().unwrap_or_default() {
+            let existing = tools.iter_mut().find(|tool| {
+                tool.get("name").is_some() && tool.get("name") == child_tool.get("name")
+            });
+            match (existing, child_tool) {
+                (Some(Value::Object(tool)), Value::Object(overrides)) => tool.extend(overrides),
+                (_, child_tool) => tools.push(child_tool),
+            }
+        }
+        merged.insert(key, Value::Array(tools));
+    }
+
+    Value::Object(merged)
+}
+
+/// Expand `$VAR` and `${VAR}` in `value` using `lookup`. Unknown variables, and a `$`
+/// not followed by a variable name (`$1`, `$@`, `$(...)`), are left as written.
+fn expand_env(value: &str, lookup: impl Fn(&str) -> Option<String>) -> String {
+    let is_name_char = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    let mut expanded = String::with_capacity(value.len());
+    let mut rest = value;
+
+    while let Some(pos) = rest.find('$') {
+        expanded.push_str(&rest[..pos]);
+        let after = &rest[pos + 1..];
+        let (name, len) = match after.strip_prefix('{') {
+            Some(braced) => braced
+                .find('}')
+                .map_or(("", 0), |end| (&braced[..end], end + 2)),
+            None => {
+                let end = after.find(|c| !is_name_char(c)).unwrap_or(after.len());
+                (&after[..end], end)
+            }
+        };
+
+        let valid = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+            && name.chars().all(is_name_char);
+        match valid.then(|| lookup(name)).flatten() {
+            Some(replacement) => {
+                expanded.push_str(&replacement);
+                rest = &after[len..];
+            }
+            None => {
+                expanded.push('$');
+                rest = after;
+            }
+        }
+    }
+
+    expanded.push_str(rest);
+    expanded
+}
+
+/// Read newline-delimited glob patterns, skipping blank lines and `#` comments.
+fn read_pattern_file(path: &Path) -> Result<Vec<String>> {
+    let contents = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read pattern file: {}", path.display()))?;
+
+    Ok(contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(String::from)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_and_validate(yaml: &str) -> Result<Config> {
+        let config: Config = serde_yaml::from_str(yaml)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    #[test]
+    fn test_expand_env_replaces_known_variables_only() {
+        let lookup = |name: &str| match name {
+            "HOME" => Some("/home/dev".to_string()),
+            "CI" => Some("true".to_string()),
+            _ => None,
+        };
+
+        assert_eq!(
+            expand_env("$HOME/.prettierrc", lookup),
+            "/home/dev/.prettierrc"
+        );
+        assert_eq!(expand_env("--ci=${CI}!", lookup), "--ci=true!");
+        assert_eq!(
+            expand_env("$MISSING ${MISSING}", lookup),
+            "$MISSING ${MISSING}"
+        );
+        assert_eq!(
+            expand_env("for f; do echo \"$1\" \"$@\" $(pwd) ${1} $", lookup),
+            "for f; do echo \"$1\" \"$@\" $(pwd) ${1} $"
+        );
+        assert_eq!(expand_env("${HOME", lookup), "${HOME");
+    }
+
+    #[test]
+    fn test_expand_env_vars_covers_tool_fields() {
+        let yaml = r#"
+version: 1
+tools:
+  - name: prettier
+    include: ["**/*.js"]
+    cmd: $BIN/prettier
+    args: ["--config", "$HOME/.prettierrc", "--mode=$MODE"]
+    check_args: ["--check", "${HOME}"]
+    working_dir: ${APP}
+    env:
+      MODE: strict
+      CACHE: $HOME/.cache
+  - name: script
+    include: ["**/*.sh"]
+    cmd: echo $HOME
+    shell: true
+"#;
+        let mut config: Config = serde_yaml::from_str(yaml).unwrap();
+        config.expand_env_vars(|name| match name {
+            "HOME" => Some("/home/dev".to_string()),
+            "BIN" => Some("/opt/bin".to_string()),
+            "APP" => Some("frontend".to_string()),
+            "MODE" => Some("from-process".to_string()),
+            _ => None,
+        });
+
+        let tool = &config.tools[0];
+        assert_eq!(tool.cmd, "/opt/bin/prettier");
+        assert_eq!(
+            tool.args,
+            ["--config", "/home/dev/.prettierrc", "--mode=strict"]
+        );
+        assert_eq!(tool.check_args.as_ref().unwrap(), &["--check", "/home/dev"]);
+        assert_eq!(tool.working_dir.as_deref(), Some("frontend"));
+        assert_eq!(tool.env["CACHE"], "/home/dev/.cache");
+        assert_eq!(config.tools[1].cmd, "echo $HOME");
+    }
+
+    #[test]
+    fn test_working_dir_is_normalized_on_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(".fast-format-x.yaml");
+        fs::write(
+            &path,
+            r#"
+version: 1
+tools:
+  - name: prettier
+    include: ["**/*.ts"]
+    cmd: prettier
+    working_dir: ./frontend/
+  - name: root
+    include: ["**/*.md"]
+    cmd: mdformat
+    working_dir: .
+"#,
+        )
+        .unwrap();
+
+        let config = Config::load(&path, dir.path()).unwrap();
+
+        assert_eq!(config.tools[0].working_dir.as_deref(), Some("frontend"));
+        assert_eq!(config.tools[1].working_dir, None);
+    }
+
+    #[test]
+    fn test_load_toml_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ffx.toml");
+        fs::write(
+            &path,
+            r#"
+version = 1
+
+[[tools]]
+name = "rustfmt"
+include = ["**/*.rs"]
+cmd = "rustfmt"
+check_args = ["--check"]
+"#,
+        )
+        .unwrap();
+
+        let config = Config::load(&path, dir.path()).unwrap();
+
+        assert_eq!(config.tools[0].name, "rustfmt");
+        assert_eq!(config.tools[0].get_args(Mode::Check), vec!["--check"]);
+    }
+
+    #[test]
+    fn test_parse_error_names_format() {
+        let dir = tempfile::tempdir().unwrap();
+        let toml_path = dir.path().join("ffx.toml");
+        fs::write(&toml_path, "version = [").unwrap();
+        let yaml_path = dir.path().join("ffx.yml");
+        fs::write(&yaml_path, "version: [").unwrap();
+
+        let err = Config::load(&toml_path, dir.path()).unwrap_err();
+        assert!(err.to_string().contains("Failed to parse TOML"));
+
+        let err = Config::load(&yaml_path, dir.path()).unwrap_err();
+        assert!(err.to_string().contains("Failed to parse YAML"));
+    }
+
+    #[test]
+    fn test_extends_merges_tools_by_name() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("shared")).unwrap();
+        fs::write(
+            dir.path().join("shared/base.yaml"),
+            r#"
+version: 1
+cache: true
+tools:
+  - name: prettier
+    include: ["**/*.md"]
+    cmd: npx
+    args: [prettier, --write]
+  - name: gofmt
+    include: ["**/*.go"]
+    cmd: gofmt
+"#,
+        )
+        .unwrap();
+        let path = dir.path().join(".fast-format-x.yaml");
+        fs::write(
+            &path,
+            r#"
+extends: shared/base.yaml
+tools:
+  - name: prettier
+    args: [prettier, --write, --no-semi]
+  - name: rustfmt
+    include: ["**/*.rs"]
+    cmd: rustfmt
+"#,
+        )
+        .unwrap();
+
+        let config = Config::load(&path, dir.path()).unwrap();
+
+        assert!(config.cache);
+        let names: Vec<&str> = config.tools.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, vec!["prettier", "gofmt", "rustfmt"]);
+        assert_eq!(config.tools[0].cmd, "npx");
+        assert_eq!(config.tools[0].include, vec!["**/*.md"]);
+        assert_eq!(
+            config.tools[0].args,
+            vec!["prettier", "--write", "--no-semi"]
+        );
+    }
+
+    #[test]
+    fn test_local_overlay_overrides_shared_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(".fast-format-x.yaml");
+        fs::write(
+            &path,
+            r#"
+version: 1
+tools:
+  - name: prettier
+    include: ["**/*.md"]
+    cmd: npx
+    args: [prettier, --write]
+  - name: eslint
+    include: ["**/*.js"]
+    cmd: npx
+    args: [eslint, --fix]
+"#,
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join(".fast-format-x.local.yaml"),
+            r#"
+cache: true
+tools:
+  - name: eslint
+    disabled: true
+  - name: prettier
+    args: [prettier, --write, --no-semi]
+"#,
+        )
+        .unwrap();
+
+        let config = Config::load(&path, dir.path()).unwrap();
+
+        assert!(config.cache);
+        assert_eq!(config.tools.len(), 2);
+        assert_eq!(config.tools[0].args, ["prettier", "--write", "--no-semi"]);
+        assert_eq!(config.tools[0].include, ["**/*.md"]);
+        assert!(config.tools[1].disabled);
+        assert!(!config.tools[0].disabled);
+    }
+
+    #[test]
+    fn test_local_overlay_cannot_extend() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(".ffx.yml");
+        fs::write(
+            &path,
+            "version: 1\ntools:\n  - name: gofmt\n    include: [\"**/*.go\"]\n    cmd: gofmt\n",
+        )
+        .unwrap();
+        fs::write(dir.path().join(".ffx.local.yml"), "extends: other.yaml\n").unwrap();
+
+        let err = Config::load(&path, dir.path()).unwrap_err();
+
+        assert!(
+            format!("{err:#}").contains("can't use 'extends'"),
+            "{err:#}"
+        );
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[test]
+    fn test_extends_rejects_plain_http() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(".fast-format-x.yaml");
+        fs::write(&path, "extends: http://example.com/ffx.yaml\nversion: 1\n").unwrap();
 
-    fn parse_and_validate(yaml: &str) -> Result<Config> {
-        let config: Config = serde_yaml::from_str(yaml)?;
-        config.validate()?;
-        Ok(config)
+        let err = Config::load(&path, dir.path()).unwrap_err();
+
+        assert!(
+            format!("{err:#}").contains("over plain HTTP; use an https:// URL"),
+            "{err:#}"
+        );
+    }
+
+    #[test]
+    fn test_cyclic_extends_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("a.yaml"), "extends: b.yaml\nversion: 1\n").unwrap();
+        fs::write(dir.path().join("b.yaml"), "extends: a.yaml\nversion: 1\n").unwrap();
+
+        let err = Config::load(&dir.path().join("a.yaml"), dir.path()).unwrap_err();
+
+        assert!(format!("{err:#}").contains("Cyclic extends"), "{err:#}");
     }
 
     #[test]
@@ -178,7 +1076,7 @@ tools:
         let tool = &config.tools[0];
 
         // Normal mode should use args
-        assert_eq!(tool.get_args(false), vec!["prettier", "--write"]);
+        assert_eq!(tool.get_args(Mode::Format), vec!["prettier", "--write"]);
     }
 
     #[test]
@@ -197,7 +1095,7 @@ tools:
         let tool = &config.tools[0];
 
         // Check mode should use check_args
-        assert_eq!(tool.get_args(true), vec!["prettier", "--check"]);
+        assert_eq!(tool.get_args(Mode::Check), vec!["prettier", "--check"]);
     }
 
     #[test]
@@ -215,7 +1113,31 @@ tools:
         let tool = &config.tools[0];
 
         // Check mode without check_args should fall back to args
-        assert_eq!(tool.get_args(true), vec!["prettier", "--write"]);
+        assert_eq!(tool.get_args(Mode::Check), vec!["prettier", "--write"]);
+    }
+
+    #[test]
+    fn test_get_args_diff_mode() {
+        let yaml = r#"
+version: 1
+
+tools:
+  - name: rustfmt
+    include: ["**/*.rs"]
+    cmd: rustfmt
+    args: [--edition, "2021"]
+    diff_args: [--check]
+  - name: prettier
+    include: ["**/*.md"]
+    cmd: npx
+    args: [prettier, --write]
+"#;
+        let config = parse_and_validate(yaml).unwrap();
+
+        assert_eq!(config.tools[0].get_args(Mode::Diff), vec!["--check"]);
+        assert!(config.tools[0].supports(Mode::Diff));
+        assert!(!config.tools[1].supports(Mode::Diff));
+        assert!(config.tools[1].supports(Mode::Format));
     }
 
     #[test]
@@ -244,10 +1166,300 @@ tools:
         assert!(config.tools[0].args.is_empty());
     }
 
+    #[test]
+    fn test_success_exit_codes() {
+        let yaml = r#"
+version: 1
+tools:
+  - name: plain
+    include: ["**/*.rb"]
+    cmd: plain
+  - name: fixer
+    include: ["**/*.py"]
+    cmd: fixer
+    success_exit_codes: [0, 1, 3]
+"#;
+        let config = parse_and_validate(yaml).unwrap();
+        let (plain, fixer) = (&config.tools[0], &config.tools[1]);
+
+        assert!(plain.is_success_code(Some(0), Mode::Format));
+        assert!(!plain.is_success_code(Some(1), Mode::Format));
+        assert!(!plain.is_success_code(None, Mode::Format));
+
+        assert!(fixer.is_success_code(Some(1), Mode::Format));
+        assert!(fixer.is_success_code(Some(3), Mode::Check));
+        assert!(!fixer.is_success_code(Some(2), Mode::Format));
+        // The issues exit code still fails check mode
+        assert!(!fixer.is_success_code(Some(1), Mode::Check));
+
+        let empty = yaml.replace("[0, 1, 3]", "[]");
+        let err = parse_and_validate(&empty).unwrap_err();
+        assert!(err.to_string().contains("at least one exit code"));
+    }
+
+    #[test]
+    fn test_ignore_exit_code_defaults_to_false() {
+        let yaml = r#"
+version: 1
+tools:
+  - name: test
+    include: ["**/*.rs"]
+    cmd: echo
+"#;
+        let config = parse_and_validate(yaml).unwrap();
+        assert!(!config.tools[0].ignore_exit_code);
+    }
+
+    #[test]
+    fn test_issues_exit_code_defaults_to_one() {
+        let yaml = r#"
+version: 1
+tools:
+  - name: test
+    include: ["**/*.rs"]
+    cmd: echo
+  - name: custom
+    include: ["**/*.md"]
+    cmd: echo
+    issues_exit_code: 4
+"#;
+        let config = parse_and_validate(yaml).unwrap();
+        assert_eq!(config.tools[0].issues_exit_code(), 1);
+        assert_eq!(config.tools[1].issues_exit_code(), 4);
+    }
+
+    #[test]
+    fn test_parse_timeout() {
+        let yaml = r#"
+version: 1
+tools:
+  - name: prettier
+    include: ["**/*.md"]
+    cmd: npx
+    timeout: 30
+"#;
+        let config = parse_and_validate(yaml).unwrap();
+        assert_eq!(config.tools[0].timeout(), Some(Duration::from_secs(30)));
+    }
+
+    #[test]
+    fn test_zero_timeout_rejected() {
+        let yaml = r#"
+version: 1
+tools:
+  - name: prettier
+    include: ["**/*.md"]
+    cmd: npx
+    timeout: 0
+"#;
+        let result = parse_and_validate(yaml);
+        assert!(result.unwrap_err().to_string().contains("timeout"));
+    }
+
+    #[test]
+    fn test_zero_max_parallel_rejected() {
+        let yaml = r#"
+version: 1
+tools:
+  - name: tsc
+    include: ["**/*.ts"]
+    cmd: tsc
+    max_parallel: 0
+"#;
+        let result = parse_and_validate(yaml);
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("Tool 'tsc' max_parallel must be greater than 0"));
+
+        let config = parse_and_validate(&yaml.replace("max_parallel: 0", "max_parallel: 2"));
+        assert_eq!(config.unwrap().tools[0].max_parallel, Some(2));
+    }
+
+    #[test]
+    fn test_zero_max_files_per_batch_rejected() {
+        let yaml = r#"
+version: 1
+tools:
+  - name: prettier
+    include: ["**/*.md"]
+    cmd: npx
+    max_files_per_batch: 0
+"#;
+        let result = parse_and_validate(yaml);
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("max_files_per_batch"));
+    }
+
+    #[test]
+    fn test_parse_env() {
+        let yaml = r#"
+version: 1
+tools:
+  - name: rustfmt
+    include: ["**/*.rs"]
+    cmd: rustfmt
+    env:
+      RUSTFMT_EDITION: "2021"
+"#;
+        let config = parse_and_validate(yaml).unwrap();
+        assert_eq!(config.tools[0].env["RUSTFMT_EDITION"], "2021");
+    }
+
+    #[test]
+    fn test_extensions_satisfy_include_requirement() {
+        let yaml = r#"
+version: 1
+tools:
+  - name: rustfmt
+    extensions: [rs, .rlib]
+    cmd: rustfmt
+"#;
+        let config = parse_and_validate(yaml).unwrap();
+        assert_eq!(
+            config.tools[0].include_patterns(),
+            vec!["**/*.rs", "**/*.rlib"]
+        );
+
+        let err = parse_and_validate(&yaml.replace("[rs, .rlib]", "[]")).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("must have at least one include pattern or extension"));
+
+        let err = parse_and_validate(&yaml.replace("[rs, .rlib]", "[src/rs]")).unwrap_err();
+        assert!(err.to_string().contains("invalid extension 'src/rs'"));
+    }
+
+    #[test]
+    fn test_cmd_with_flags_is_rejected() {
+        let yaml = r#"
+version: 1
+tools:
+  - name: prettier
+    include: ["**/*.js"]
+    cmd: prettier --write
+"#;
+        let err = parse_and_validate(yaml).unwrap_err().to_string();
+        assert!(err.contains("looks like a command line"), "{err}");
+        assert!(err.contains("cmd: prettier, args: [--write]"), "{err}");
+
+        // Shell snippets and paths with spaces are fine
+        assert!(parse_and_validate(&format!("{yaml}    shell: true\n")).is_ok());
+        assert!(parse_and_validate(&yaml.replace("prettier --write", "/opt/My Tools/fmt")).is_ok());
+        assert!(parse_and_validate(&yaml.replace("prettier --write", "fmt && lint")).is_err());
+    }
+
+    #[test]
+    fn test_invalid_glob_fails_validation() {
+        let yaml = r#"
+version: 1
+tools:
+  - name: rustfmt
+    include: ["**/*.rs"]
+    exclude: ["src/[gen/**"]
+    cmd: rustfmt
+"#;
+        let err = parse_and_validate(yaml).unwrap_err();
+        let message = format!("{err:#}");
+        assert!(message.contains("Tool 'rustfmt' has an invalid exclude pattern"));
+        assert!(message.contains("src/[gen/**"));
+    }
+
+    #[test]
+    fn test_annotation_pattern_requires_file_capture() {
+        let yaml = r#"
+version: 1
+tools:
+  - name: lint
+    include: ["**/*.rs"]
+    cmd: lint
+    annotation_pattern: '^(?P<path>\S+): (?P<message>.*)$'
+"#;
+        let err = parse_and_validate(yaml).unwrap_err();
+        assert!(err.to_string().contains("named `file` capture"));
+
+        let invalid = yaml.replace("(?P<path>", "(?P<file>(");
+        let err = parse_and_validate(&invalid).unwrap_err();
+        assert!(err.to_string().contains("invalid annotation_pattern"));
+    }
+
+    #[test]
+    fn test_cache_defaults_to_disabled() {
+        let yaml = r#"
+version: 1
+tools:
+  - name: test
+    include: ["**/*.rs"]
+    cmd: echo
+"#;
+        let config = parse_and_validate(yaml).unwrap();
+        assert!(!config.cache);
+
+        let config = parse_and_validate(&format!("cache: true\n{yaml}")).unwrap();
+        assert!(config.cache);
+    }
+
+    #[test]
+    fn test_max_file_bytes_must_be_positive() {
+        let yaml = r#"
+version: 1
+tools:
+  - name: prettier
+    include: ["**/*.js"]
+    cmd: prettier
+    max_file_bytes: 0
+"#;
+        let err = parse_and_validate(yaml).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Tool 'prettier' max_file_bytes must be greater than 0"));
+
+        let yaml = yaml.replace("max_file_bytes: 0", "max_file_bytes: 1000000");
+        let config = parse_and_validate(&format!("max_file_bytes: 0\n{yaml}"));
+        assert!(config.is_err());
+    }
+
+    #[test]
+    fn test_default_excludes_defaults_to_true() {
+        let yaml = r#"
+version: 1
+tools:
+  - name: test
+    include: ["**/*.rs"]
+    cmd: echo
+"#;
+        let config = parse_and_validate(yaml).unwrap();
+        assert!(config.default_excludes);
+
+        let config = parse_and_validate(&format!("default_excludes: false\n{yaml}")).unwrap();
+        assert!(!config.default_excludes);
+    }
+
+    #[test]
+    fn test_duplicate_tool_names_rejected() {
+        let yaml = r#"
+version: 1
+tools:
+  - name: prettier
+    include: ["**/*.md"]
+    cmd: npx
+  - name: rustfmt
+    include: ["**/*.rs"]
+    cmd: rustfmt
+  - name: prettier
+    include: ["**/*.ts"]
+    cmd: npx
+"#;
+        let err = parse_and_validate(yaml).unwrap_err();
+        assert_eq!(err.to_string(), "Duplicate tool names: prettier");
+    }
+
     #[test]
     fn test_invalid_version() {
         let yaml = r#"
-version: 2
+version: 0
 tools:
   - name: test
     include: ["**/*.rs"]
@@ -256,8 +1468,31 @@ tools:
         let result = parse_and_validate(yaml);
         assert!(result.is_err());
         let err = result.unwrap_err().to_string();
-        assert!(err.contains("Unsupported config version"));
-        assert!(err.contains("2"));
+        assert!(err.contains("Unsupported config version: 0"));
+    }
+
+    #[test]
+    fn test_supported_versions_accepted() {
+        for version in MIN_VERSION..=MAX_VERSION {
+            let yaml = format!(
+                "version: {version}\ntools:\n  - name: test\n    include: [\"**/*.rs\"]\n    cmd: echo\n"
+            );
+            assert!(parse_and_validate(&yaml).is_ok(), "version {version}");
+        }
+    }
+
+    #[test]
+    fn test_newer_version_suggests_update() {
+        let yaml = r#"
+version: 3
+tools:
+  - name: test
+    include: ["**/*.rs"]
+    cmd: echo
+"#;
+        let err = parse_and_validate(yaml).unwrap_err().to_string();
+        assert!(err.contains("Unsupported config version: 3"));
+        assert!(err.contains("ffx update"));
     }
 
     #[test]
@@ -343,4 +1578,80 @@ tools:
         assert_eq!(config.tools[1].name, "prettier");
         assert_eq!(config.tools[1].exclude, vec!["node_modules/**"]);
     }
+
+    #[test]
+    fn test_include_from_and_exclude_from_merge_patterns() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join("prettier-include"),
+            "# shared with JS tooling\n**/*.js\n\n**/*.ts\n",
+        )
+        .unwrap();
+        fs::write(dir.path().join("prettier-exclude"), "dist/**\n").unwrap();
+
+        let config_path = dir.path().join(".fast-format-x.yaml");
+        fs::write(
+            &config_path,
+            r#"
+version: 1
+tools:
+  - name: prettier
+    include: ["**/*.md"]
+    include_from: prettier-include
+    exclude_from: prettier-exclude
+    cmd: npx
+"#,
+        )
+        .unwrap();
+
+        let config = Config::load(&config_path, dir.path()).unwrap();
+        assert_eq!(
+            config.tools[0].include,
+            vec!["**/*.md", "**/*.js", "**/*.ts"]
+        );
+        assert_eq!(config.tools[0].exclude, vec!["dist/**"]);
+    }
+
+    #[test]
+    fn test_include_from_alone_satisfies_include_requirement() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("patterns"), "**/*.rs\n").unwrap();
+
+        let config_path = dir.path().join(".fast-format-x.yaml");
+        fs::write(
+            &config_path,
+            r#"
+version: 1
+tools:
+  - name: rust
+    include_from: patterns
+    cmd: echo
+"#,
+        )
+        .unwrap();
+
+        let config = Config::load(&config_path, dir.path()).unwrap();
+        assert_eq!(config.tools[0].include, vec!["**/*.rs"]);
+    }
+
+    #[test]
+    fn test_missing_pattern_file_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_path = dir.path().join(".fast-format-x.yaml");
+        fs::write(
+            &config_path,
+            r#"
+version: 1
+tools:
+  - name: rust
+    include: ["**/*.rs"]
+    exclude_from: missing-file
+    cmd: echo
+"#,
+        )
+        .unwrap();
+
+        let err = Config::load(&config_path, dir.path()).unwrap_err();
+        assert!(format!("{err:#}").contains("missing-file"));
+    }
 }
@@ -2,21 +2,96 @@
 //!
 //! Runs formatter commands with batched file arguments in parallel.
 
-use crate::config::Tool;
+use crate::config::{Mode, Tool};
 use anyhow::{Context, Result};
 use rayon::prelude::*;
 use std::ffi::OsStr;
-use std::io::ErrorKind;
-use std::path::Path;
-use std::process::{Command, Output};
-
-/// Maximum bytes per command invocation to avoid ARG_MAX limits.
+use std::io::{self, BufRead, BufReader, ErrorKind, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, ExitStatus, Output, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Condvar, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// Default maximum bytes per command invocation to avoid ARG_MAX limits.
 /// 128KB is safe for most systems (macOS ARG_MAX is 256KB, Linux is 2MB+).
-/// This leaves headroom for environment variables.
-const MAX_BATCH_BYTES: usize = 128 * 1024;
+/// This leaves headroom for environment variables. Override with `--max-batch-bytes`.
+pub const MAX_BATCH_BYTES: usize = 128 * 1024;
+
+/// Arg that is replaced by the batch's file paths. Without it, files are appended.
+const FILES_PLACEHOLDER: &str = "{files}";
+
+/// Lowercase stderr phrases suggesting a tool only accepts one file per invocation.
+const SINGLE_FILE_ERRORS: &[&str] = &[
+    "too many arguments",
+    "expected 1 argument",
+    "expected one argument",
+    "accepts only one file",
+    "only one file",
+];
+
+/// How often to poll a running child for exit while a timeout or fail-fast abort is pending.
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// How formatter work is scheduled on the thread pool.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum Strategy {
+    /// Run tools in parallel, and each tool's batches in parallel within it
+    /// (nested parallelism that can over- or under-subscribe the pool)
+    ByTool,
+    /// Flatten every tool's batches into a single parallel work queue, so the
+    /// thread budget is shared evenly no matter how batches are spread across tools
+    #[default]
+    ByBatch,
+    /// Run tools one at a time in config order (batches within a tool still run in
+    /// parallel), so each tool sees the previous tools' writes. Set by `--sequential`.
+    #[value(skip)]
+    Sequential,
+}
+
+/// Options shared by every formatter invocation in a run.
+#[derive(Debug, Clone, Copy)]
+pub struct RunOptions<'a> {
+    /// Capture command strings for logging
+    pub verbose: bool,
+    /// Echo each tool's stderr live as it arrives. Only for terminals: in logs,
+    /// lines from parallel tools would interleave, so output is printed per tool afterwards.
+    pub stream_stderr: bool,
+    /// Which args to pass (args, check_args, or diff_args)
+    pub mode: Mode,
+    /// Working directory for the formatter commands
+    pub work_dir: &'a Path,
+    /// Maximum arg bytes per invocation (see `MAX_BATCH_BYTES`)
+    pub max_batch_bytes: usize,
+    /// Extra arguments passed to every tool, after its args and before the files
+    pub extra_args: &'a [String],
+    /// With `--fail-fast`: set when a batch fails (unless its tool has `allow_failure`).
+    /// Once set, batches that haven't started are skipped and running commands are
+    /// killed, since a child only stops mid-run if we hold it and `kill` it.
+    pub fail_fast: Option<&'a AtomicBool>,
+    /// Issues-found exits count as passing (lenient `--check-level`, or diff mode),
+    /// so they don't trip `fail_fast`
+    pub lenient: bool,
+}
+
+impl RunOptions<'_> {
+    /// Whether a failure elsewhere has aborted the run (`--fail-fast`).
+    fn aborted(&self) -> bool {
+        self.fail_fast
+            .is_some_and(|flag| flag.load(Ordering::Relaxed))
+    }
+
+    /// The args `tool` runs with in this mode (args, check_args, or diff_args),
+    /// plus any extra args. Batching and command building both use this, so batch
+    /// sizes are computed from the args that are actually passed.
+    fn tool_args(&self, tool: &Tool) -> Vec<String> {
+        with_extra_args(tool.get_args(self.mode), self.extra_args)
+    }
+}
 
 /// Result of running a single batch.
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct BatchResult {
     /// Whether the command succeeded (exit code 0)
     pub success: bool,
@@ -26,6 +101,39 @@ pub struct BatchResult {
     pub stderr: String,
     /// The command that was run (for verbose output)
     pub command: String,
+    /// Whether the command exited with the tool's issues-found code
+    /// (files need formatting, as opposed to the tool itself erroring)
+    pub issues_found: bool,
+    /// Exit code of the command (`None` if it was killed by a signal or never started)
+    pub exit_code: Option<i32>,
+    /// Number of times the batch was re-run after failing (see `Tool::retries`)
+    pub retries: u32,
+    /// Whether the command was killed because another batch failed (`--fail-fast`)
+    pub cancelled: bool,
+}
+
+impl BatchResult {
+    /// Describe how the command exited, e.g. "exited with code 2 after 1 retry".
+    pub fn exit_description(&self) -> String {
+        let exit = match self.exit_code {
+            Some(code) => format!("exited with code {code}"),
+            None => "exited with code none (terminated by signal)".to_string(),
+        };
+        match self.retries {
+            0 => exit,
+            1 => format!("{exit} after 1 retry"),
+            n => format!("{exit} after {n} retries"),
+        }
+    }
+}
+
+/// Result of running a tool as a stdin filter (`run_stdin`).
+#[derive(Debug)]
+pub struct StdinOutput {
+    /// Exit status and captured stdout (the formatted content) and stderr
+    pub output: Output,
+    /// Whether the tool was killed for outliving its `timeout`
+    pub timed_out: bool,
 }
 
 /// Result of running all batches for a tool.
@@ -33,8 +141,26 @@ pub struct BatchResult {
 pub struct ToolResult {
     /// Whether all batches succeeded
     pub success: bool,
-    /// Results from each batch
+    /// Results from each batch, in the order the batches were created (input file
+    /// order), however the batches were scheduled, so verbose logs are reproducible
     pub batches: Vec<BatchResult>,
+    /// Wall-clock time from the tool's first batch starting to its last finishing
+    pub duration: Duration,
+}
+
+impl ToolResult {
+    /// Whether the tool failed only because files need formatting.
+    ///
+    /// False if the tool succeeded or if any failing batch exited with a code
+    /// other than the tool's issues-found code.
+    pub fn issues_only(&self) -> bool {
+        !self.success
+            && self
+                .batches
+                .iter()
+                .filter(|b| !b.success)
+                .all(|b| b.issues_found)
+    }
 }
 
 /// Calculate the byte size of an OS string (for arg length estimation).
@@ -43,11 +169,22 @@ fn arg_bytes(s: &OsStr) -> usize {
     s.len() + 1
 }
 
-/// Create batches of files that fit within MAX_BATCH_BYTES.
+/// Create batches of files that fit within `max_bytes` and the tool's `max_files_per_batch`.
 ///
-/// Each batch's total arg bytes (cmd + args + files) stays under the limit.
-fn create_batches<'a>(tool: &Tool, files: &[&'a Path], check_mode: bool) -> Vec<Vec<&'a Path>> {
-    let args = tool.get_args(check_mode);
+/// `args` are the resolved args for the run's mode (see `RunOptions::tool_args`).
+/// Each batch's total arg bytes (cmd + args + files) stays under the byte limit,
+/// and a new batch starts when either limit would be exceeded.
+/// Tools with `per_file` get one batch per file.
+fn create_batches<'a>(
+    tool: &Tool,
+    files: &[&'a Path],
+    args: &[String],
+    max_bytes: usize,
+) -> Vec<Vec<&'a Path>> {
+    if tool.per_file {
+        return files.iter().map(|file| vec![*file]).collect();
+    }
+
     // Calculate fixed overhead: command + configured args
     let base_bytes: usize = arg_bytes(OsStr::new(&tool.cmd))
         + args.iter().map(|a| arg_bytes(OsStr::new(a))).sum::<usize>();
@@ -59,9 +196,13 @@ fn create_batches<'a>(tool: &Tool, files: &[&'a Path], check_mode: bool) -> Vec<
     for file in files {
         let file_bytes = arg_bytes(file.as_os_str());
 
-        // If adding this file would exceed limit, start a new batch
+        // If adding this file would exceed either limit, start a new batch
         // (unless batch is empty - we must include at least one file)
-        if !current_batch.is_empty() && current_bytes + file_bytes > MAX_BATCH_BYTES {
+        let full = current_bytes + file_bytes > max_bytes
+            || tool
+                .max_files_per_batch
+                .is_some_and(|max| current_batch.len() >= max);
+        if !current_batch.is_empty() && full {
             batches.push(std::mem::take(&mut current_batch));
             current_bytes = base_bytes;
         }
@@ -82,29 +223,266 @@ fn create_batches<'a>(tool: &Tool, files: &[&'a Path], check_mode: bool) -> Vec<
 ///
 /// Files are batched by total argument bytes to avoid ARG_MAX limits.
 /// Batches run in parallel using rayon.
-/// When `verbose` or `check_mode` is true, command strings are captured for logging.
-/// When `check_mode` is true, uses check_args instead of args (for CI).
-/// `work_dir` sets the working directory for the formatter commands.
-pub fn run_tool(
-    tool: &Tool,
-    files: &[&Path],
-    verbose: bool,
-    check_mode: bool,
-    work_dir: &Path,
-) -> Result<ToolResult> {
+/// When `verbose` is true or not formatting in place, command strings are captured for logging.
+/// `mode` selects args, check_args, or diff_args.
+/// Returns `None` if `--fail-fast` aborted some of its batches and none failed.
+#[tracing::instrument(skip_all, fields(tool = %tool.name, files = files.len()))]
+pub fn run_tool(tool: &Tool, files: &[&Path], options: &RunOptions) -> Option<Result<ToolResult>> {
+    let start = Instant::now();
+    let span = tracing::Span::current();
+
     // Create batches based on total arg bytes
-    let batches = create_batches(tool, files, check_mode);
+    let batches = create_batches(
+        tool,
+        files,
+        &options.tool_args(tool),
+        options.max_batch_bytes,
+    );
+    let limit = BatchLimit::for_tool(tool);
+    let run = |batch: &Vec<&Path>| {
+        // Batches run on pool threads, which don't inherit the tool's span
+        let _entered = span.enter();
+        let _permit = limit.as_ref().map(BatchLimit::acquire);
+        (!options.aborted()).then(|| run_batch(tool, batch, options))
+    };
+
+    // Run batches in parallel, unless the tool must not overlap with itself
+    let results: Vec<Option<Result<BatchResult>>> = if tool.serial {
+        batches.iter().map(run).collect()
+    } else {
+        batches.par_iter().map(run).collect()
+    };
+
+    aggregate_batches(results, start.elapsed())
+}
+
+/// Caps how many of one tool's batches run at once (`max_parallel`).
+///
+/// A batch waiting for a permit still occupies its pool thread, so the global
+/// `--jobs` limit keeps bounding overall concurrency.
+struct BatchLimit {
+    available: Mutex<usize>,
+    freed: Condvar,
+}
+
+impl BatchLimit {
+    fn for_tool(tool: &Tool) -> Option<Self> {
+        tool.max_parallel.map(|permits| BatchLimit {
+            available: Mutex::new(permits),
+            freed: Condvar::new(),
+        })
+    }
+
+    /// Block until a permit is free; it is returned when the guard drops.
+    fn acquire(&self) -> BatchPermit<'_> {
+        let mut available = self.available.lock().unwrap_or_else(|e| e.into_inner());
+        while *available == 0 {
+            available = self
+                .freed
+                .wait(available)
+                .unwrap_or_else(|e| e.into_inner());
+        }
+        *available -= 1;
+
+        BatchPermit(self)
+    }
+}
+
+struct BatchPermit<'a>(&'a BatchLimit);
+
+impl Drop for BatchPermit<'_> {
+    fn drop(&mut self) {
+        *self.0.available.lock().unwrap_or_else(|e| e.into_inner()) += 1;
+        self.0.freed.notify_one();
+    }
+}
+
+/// Run every tool on its files, scheduling work according to `strategy`.
+///
+/// `on_done` receives each tool's index and result once all of its batches have
+/// finished. `should_skip` is checked before each unit of work starts (a tool for
+/// `ByTool`, a batch for `ByBatch`); tools with skipped work and no failures are
+/// reported as `None`.
+///
+/// Tools marked `serial` run after all other work finishes, one at a time, except
+/// with `Sequential`, where every tool runs in the order given.
+pub fn run_tools<S, F>(
+    jobs: &[(&Tool, &[&Path])],
+    strategy: Strategy,
+    options: &RunOptions,
+    should_skip: S,
+    on_done: F,
+) where
+    S: Fn() -> bool + Sync,
+    F: Fn(usize, Option<Result<ToolResult>>) + Sync,
+{
+    // Sequential runs keep serial tools in config order with everything else
+    let (serial, parallel): (Vec<usize>, Vec<usize>) =
+        (0..jobs.len()).partition(|&idx| jobs[idx].0.serial && strategy != Strategy::Sequential);
+
+    let parallel_jobs: Vec<(&Tool, &[&Path])> = parallel.iter().map(|&idx| jobs[idx]).collect();
+    run_parallel(
+        &parallel_jobs,
+        strategy,
+        options,
+        &should_skip,
+        |idx, result| on_done(parallel[idx], result),
+    );
+
+    for idx in serial {
+        if should_skip() {
+            on_done(idx, None);
+            continue;
+        }
+        let (tool, files) = jobs[idx];
+        on_done(idx, run_tool(tool, files, options));
+    }
+}
+
+/// Run `jobs` concurrently according to `strategy`.
+fn run_parallel<S, F>(
+    jobs: &[(&Tool, &[&Path])],
+    strategy: Strategy,
+    options: &RunOptions,
+    should_skip: S,
+    on_done: F,
+) where
+    S: Fn() -> bool + Sync,
+    F: Fn(usize, Option<Result<ToolResult>>) + Sync,
+{
+    match strategy {
+        Strategy::ByTool => jobs
+            .par_iter()
+            .enumerate()
+            .for_each(|(idx, (tool, files))| {
+                if should_skip() {
+                    on_done(idx, None);
+                    return;
+                }
+                on_done(idx, run_tool(tool, files, options));
+            }),
+        Strategy::ByBatch => run_tools_by_batch(jobs, options, should_skip, on_done),
+        Strategy::Sequential => {
+            for (idx, (tool, files)) in jobs.iter().enumerate() {
+                if should_skip() {
+                    on_done(idx, None);
+                    continue;
+                }
+                on_done(idx, run_tool(tool, files, options));
+            }
+        }
+    }
+}
+
+/// Per-tool bookkeeping while its batches run in the flattened queue.
+struct PendingTool {
+    results: Vec<Option<Result<BatchResult>>>,
+    remaining: usize,
+    started: Option<Instant>,
+}
+
+/// Flatten all (tool, batch) units into one rayon iterator.
+///
+/// Results are aggregated per tool and reported when the tool's last batch finishes.
+fn run_tools_by_batch<S, F>(
+    jobs: &[(&Tool, &[&Path])],
+    options: &RunOptions,
+    should_skip: S,
+    on_done: F,
+) where
+    S: Fn() -> bool + Sync,
+    F: Fn(usize, Option<Result<ToolResult>>) + Sync,
+{
+    let tool_batches: Vec<Vec<Vec<&Path>>> = jobs
+        .iter()
+        .map(|(tool, files)| {
+            create_batches(
+                tool,
+                files,
+                &options.tool_args(tool),
+                options.max_batch_bytes,
+            )
+        })
+        .collect();
+
+    let limits: Vec<Option<BatchLimit>> = jobs
+        .iter()
+        .map(|(tool, _)| BatchLimit::for_tool(tool))
+        .collect();
+
+    let pending: Vec<Mutex<PendingTool>> = tool_batches
+        .iter()
+        .map(|batches| {
+            Mutex::new(PendingTool {
+                results: (0..batches.len()).map(|_| None).collect(),
+                remaining: batches.len(),
+                started: None,
+            })
+        })
+        .collect();
+
+    let units: Vec<(usize, usize)> = tool_batches
+        .iter()
+        .enumerate()
+        .flat_map(|(tool_idx, batches)| (0..batches.len()).map(move |b| (tool_idx, b)))
+        .collect();
 
-    // Capture commands in verbose mode OR check mode (for failure details)
-    let capture_commands = verbose || check_mode;
+    units.par_iter().for_each(|&(tool_idx, batch_idx)| {
+        pending[tool_idx]
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .started
+            .get_or_insert_with(Instant::now);
+
+        let permit = limits[tool_idx].as_ref().map(BatchLimit::acquire);
+        let result = if should_skip() || options.aborted() {
+            None
+        } else {
+            let tool = jobs[tool_idx].0;
+            Some(run_batch(tool, &tool_batches[tool_idx][batch_idx], options))
+        };
+        drop(permit);
+
+        let finished = {
+            let mut state = pending[tool_idx].lock().unwrap_or_else(|e| e.into_inner());
+            state.results[batch_idx] = result;
+            state.remaining -= 1;
+            (state.remaining == 0).then(|| {
+                let duration = state.started.map(|s| s.elapsed()).unwrap_or_default();
+                (std::mem::take(&mut state.results), duration)
+            })
+        };
+
+        if let Some((results, duration)) = finished {
+            on_done(tool_idx, aggregate_batches(results, duration));
+        }
+    });
+}
 
-    // Run batches in parallel
-    let results: Vec<Result<BatchResult>> = batches
-        .par_iter()
-        .map(|batch| run_batch(tool, batch, capture_commands, check_mode, work_dir))
+/// Combine a tool's batch results, treating any skipped or cancelled batch as a
+/// skip unless another batch already failed.
+fn aggregate_batches(
+    results: Vec<Option<Result<BatchResult>>>,
+    duration: Duration,
+) -> Option<Result<ToolResult>> {
+    let results: Vec<Option<Result<BatchResult>>> = results
+        .into_iter()
+        .map(|result| result.filter(|r| !matches!(r, Ok(batch) if batch.cancelled)))
         .collect();
+    let skipped = results.iter().any(Option::is_none);
+    let tool_result = collect_tool_result(results.into_iter().flatten().collect(), duration);
+
+    match tool_result {
+        Ok(r) if skipped && r.success => None,
+        other => Some(other),
+    }
+}
 
-    // Collect results, propagating any errors
+/// Collect batch results into a tool result, propagating any errors.
+fn collect_tool_result(
+    results: Vec<Result<BatchResult>>,
+    duration: Duration,
+) -> Result<ToolResult> {
     let mut batch_results = Vec::new();
     let mut all_success = true;
 
@@ -119,70 +497,578 @@ pub fn run_tool(
     Ok(ToolResult {
         success: all_success,
         batches: batch_results,
+        duration,
     })
 }
 
 /// Run a single batch of files through a formatter.
-fn run_batch(
+///
+/// A failed batch of a tool without `allow_failure` aborts the run under `--fail-fast`,
+/// as does a batch that couldn't be run at all. In lenient runs, a batch that only
+/// found issues doesn't count as failed.
+fn run_batch(tool: &Tool, files: &[&Path], options: &RunOptions) -> Result<BatchResult> {
+    let mut result = match run_batch_with_retries(tool, files, options) {
+        Ok(result) => result,
+        Err(err) => {
+            abort_run(tool, options);
+            return Err(err);
+        }
+    };
+
+    if files.len() > 1 && !result.success && rejects_multiple_files(&result.stderr) {
+        if !result.stderr.is_empty() && !result.stderr.ends_with('\n') {
+            result.stderr.push('\n');
+        }
+        result.stderr.push_str(&format!(
+            "hint: '{}' may accept only one file at a time; try `per_file: true` for this tool",
+            tool.name
+        ));
+    }
+
+    let advisory = options.lenient && result.issues_found;
+    if !result.success && !result.cancelled && !advisory {
+        abort_run(tool, options);
+    }
+
+    Ok(result)
+}
+
+/// Under `--fail-fast`, stop the run because `tool` failed (unless it has `allow_failure`).
+fn abort_run(tool: &Tool, options: &RunOptions) {
+    if tool.allow_failure {
+        return;
+    }
+    if let Some(flag) = options.fail_fast {
+        flag.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Whether a failed batch's stderr looks like the tool rejected being given several files.
+fn rejects_multiple_files(stderr: &str) -> bool {
+    let stderr = stderr.to_lowercase();
+    SINGLE_FILE_ERRORS
+        .iter()
+        .any(|phrase| stderr.contains(phrase))
+}
+
+/// Run a batch, re-running it up to `tool.retries` times while it fails.
+///
+/// A batch that exits with the issues-found code is not retried: the files
+/// need formatting and another run won't change that.
+fn run_batch_with_retries(
     tool: &Tool,
     files: &[&Path],
-    verbose: bool,
-    check_mode: bool,
-    work_dir: &Path,
+    options: &RunOptions,
 ) -> Result<BatchResult> {
-    let args = tool.get_args(check_mode);
-    let mut cmd = Command::new(&tool.cmd);
+    let mut result = run_batch_once(tool, files, options)?;
 
-    // Run from repo root so paths resolve correctly
-    cmd.current_dir(work_dir);
+    while !result.success
+        && !result.issues_found
+        && !result.cancelled
+        && result.retries < tool.retries
+        && !options.aborted()
+    {
+        let retries = result.retries + 1;
+        if options.verbose {
+            eprintln!(
+                "  [{}] {}, retrying ({}/{})",
+                tool.name,
+                result.exit_description(),
+                retries,
+                tool.retries
+            );
+        }
+        result = BatchResult {
+            retries,
+            ..run_batch_once(tool, files, options)?
+        };
+    }
 
-    // Add configured arguments (check_args in check mode, args otherwise)
-    cmd.args(args);
+    Ok(result)
+}
 
-    // Add file paths
-    for file in files {
-        cmd.arg(file);
+/// Describe each batch's command without running it (for `--dry-run`).
+///
+/// Each line is a shell-like `cd <dir> && [VAR=value ...] <cmd> <args...>`, built
+/// exactly as `run_tool` would build the command for that batch.
+pub fn dry_run_commands(tool: &Tool, files: &[&Path], options: &RunOptions) -> Result<Vec<String>> {
+    create_batches(
+        tool,
+        files,
+        &options.tool_args(tool),
+        options.max_batch_bytes,
+    )
+    .iter()
+    .map(|batch| {
+        let cmd = build_command(tool, batch, options)?;
+        let dir = cmd.get_current_dir().unwrap_or(options.work_dir);
+
+        let mut env: Vec<String> = cmd
+            .get_envs()
+            .filter_map(|(key, value)| {
+                Some(format!(
+                    "{}={}",
+                    key.to_string_lossy(),
+                    value?.to_string_lossy()
+                ))
+            })
+            .collect();
+        env.sort();
+        env.push(command_string(&cmd));
+
+        Ok(format!("cd {} && {}", dir.display(), env.join(" ")))
+    })
+    .collect()
+}
+
+/// Run `tool` as a filter on `input` (for `--stdin`): the content goes to its stdin
+/// and the formatted result comes back on stdout.
+///
+/// `file` is the path the content belongs to. It only reaches the tool where a
+/// `{files}` placeholder asks for it (e.g. prettier's `--stdin-filepath {files}`),
+/// since appending it would make most tools format the file on disk instead.
+pub fn run_stdin(
+    tool: &Tool,
+    file: &Path,
+    input: &[u8],
+    options: &RunOptions,
+) -> Result<StdinOutput> {
+    let wants_path = if tool.shell {
+        tool.cmd.contains(FILES_PLACEHOLDER)
+    } else {
+        tool.get_args(options.mode)
+            .iter()
+            .any(|a| a == FILES_PLACEHOLDER)
+    };
+    let files: &[&Path] = if wants_path { &[file] } else { &[] };
+
+    let mut cmd = build_command(tool, files, options)?;
+    let (output, outcome) = spawn_and_wait(&mut cmd, tool.timeout(), None, None, Some(input))
+        .with_context(|| format!("Failed to execute '{}'", program(tool)))?;
+
+    Ok(StdinOutput {
+        output,
+        timed_out: outcome == WaitOutcome::TimedOut,
+    })
+}
+
+/// The command line of `cmd` as a single space-separated string.
+fn command_string(cmd: &Command) -> String {
+    let mut parts = vec![cmd.get_program().to_string_lossy().into_owned()];
+    parts.extend(cmd.get_args().map(|a| a.to_string_lossy().into_owned()));
+    parts.join(" ")
+}
+
+/// Build the command for one batch: working directory, environment, and args with files.
+fn build_command(tool: &Tool, files: &[&Path], options: &RunOptions) -> Result<Command> {
+    let args = options.tool_args(tool);
+
+    // Run from repo root so paths resolve correctly, or from the tool's
+    // working_dir with paths rewritten relative to it
+    let relative_files;
+    let (dir, files) = match &tool.working_dir {
+        Some(dir) if !tool.absolute_paths => {
+            relative_files = relative_to(files, Path::new(dir)).with_context(|| {
+                format!("Tool '{}' has files outside its working_dir", tool.name)
+            })?;
+            (options.work_dir.join(dir), relative_files.as_slice())
+        }
+        Some(dir) => (options.work_dir.join(dir), files),
+        None => (options.work_dir.to_path_buf(), files),
+    };
+
+    // Some tools resolve paths against their own notion of the working directory
+    let absolute_files: Vec<PathBuf>;
+    let absolute_refs: Vec<&Path>;
+    let files = if tool.absolute_paths {
+        absolute_files = files.iter().map(|f| options.work_dir.join(f)).collect();
+        absolute_refs = absolute_files.iter().map(PathBuf::as_path).collect();
+        absolute_refs.as_slice()
+    } else {
+        files
+    };
+
+    // Configured arguments (check_args in check mode, args otherwise) with file paths
+    let args = command_args(&args, files);
+    let mut cmd = if tool.shell {
+        shell_command(&tool.cmd, &args)
+    } else {
+        let mut cmd = Command::new(&tool.cmd);
+        cmd.args(args);
+        cmd
+    };
+    cmd.current_dir(dir);
+
+    // Per-tool environment overrides the inherited environment
+    cmd.envs(&tool.env);
+
+    Ok(cmd)
+}
+
+/// The executable ffx launches for `tool`: its `cmd`, or the platform shell for `shell` tools.
+pub fn program(tool: &Tool) -> &str {
+    match (tool.shell, cfg!(windows)) {
+        (true, true) => "cmd",
+        (true, false) => "sh",
+        (false, _) => &tool.cmd,
     }
+}
 
-    // Only build command string when verbose (avoids allocation overhead)
-    let command = if verbose {
-        format!(
-            "{} {} {}",
-            tool.cmd,
-            args.join(" "),
-            files
-                .iter()
-                .map(|p| p.to_string_lossy())
-                .collect::<Vec<_>>()
-                .join(" ")
-        )
+/// Run `script` through `sh -c`, passing `args` as positional parameters.
+///
+/// `{files}` in the script (or the end of it, if absent) becomes `"$@"`, so paths
+/// reach the script intact without any quoting.
+#[cfg(not(windows))]
+fn shell_command(script: &str, args: &[&OsStr]) -> Command {
+    let script = if script.contains(FILES_PLACEHOLDER) {
+        script.replace(FILES_PLACEHOLDER, "\"$@\"")
+    } else {
+        format!("{script} \"$@\"")
+    };
+
+    let mut cmd = Command::new("sh");
+    cmd.arg("-c").arg(script).arg("sh").args(args);
+    cmd
+}
+
+/// Run `script` through `cmd /C`, with `args` double-quoted and substituted for
+/// `{files}` (or appended, if absent). Windows paths can't contain `"`.
+#[cfg(windows)]
+fn shell_command(script: &str, args: &[&OsStr]) -> Command {
+    use std::os::windows::process::CommandExt;
+
+    let quoted: Vec<String> = args
+        .iter()
+        .map(|arg| format!("\"{}\"", arg.to_string_lossy()))
+        .collect();
+    let quoted = quoted.join(" ");
+    let line = if script.contains(FILES_PLACEHOLDER) {
+        script.replace(FILES_PLACEHOLDER, &quoted)
+    } else {
+        format!("{script} {quoted}")
+    };
+
+    let mut cmd = Command::new("cmd");
+    cmd.arg("/C").raw_arg(line);
+    cmd
+}
+
+/// Run a single invocation of a tool on one batch of files.
+fn run_batch_once(tool: &Tool, files: &[&Path], options: &RunOptions) -> Result<BatchResult> {
+    let mut cmd = build_command(tool, files, options)?;
+
+    // Only build command string when verbose or in check/diff mode (for details)
+    let command = if options.verbose || options.mode != Mode::Format {
+        command_string(&cmd)
     } else {
         String::new()
     };
 
-    let output: Output = match cmd.output() {
-        Ok(output) => output,
+    tracing::debug!(command = %command_string(&cmd), files = files.len(), "running batch");
+
+    // Verbose runs on a terminal echo stderr live so slow tools visibly make progress
+    let stream_prefix = options.stream_stderr.then_some(tool.name.as_str());
+    let spawned =
+        if tool.timeout().is_some() || stream_prefix.is_some() || options.fail_fast.is_some() {
+            spawn_and_wait(
+                &mut cmd,
+                tool.timeout(),
+                stream_prefix,
+                options.fail_fast,
+                None,
+            )
+        } else {
+            cmd.output().map(|output| (output, WaitOutcome::Exited))
+        };
+
+    let (output, outcome) = match spawned {
+        Ok(spawned) => spawned,
         Err(err) if err.kind() == ErrorKind::ArgumentListTooLong => {
             return Ok(BatchResult {
                 success: false,
                 stdout: String::new(),
-                stderr: format!("Argument list too long when executing '{}'", tool.cmd),
+                stderr: format!("Argument list too long when executing '{}'", program(tool)),
                 command,
+                issues_found: false,
+                exit_code: None,
+                retries: 0,
+                cancelled: false,
             });
         }
         Err(err) => {
-            return Err(err).with_context(|| format!("Failed to execute '{}'", tool.cmd));
+            return Err(err).with_context(|| format!("Failed to execute '{}'", program(tool)));
         }
     };
 
     let stdout = String::from_utf8_lossy(&output.stdout).to_string();
-    let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+    let mut stderr = String::from_utf8_lossy(&output.stderr).to_string();
+
+    let timed_out = outcome == WaitOutcome::TimedOut;
+    if timed_out {
+        if !stderr.is_empty() && !stderr.ends_with('\n') {
+            stderr.push('\n');
+        }
+        stderr.push_str(&format!(
+            "timed out after {}s",
+            tool.timeout.unwrap_or_default()
+        ));
+    }
+
+    let cancelled = outcome == WaitOutcome::Cancelled;
+    let success = !timed_out
+        && !cancelled
+        && (tool.is_success_code(output.status.code(), options.mode) || tool.ignore_exit_code);
+    tracing::debug!(
+        exit_code = ?output.status.code(),
+        success,
+        timed_out,
+        cancelled,
+        "batch finished"
+    );
 
     Ok(BatchResult {
-        success: output.status.success(),
+        success,
         stdout,
         stderr,
         command,
+        issues_found: !success
+            && !timed_out
+            && output.status.code() == Some(tool.issues_exit_code()),
+        exit_code: output.status.code(),
+        retries: 0,
+        cancelled,
+    })
+}
+
+/// Rewrite repo-root-relative `files` to be relative to `dir`, failing on any file outside it.
+fn relative_to<'a>(files: &[&'a Path], dir: &Path) -> Result<Vec<&'a Path>> {
+    files
+        .iter()
+        .map(|file| {
+            file.strip_prefix(dir).map_err(|_| {
+                anyhow::anyhow!("'{}' is not inside '{}'", file.display(), dir.display())
+            })
+        })
+        .collect()
+}
+
+/// Insert `extra` just before the file paths: at the `{files}` placeholder, or
+/// after the last arg if there is none.
+fn with_extra_args(args: &[String], extra: &[String]) -> Vec<String> {
+    let mut args = args.to_vec();
+    let pos = args
+        .iter()
+        .position(|a| a == FILES_PLACEHOLDER)
+        .unwrap_or(args.len());
+    args.splice(pos..pos, extra.iter().cloned());
+    args
+}
+
+/// Build the argument list for one batch: `args` with the `{files}` placeholder
+/// replaced by `files`, or `args` followed by `files` if there is no placeholder.
+fn command_args<'a>(args: &'a [String], files: &[&'a Path]) -> Vec<&'a OsStr> {
+    let file_args = files.iter().map(|f| f.as_os_str());
+
+    match args.iter().position(|a| a == FILES_PLACEHOLDER) {
+        Some(pos) => args[..pos]
+            .iter()
+            .map(OsStr::new)
+            .chain(file_args)
+            .chain(args[pos + 1..].iter().map(OsStr::new))
+            .collect(),
+        None => args.iter().map(OsStr::new).chain(file_args).collect(),
+    }
+}
+
+/// How a command run by `spawn_and_wait` ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum WaitOutcome {
+    Exited,
+    TimedOut,
+    /// Killed because the `abort` flag was set
+    Cancelled,
+}
+
+/// Run a command to completion, capturing its output.
+///
+/// With a `timeout`, the command is killed if it outlives it; likewise once `abort`
+/// is set. Such commands run in their own process group on Unix so the kill also
+/// reaches processes they spawned (e.g. `sh -c` or `npx` wrappers), which would
+/// otherwise keep the output pipes open until they finish. With a `stream_prefix`, each stderr line is also printed as it arrives,
+/// prefixed with `[prefix]`. With `input`, it is written to the command's stdin;
+/// otherwise stdin is closed. Returns the captured output and how the command ended.
+/// A killed child is always waited on so it doesn't linger as a zombie.
+fn spawn_and_wait(
+    cmd: &mut Command,
+    timeout: Option<Duration>,
+    stream_prefix: Option<&str>,
+    abort: Option<&AtomicBool>,
+    input: Option<&[u8]>,
+) -> io::Result<(Output, WaitOutcome)> {
+    cmd.stdin(if input.is_some() {
+        Stdio::piped()
+    } else {
+        Stdio::null()
+    })
+    .stdout(Stdio::piped())
+    .stderr(Stdio::piped());
+
+    let killable = timeout.is_some() || abort.is_some();
+    #[cfg(unix)]
+    if killable {
+        use std::os::unix::process::CommandExt;
+        cmd.process_group(0);
+    }
+
+    let mut child = cmd.spawn()?;
+    #[cfg(unix)]
+    let _group = killable.then(|| ProcessGroup::register(child.id()));
+    // Feed stdin from another thread so a tool that writes before it finishes
+    // reading can't fill the stdout pipe and deadlock. The writer is never joined: a
+    // tool that exits without reading everything (or leaves a process holding stdin)
+    // would block it, and its exit status tells the story anyway.
+    if let (Some(input), Some(mut stdin)) = (input, child.stdin.take()) {
+        let input = input.to_vec();
+        thread::spawn(move || stdin.write_all(&input));
+    }
+    let stdout = spawn_reader(child.stdout.take());
+    let stderr = match stream_prefix {
+        Some(prefix) => spawn_streaming_reader(child.stderr.take(), prefix.to_string()),
+        None => spawn_reader(child.stderr.take()),
+    };
+
+    let (status, outcome) = if killable {
+        wait_polling(&mut child, timeout.map(|t| Instant::now() + t), abort)?
+    } else {
+        (child.wait()?, WaitOutcome::Exited)
+    };
+
+    let output = Output {
+        status,
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+    };
+
+    Ok((output, outcome))
+}
+
+/// Poll a child until it exits, killing it if the deadline passes or `abort` is set.
+fn wait_polling(
+    child: &mut Child,
+    deadline: Option<Instant>,
+    abort: Option<&AtomicBool>,
+) -> io::Result<(ExitStatus, WaitOutcome)> {
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok((status, WaitOutcome::Exited));
+        }
+
+        let outcome = if deadline.is_some_and(|d| Instant::now() >= d) {
+            Some(WaitOutcome::TimedOut)
+        } else if abort.is_some_and(|flag| flag.load(Ordering::Relaxed)) {
+            Some(WaitOutcome::Cancelled)
+        } else {
+            None
+        };
+
+        if let Some(outcome) = outcome {
+            // The child may exit between try_wait and kill; wait() reaps it either way
+            kill_tree(child);
+            return Ok((child.wait()?, outcome));
+        }
+
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// Kill `child` and, on Unix, the rest of its process group (see `spawn_and_wait`).
+fn kill_tree(child: &mut Child) {
+    #[cfg(unix)]
+    signal_group(child.id(), libc::SIGKILL);
+    let _ = child.kill();
+}
+
+/// Process groups of running killable commands, so Ctrl-C can reach them: being in
+/// their own group, they don't get the terminal's SIGINT.
+#[cfg(unix)]
+static PROCESS_GROUPS: Mutex<Vec<u32>> = Mutex::new(Vec::new());
+
+/// Registration in `PROCESS_GROUPS` for as long as a command runs.
+#[cfg(unix)]
+struct ProcessGroup(u32);
+
+#[cfg(unix)]
+impl ProcessGroup {
+    fn register(pgid: u32) -> Self {
+        PROCESS_GROUPS
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(pgid);
+        ProcessGroup(pgid)
+    }
+}
+
+#[cfg(unix)]
+impl Drop for ProcessGroup {
+    fn drop(&mut self) {
+        PROCESS_GROUPS
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .retain(|&pgid| pgid != self.0);
+    }
+}
+
+/// Send `signal` to every process in the group led by `pgid`.
+#[cfg(unix)]
+fn signal_group(pgid: u32, signal: libc::c_int) {
+    // Safety: killpg only sends a signal; a group that already exited yields ESRCH
+    unsafe {
+        libc::killpg(pgid as libc::pid_t, signal);
+    }
+}
+
+/// Forward an interrupt (Ctrl-C) to commands running in their own process group.
+///
+/// Commands with a timeout or under `--fail-fast` don't share ffx's process group,
+/// so a Ctrl-C handler calls this to stop them as the terminal would. A no-op elsewhere.
+pub fn interrupt_running() {
+    #[cfg(unix)]
+    for &pgid in PROCESS_GROUPS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .iter()
+    {
+        signal_group(pgid, libc::SIGINT);
+    }
+}
+
+/// Drain a child's pipe on a background thread so it can't fill up and block the child.
+fn spawn_reader<R: Read + Send + 'static>(pipe: Option<R>) -> JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut buf = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut buf);
+        }
+        buf
+    })
+}
+
+/// Like `spawn_reader`, but also echoes each line to stderr as `[prefix] line` as it arrives.
+fn spawn_streaming_reader<R: Read + Send + 'static>(
+    pipe: Option<R>,
+    prefix: String,
+) -> JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut buf = Vec::new();
+        if let Some(pipe) = pipe {
+            let mut reader = BufReader::new(pipe);
+            let mut line = Vec::new();
+            while matches!(reader.read_until(b'\n', &mut line), Ok(n) if n > 0) {
+                eprintln!("  [{prefix}] {}", String::from_utf8_lossy(&line).trim_end());
+                buf.append(&mut line);
+            }
+        }
+        buf
     })
 }
 
@@ -216,7 +1102,6 @@ pub fn configure_parallelism(jobs: usize) -> Result<()> {
 mod tests {
     use super::*;
     use crate::config::Tool;
-    use std::path::PathBuf;
 
     fn make_tool(name: &str, cmd: &str, args: &[&str]) -> Tool {
         Tool {
@@ -225,7 +1110,7 @@ mod tests {
             exclude: vec![],
             cmd: cmd.to_string(),
             args: args.iter().map(|s| s.to_string()).collect(),
-            check_args: None,
+            ..Default::default()
         }
     }
 
@@ -242,9 +1127,185 @@ mod tests {
             cmd: cmd.to_string(),
             args: args.iter().map(|s| s.to_string()).collect(),
             check_args: Some(check_args.iter().map(|s| s.to_string()).collect()),
+            ..Default::default()
+        }
+    }
+
+    fn options(verbose: bool, check_mode: bool, work_dir: &Path) -> RunOptions<'_> {
+        RunOptions {
+            verbose,
+            stream_stderr: verbose,
+            mode: if check_mode {
+                Mode::Check
+            } else {
+                Mode::Format
+            },
+            work_dir,
+            max_batch_bytes: MAX_BATCH_BYTES,
+            extra_args: &[],
+            fail_fast: None,
+            lenient: false,
+        }
+    }
+
+    /// `run_tool` without `--fail-fast`, which never skips batches.
+    fn run_tool_fully(tool: &Tool, files: &[&Path], options: &RunOptions) -> Result<ToolResult> {
+        run_tool(tool, files, options).expect("batches are only skipped with fail_fast")
+    }
+
+    #[test]
+    fn test_fail_fast_kills_running_batches() {
+        let failing = make_tool("fail", "sh", &["-c", "exit 1"]);
+        let slow = make_tool("slow", "sh", &["-c", "exec sleep 10"]);
+        let files: Vec<PathBuf> = vec!["file.txt".into()];
+        let file_refs: Vec<&Path> = files.iter().map(|p| p.as_path()).collect();
+        let work_dir = std::env::current_dir().unwrap();
+        let abort = AtomicBool::new(false);
+        let options = RunOptions {
+            fail_fast: Some(&abort),
+            ..options(false, false, &work_dir)
+        };
+        let jobs = [
+            (&failing, file_refs.as_slice()),
+            (&slow, file_refs.as_slice()),
+        ];
+        let results = Mutex::new(Vec::new());
+
+        let start = Instant::now();
+        run_tools(
+            &jobs,
+            Strategy::ByBatch,
+            &options,
+            || false,
+            |idx, result| {
+                let success = result.map(|r| r.unwrap().success);
+                results.lock().unwrap().push((idx, success));
+            },
+        );
+
+        assert!(start.elapsed() < Duration::from_secs(5));
+        let mut results = results.into_inner().unwrap();
+        results.sort();
+        assert_eq!(results, vec![(0, Some(false)), (1, None)]);
+    }
+
+    #[test]
+    fn test_fail_fast_ignores_issues_in_lenient_runs() {
+        let issues = make_tool("issues", "sh", &["-c", "exit 1"]);
+        let files: Vec<PathBuf> = vec!["file.txt".into()];
+        let file_refs: Vec<&Path> = files.iter().map(|p| p.as_path()).collect();
+        let work_dir = std::env::current_dir().unwrap();
+        let abort = AtomicBool::new(false);
+
+        let lenient = RunOptions {
+            fail_fast: Some(&abort),
+            lenient: true,
+            ..options(false, true, &work_dir)
+        };
+        run_tool_fully(&issues, &file_refs, &lenient).unwrap();
+        assert!(!abort.load(Ordering::Relaxed));
+
+        let strict = RunOptions {
+            lenient: false,
+            ..lenient
+        };
+        run_tool_fully(&issues, &file_refs, &strict).unwrap();
+        assert!(abort.load(Ordering::Relaxed));
+    }
+
+    #[test]
+    fn test_fail_fast_aborts_when_a_batch_cannot_start() {
+        let missing = make_tool("missing", "definitely_not_installed_ffx", &[]);
+        let files: Vec<PathBuf> = vec!["file.txt".into()];
+        let file_refs: Vec<&Path> = files.iter().map(|p| p.as_path()).collect();
+        let work_dir = std::env::current_dir().unwrap();
+        let abort = AtomicBool::new(false);
+        let options = RunOptions {
+            fail_fast: Some(&abort),
+            ..options(false, false, &work_dir)
+        };
+
+        assert!(run_tool_fully(&missing, &file_refs, &options).is_err());
+        assert!(abort.load(Ordering::Relaxed));
+    }
+
+    #[test]
+    fn test_max_parallel_caps_concurrent_batches() {
+        let dir = tempfile::tempdir().unwrap();
+        let lock = dir.path().join("lock");
+        let lock_arg = lock.to_string_lossy().into_owned();
+        // Fails if another batch of the tool is running at the same time
+        let mut tool = make_tool(
+            "heavy",
+            "sh",
+            &[
+                "-c",
+                "mkdir \"$0\" || exit 9; sleep 0.05; rmdir \"$0\"",
+                &lock_arg,
+            ],
+        );
+        tool.max_files_per_batch = Some(1);
+        tool.max_parallel = Some(1);
+        let files: Vec<PathBuf> = (0..6).map(|i| format!("file{i}.txt").into()).collect();
+        let file_refs: Vec<&Path> = files.iter().map(|p| p.as_path()).collect();
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(4)
+            .build()
+            .unwrap();
+
+        for strategy in [Strategy::ByTool, Strategy::ByBatch] {
+            let results = Mutex::new(Vec::new());
+            pool.install(|| {
+                run_tools(
+                    &[(&tool, file_refs.as_slice())],
+                    strategy,
+                    &options(false, false, dir.path()),
+                    || false,
+                    |_, result| results.lock().unwrap().push(result.unwrap().unwrap()),
+                )
+            });
+
+            let results = results.into_inner().unwrap();
+            assert_eq!(results[0].batches.len(), 6);
+            assert!(results[0].success, "{strategy:?}: {:?}", results[0].batches);
         }
     }
 
+    #[test]
+    fn test_sequential_runs_tools_in_config_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = dir.path().join("order.log");
+        let log_arg = log.to_string_lossy().into_owned();
+        let slow = make_tool(
+            "slow",
+            "sh",
+            &["-c", "sleep 0.2; echo slow >> \"$0\"", &log_arg],
+        );
+        let mut serial = make_tool("serial", "sh", &["-c", "echo serial >> \"$0\"", &log_arg]);
+        serial.serial = true;
+        let fast = make_tool("fast", "sh", &["-c", "echo fast >> \"$0\"", &log_arg]);
+        let files: Vec<PathBuf> = vec!["file.txt".into()];
+        let file_refs: Vec<&Path> = files.iter().map(|p| p.as_path()).collect();
+        let jobs = [
+            (&slow, file_refs.as_slice()),
+            (&serial, file_refs.as_slice()),
+            (&fast, file_refs.as_slice()),
+        ];
+
+        run_tools(
+            &jobs,
+            Strategy::Sequential,
+            &options(false, false, dir.path()),
+            || false,
+            |_, _| {},
+        );
+
+        assert_eq!(
+            std::fs::read_to_string(&log).unwrap(),
+            "slow\nserial\nfast\n"
+        );
+    }
+
     #[test]
     fn test_command_exists_true() {
         // 'echo' should exist on all Unix systems
@@ -266,7 +1327,7 @@ mod tests {
         let file_refs: Vec<&Path> = files.iter().map(|p| p.as_path()).collect();
         let work_dir = std::env::current_dir().unwrap();
 
-        let result = run_tool(&tool, &file_refs, false, false, &work_dir).unwrap();
+        let result = run_tool_fully(&tool, &file_refs, &options(false, false, &work_dir)).unwrap();
 
         assert!(result.success);
         assert_eq!(result.batches.len(), 1);
@@ -283,7 +1344,7 @@ mod tests {
         let file_refs: Vec<&Path> = files.iter().map(|p| p.as_path()).collect();
         let work_dir = std::env::current_dir().unwrap();
 
-        let result = run_tool(&tool, &file_refs, false, false, &work_dir).unwrap();
+        let result = run_tool_fully(&tool, &file_refs, &options(false, false, &work_dir)).unwrap();
 
         assert!(!result.success);
         assert!(!result.batches[0].success);
@@ -296,7 +1357,7 @@ mod tests {
         let file_refs: Vec<&Path> = files.iter().map(|p| p.as_path()).collect();
         let work_dir = std::env::current_dir().unwrap();
 
-        let result = run_tool(&tool, &file_refs, false, false, &work_dir);
+        let result = run_tool_fully(&tool, &file_refs, &options(false, false, &work_dir));
 
         // Should return an error, not a failed result
         assert!(result.is_err());
@@ -315,7 +1376,7 @@ mod tests {
         let file_refs: Vec<&Path> = files.iter().map(|p| p.as_path()).collect();
         let work_dir = std::env::current_dir().unwrap();
 
-        let result = run_tool(&tool, &file_refs, false, false, &work_dir).unwrap();
+        let result = run_tool_fully(&tool, &file_refs, &options(false, false, &work_dir)).unwrap();
 
         assert!(result.success);
         // Short filenames should fit in a single batch
@@ -342,7 +1403,7 @@ mod tests {
         let file_refs: Vec<&Path> = files.iter().map(|p| p.as_path()).collect();
         let work_dir = std::env::current_dir().unwrap();
 
-        let result = run_tool(&tool, &file_refs, false, false, &work_dir).unwrap();
+        let result = run_tool_fully(&tool, &file_refs, &options(false, false, &work_dir)).unwrap();
 
         assert!(result.success);
         // Long filenames should require multiple batches
@@ -364,13 +1425,228 @@ mod tests {
         let file_refs: Vec<&Path> = files.iter().map(|p| p.as_path()).collect();
         let work_dir = std::env::current_dir().unwrap();
 
-        let result = run_tool(&tool, &file_refs, false, false, &work_dir).unwrap();
+        let result = run_tool_fully(&tool, &file_refs, &options(false, false, &work_dir)).unwrap();
 
         // Should still run (even if arg might be too long for actual execution)
         // The important thing is we don't panic or create empty batches
         assert_eq!(result.batches.len(), 1);
     }
 
+    #[test]
+    fn test_create_batches_splits_on_custom_byte_limit() {
+        let tool = make_tool("test", "echo", &[]);
+        let files: Vec<PathBuf> = (0..4).map(|i| format!("file{}.txt", i).into()).collect();
+        let file_refs: Vec<&Path> = files.iter().map(|p| p.as_path()).collect();
+
+        // "echo" (5 bytes) plus two 10-byte paths fits in 25 bytes, a third doesn't
+        let batches = create_batches(&tool, &file_refs, &tool.args, 25);
+
+        assert_eq!(batches.len(), 2);
+        assert_eq!(batches[0].len(), 2);
+        assert_eq!(batches[1].len(), 2);
+    }
+
+    #[test]
+    fn test_create_batches_splits_on_file_count() {
+        let mut tool = make_tool("test", "echo", &[]);
+        tool.max_files_per_batch = Some(2);
+        let files: Vec<PathBuf> = (0..5).map(|i| format!("file{}.txt", i).into()).collect();
+        let file_refs: Vec<&Path> = files.iter().map(|p| p.as_path()).collect();
+
+        let batches = create_batches(&tool, &file_refs, &tool.args, MAX_BATCH_BYTES);
+
+        let sizes: Vec<usize> = batches.iter().map(|b| b.len()).collect();
+        assert_eq!(sizes, vec![2, 2, 1]);
+    }
+
+    #[test]
+    fn test_dry_run_commands_show_dir_env_and_files() {
+        let mut tool = make_tool("fmt", "fmt", &["--write"]);
+        tool.env
+            .insert("FMT_MODE".to_string(), "strict".to_string());
+        let files: Vec<PathBuf> = vec!["a.rs".into(), "b.rs".into()];
+        let file_refs: Vec<&Path> = files.iter().map(|p| p.as_path()).collect();
+        let work_dir = Path::new("/repo");
+
+        let commands =
+            dry_run_commands(&tool, &file_refs, &options(false, false, work_dir)).unwrap();
+
+        assert_eq!(
+            commands,
+            vec!["cd /repo && FMT_MODE=strict fmt --write a.rs b.rs"]
+        );
+    }
+
+    #[test]
+    fn test_extra_args_go_before_files() {
+        let extra = vec!["--verbose".to_string()];
+        assert_eq!(
+            with_extra_args(&["--write".to_string()], &extra),
+            vec!["--write", "--verbose"]
+        );
+        assert_eq!(
+            with_extra_args(
+                &[
+                    "--files".to_string(),
+                    FILES_PLACEHOLDER.to_string(),
+                    "--out".to_string()
+                ],
+                &extra
+            ),
+            vec!["--files", "--verbose", FILES_PLACEHOLDER, "--out"]
+        );
+    }
+
+    #[test]
+    fn test_multi_file_rejection_adds_per_file_hint() {
+        let tool = make_tool(
+            "single",
+            "sh",
+            &["-c", "echo 'Error: Too many arguments' >&2; exit 2", "sh"],
+        );
+        let files: Vec<PathBuf> = vec!["a.txt".into(), "b.txt".into()];
+        let file_refs: Vec<&Path> = files.iter().map(|p| p.as_path()).collect();
+        let work_dir = std::env::current_dir().unwrap();
+
+        let result = run_tool_fully(&tool, &file_refs, &options(false, false, &work_dir)).unwrap();
+        assert!(result.batches[0]
+            .stderr
+            .ends_with("Error: Too many arguments\nhint: 'single' may accept only one file at a time; try `per_file: true` for this tool"));
+
+        // A single file can't be the problem, so no hint
+        let result =
+            run_tool_fully(&tool, &file_refs[..1], &options(false, false, &work_dir)).unwrap();
+        assert!(!result.batches[0].stderr.contains("hint:"));
+    }
+
+    #[test]
+    fn test_shell_tool_receives_files_as_arguments() {
+        let mut tool = make_tool("snippet", "printf '<%s>' {files} && echo", &["--flag"]);
+        tool.shell = true;
+        let files: Vec<PathBuf> = vec!["a b.txt".into(), "c.txt".into()];
+        let file_refs: Vec<&Path> = files.iter().map(|p| p.as_path()).collect();
+        let work_dir = std::env::current_dir().unwrap();
+
+        let result = run_tool_fully(&tool, &file_refs, &options(false, false, &work_dir)).unwrap();
+
+        assert!(result.success);
+        assert_eq!(result.batches[0].stdout, "<--flag><a b.txt><c.txt>\n");
+        assert_eq!(program(&tool), "sh");
+    }
+
+    #[test]
+    fn test_per_file_runs_one_batch_per_file() {
+        let mut tool = make_tool("single", "echo", &[]);
+        tool.per_file = true;
+        let files: Vec<PathBuf> = (0..3).map(|i| format!("file{}.txt", i).into()).collect();
+        let file_refs: Vec<&Path> = files.iter().map(|p| p.as_path()).collect();
+        let work_dir = std::env::current_dir().unwrap();
+
+        let result = run_tool_fully(&tool, &file_refs, &options(false, false, &work_dir)).unwrap();
+
+        assert!(result.success);
+        assert_eq!(result.batches.len(), 3);
+        assert_eq!(result.batches[1].stdout.trim(), "file1.txt");
+    }
+
+    #[test]
+    fn test_command_args_appends_files_without_placeholder() {
+        let args = vec!["--write".to_string()];
+        let files = [Path::new("a.rs"), Path::new("b.rs")];
+
+        assert_eq!(command_args(&args, &files), vec!["--write", "a.rs", "b.rs"]);
+    }
+
+    #[test]
+    fn test_command_args_substitutes_placeholder() {
+        let args: Vec<String> = ["--files", "{files}", "--output", "x"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let files = [Path::new("a.rs"), Path::new("b.rs")];
+
+        assert_eq!(
+            command_args(&args, &files),
+            vec!["--files", "a.rs", "b.rs", "--output", "x"]
+        );
+    }
+
+    #[test]
+    fn test_placeholder_places_files_mid_command() {
+        let tool = make_tool(
+            "placeholder",
+            "sh",
+            &["-c", "echo \"$@\" done", "sh", "{files}"],
+        );
+        let files: Vec<PathBuf> = vec!["a.txt".into(), "b.txt".into()];
+        let file_refs: Vec<&Path> = files.iter().map(|p| p.as_path()).collect();
+        let work_dir = std::env::current_dir().unwrap();
+
+        let result = run_tool_fully(&tool, &file_refs, &options(true, false, &work_dir)).unwrap();
+
+        assert_eq!(result.batches[0].stdout.trim(), "a.txt b.txt done");
+        assert!(result.batches[0].command.ends_with("sh a.txt b.txt"));
+    }
+
+    #[test]
+    fn test_working_dir_runs_from_subdirectory_with_relative_paths() {
+        let repo = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(repo.path().join("frontend/src")).unwrap();
+        std::fs::write(repo.path().join("frontend/src/app.js"), "").unwrap();
+
+        let mut tool = make_tool(
+            "sub",
+            "sh",
+            &["-c", "pwd; test -f \"$1\" && echo \"$1\"", "sh"],
+        );
+        tool.working_dir = Some("frontend".to_string());
+        let files = [Path::new("frontend/src/app.js")];
+
+        let result = run_tool_fully(&tool, &files, &options(false, false, repo.path())).unwrap();
+
+        assert!(result.success, "{:?}", result.batches[0]);
+        let stdout = &result.batches[0].stdout;
+        assert!(stdout.contains("frontend\n"), "stdout: {stdout}");
+        assert!(stdout.contains("src/app.js"));
+    }
+
+    #[test]
+    fn test_absolute_paths_passes_paths_joined_to_work_dir() {
+        let repo = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(repo.path().join("frontend")).unwrap();
+
+        let mut tool = make_tool("abs", "echo", &[]);
+        tool.absolute_paths = true;
+        let files = [Path::new("src/a.java"), Path::new("frontend/b.java")];
+        let result = run_tool_fully(&tool, &files, &options(false, false, repo.path())).unwrap();
+
+        let expected = format!(
+            "{} {}",
+            repo.path().join("src/a.java").display(),
+            repo.path().join("frontend/b.java").display()
+        );
+        assert_eq!(result.batches[0].stdout.trim(), expected);
+
+        // Still absolute (and not rejected) when combined with working_dir
+        tool.working_dir = Some("frontend".to_string());
+        let result = run_tool_fully(&tool, &files, &options(false, false, repo.path())).unwrap();
+        assert_eq!(result.batches[0].stdout.trim(), expected);
+    }
+
+    #[test]
+    fn test_working_dir_rejects_files_outside_it() {
+        let mut tool = make_tool("sub", "echo", &[]);
+        tool.working_dir = Some("frontend".to_string());
+        let files = [Path::new("backend/main.go")];
+        let work_dir = std::env::current_dir().unwrap();
+
+        let err = run_tool_fully(&tool, &files, &options(false, false, &work_dir)).unwrap_err();
+
+        let message = format!("{err:#}");
+        assert!(message.contains("outside its working_dir"), "{message}");
+        assert!(message.contains("backend/main.go"), "{message}");
+    }
+
     #[test]
     fn test_batch_result_contains_command_when_verbose() {
         let tool = make_tool("test", "echo", &["--flag"]);
@@ -378,7 +1654,7 @@ mod tests {
         let file_refs: Vec<&Path> = files.iter().map(|p| p.as_path()).collect();
         let work_dir = std::env::current_dir().unwrap();
 
-        let result = run_tool(&tool, &file_refs, true, false, &work_dir).unwrap();
+        let result = run_tool_fully(&tool, &file_refs, &options(true, false, &work_dir)).unwrap();
 
         let cmd = &result.batches[0].command;
         assert!(cmd.contains("echo"));
@@ -393,7 +1669,7 @@ mod tests {
         let file_refs: Vec<&Path> = files.iter().map(|p| p.as_path()).collect();
         let work_dir = std::env::current_dir().unwrap();
 
-        let result = run_tool(&tool, &file_refs, false, false, &work_dir).unwrap();
+        let result = run_tool_fully(&tool, &file_refs, &options(false, false, &work_dir)).unwrap();
 
         // Command should be empty when not verbose
         assert!(result.batches[0].command.is_empty());
@@ -407,16 +1683,82 @@ mod tests {
         let work_dir = std::env::current_dir().unwrap();
 
         // Normal mode uses args
-        let result = run_tool(&tool, &file_refs, true, false, &work_dir).unwrap();
+        let result = run_tool_fully(&tool, &file_refs, &options(true, false, &work_dir)).unwrap();
         assert!(result.batches[0].command.contains("--write"));
         assert!(!result.batches[0].command.contains("--check"));
 
         // Check mode uses check_args
-        let result = run_tool(&tool, &file_refs, true, true, &work_dir).unwrap();
+        let result = run_tool_fully(&tool, &file_refs, &options(true, true, &work_dir)).unwrap();
         assert!(result.batches[0].command.contains("--check"));
         assert!(!result.batches[0].command.contains("--write"));
     }
 
+    #[test]
+    fn test_check_mode_invokes_check_args() {
+        let tool = make_tool_with_check_args("test", "echo", &["--write"], &["--check"]);
+        let files: Vec<PathBuf> = vec!["file.txt".into()];
+        let file_refs: Vec<&Path> = files.iter().map(|p| p.as_path()).collect();
+        let work_dir = std::env::current_dir().unwrap();
+
+        let result = run_tool_fully(&tool, &file_refs, &options(false, true, &work_dir)).unwrap();
+
+        assert_eq!(result.batches[0].stdout, "--check file.txt\n");
+    }
+
+    #[test]
+    fn test_check_mode_batches_by_check_args() {
+        let tool = make_tool_with_check_args("test", "echo", &[], &["--check-everything"]);
+        let files: Vec<PathBuf> = (0..4).map(|i| format!("file{}.txt", i).into()).collect();
+        let file_refs: Vec<&Path> = files.iter().map(|p| p.as_path()).collect();
+        let work_dir = std::env::current_dir().unwrap();
+        let options = RunOptions {
+            max_batch_bytes: 50,
+            ..options(false, true, &work_dir)
+        };
+
+        // "echo" (5) + "--check-everything" (19) leaves room for two 10-byte paths, not four
+        let batches = create_batches(&tool, &file_refs, &options.tool_args(&tool), 50);
+        assert_eq!(batches.len(), 2);
+
+        let result = run_tool_fully(&tool, &file_refs, &options).unwrap();
+        assert_eq!(result.batches.len(), 2);
+    }
+
+    #[test]
+    fn test_batch_results_keep_input_order_across_runs() {
+        let tool = make_tool("echo", "echo", &[]);
+        let files: Vec<PathBuf> = (0..12).map(|i| format!("dir{i}/file.txt").into()).collect();
+        let file_refs: Vec<&Path> = files.iter().map(|p| p.as_path()).collect();
+        let work_dir = std::env::current_dir().unwrap();
+        let options = RunOptions {
+            max_batch_bytes: 60,
+            ..options(true, false, &work_dir)
+        };
+
+        for strategy in [Strategy::ByTool, Strategy::ByBatch] {
+            let run = || {
+                let commands = Mutex::new(Vec::new());
+                run_tools(
+                    &[(&tool, file_refs.as_slice())],
+                    strategy,
+                    &options,
+                    || false,
+                    |_, result| {
+                        let result = result.unwrap().unwrap();
+                        *commands.lock().unwrap() =
+                            result.batches.into_iter().map(|b| b.command).collect();
+                    },
+                );
+                commands.into_inner().unwrap()
+            };
+
+            let first: Vec<String> = run();
+            assert!(first.len() > 1, "expected several batches: {first:?}");
+            assert!(first[0].contains("dir0/file.txt"));
+            assert_eq!(first, run());
+        }
+    }
+
     #[test]
     fn test_check_mode_falls_back_to_args() {
         let tool = make_tool("test", "echo", &["--write"]);
@@ -425,7 +1767,393 @@ mod tests {
         let work_dir = std::env::current_dir().unwrap();
 
         // Check mode without check_args should fall back to args
-        let result = run_tool(&tool, &file_refs, true, true, &work_dir).unwrap();
+        let result = run_tool_fully(&tool, &file_refs, &options(true, true, &work_dir)).unwrap();
         assert!(result.batches[0].command.contains("--write"));
     }
+
+    #[test]
+    fn test_strategies_produce_identical_results() {
+        let echo = make_tool("echo", "echo", &["formatted"]);
+        let fail = make_tool("fail", "false", &[]);
+        let echo_files: Vec<PathBuf> = vec!["a.txt".into(), "b.txt".into()];
+        let fail_files: Vec<PathBuf> = vec!["c.txt".into()];
+        let echo_refs: Vec<&Path> = echo_files.iter().map(|p| p.as_path()).collect();
+        let fail_refs: Vec<&Path> = fail_files.iter().map(|p| p.as_path()).collect();
+        let jobs: Vec<(&Tool, &[&Path])> = vec![(&echo, &echo_refs), (&fail, &fail_refs)];
+        let work_dir = std::env::current_dir().unwrap();
+        let opts = options(false, false, &work_dir);
+
+        let run = |strategy| {
+            let results = Mutex::new(Vec::new());
+            run_tools(
+                &jobs,
+                strategy,
+                &opts,
+                || false,
+                |idx, result| {
+                    let r = result.unwrap().unwrap();
+                    let stdout: Vec<String> = r.batches.iter().map(|b| b.stdout.clone()).collect();
+                    results.lock().unwrap().push((idx, r.success, stdout));
+                },
+            );
+            let mut results = results.into_inner().unwrap();
+            results.sort();
+            results
+        };
+
+        let by_tool = run(Strategy::ByTool);
+        let by_batch = run(Strategy::ByBatch);
+
+        assert_eq!(by_tool, by_batch);
+        assert_eq!(by_tool.len(), 2);
+        assert!(by_tool[0].1);
+        assert!(by_tool[0].2[0].contains("a.txt"));
+        assert!(!by_tool[1].1);
+    }
+
+    #[test]
+    fn test_by_batch_reports_skipped_tools_as_none() {
+        let tool = make_tool("echo", "echo", &[]);
+        let files: Vec<PathBuf> = vec!["a.txt".into()];
+        let file_refs: Vec<&Path> = files.iter().map(|p| p.as_path()).collect();
+        let jobs: Vec<(&Tool, &[&Path])> = vec![(&tool, &file_refs)];
+        let work_dir = std::env::current_dir().unwrap();
+        let skipped = Mutex::new(false);
+
+        run_tools(
+            &jobs,
+            Strategy::ByBatch,
+            &options(false, false, &work_dir),
+            || true,
+            |_, result| *skipped.lock().unwrap() = result.is_none(),
+        );
+
+        assert!(*skipped.lock().unwrap());
+    }
+
+    #[test]
+    fn test_ignore_exit_code_reports_success() {
+        let mut tool = make_tool("side-effect", "sh", &["-c", "echo regenerated; exit 3"]);
+        tool.ignore_exit_code = true;
+        let files: Vec<PathBuf> = vec!["file.txt".into()];
+        let file_refs: Vec<&Path> = files.iter().map(|p| p.as_path()).collect();
+        let work_dir = std::env::current_dir().unwrap();
+
+        let result = run_tool_fully(&tool, &file_refs, &options(false, false, &work_dir)).unwrap();
+
+        assert!(result.success);
+        assert!(result.batches[0].success);
+        assert!(result.batches[0].stdout.contains("regenerated"));
+    }
+
+    #[test]
+    fn test_success_exit_codes_apply_outside_issues_code_in_check_mode() {
+        let mut tool = make_tool("fixer", "sh", &["-c", "exit 1"]);
+        tool.success_exit_codes = Some(vec![0, 1]);
+        let files: Vec<PathBuf> = vec!["file.txt".into()];
+        let file_refs: Vec<&Path> = files.iter().map(|p| p.as_path()).collect();
+        let work_dir = std::env::current_dir().unwrap();
+
+        let result = run_tool_fully(&tool, &file_refs, &options(false, false, &work_dir)).unwrap();
+        assert!(result.success);
+
+        // Exit 1 is also the issues code, so check mode still fails
+        let result = run_tool_fully(&tool, &file_refs, &options(false, true, &work_dir)).unwrap();
+        assert!(!result.success);
+        assert!(result.issues_only());
+    }
+
+    #[test]
+    fn test_tool_env_is_applied() {
+        let mut tool = make_tool("env", "sh", &["-c", "echo \"edition=$FFX_TEST_EDITION\""]);
+        tool.env
+            .insert("FFX_TEST_EDITION".to_string(), "2021".to_string());
+        let files: Vec<PathBuf> = vec!["file.txt".into()];
+        let file_refs: Vec<&Path> = files.iter().map(|p| p.as_path()).collect();
+        let work_dir = std::env::current_dir().unwrap();
+
+        let result = run_tool_fully(&tool, &file_refs, &options(false, false, &work_dir)).unwrap();
+
+        assert!(result.batches[0].stdout.contains("edition=2021"));
+    }
+
+    #[test]
+    fn test_issues_only_distinguishes_issue_code_from_errors() {
+        let tool = make_tool("lint", "sh", &["-c", "exit 1"]);
+        let files: Vec<PathBuf> = vec!["file.txt".into()];
+        let file_refs: Vec<&Path> = files.iter().map(|p| p.as_path()).collect();
+        let work_dir = std::env::current_dir().unwrap();
+
+        let result = run_tool_fully(&tool, &file_refs, &options(false, true, &work_dir)).unwrap();
+        assert!(!result.success);
+        assert!(result.issues_only());
+
+        let crashing = make_tool("lint", "sh", &["-c", "exit 2"]);
+        let result =
+            run_tool_fully(&crashing, &file_refs, &options(false, true, &work_dir)).unwrap();
+        assert!(!result.success);
+        assert!(!result.issues_only());
+    }
+
+    #[test]
+    fn test_batch_records_exit_code() {
+        let tool = make_tool("lint", "sh", &["-c", "exit 2"]);
+        let files: Vec<PathBuf> = vec!["file.txt".into()];
+        let file_refs: Vec<&Path> = files.iter().map(|p| p.as_path()).collect();
+        let work_dir = std::env::current_dir().unwrap();
+
+        let result = run_tool_fully(&tool, &file_refs, &options(false, false, &work_dir)).unwrap();
+
+        assert_eq!(result.batches[0].exit_code, Some(2));
+        assert_eq!(result.batches[0].exit_description(), "exited with code 2");
+    }
+
+    #[test]
+    fn test_failed_batch_is_retried() {
+        let dir = tempfile::tempdir().unwrap();
+        let marker = dir.path().join("attempted");
+        let script = format!(
+            "if [ -e '{0}' ]; then exit 0; else touch '{0}'; exit 2; fi",
+            marker.display()
+        );
+        let mut tool = make_tool("flaky", "sh", &["-c", &script]);
+        tool.retries = 2;
+        let files: Vec<PathBuf> = vec!["file.txt".into()];
+        let file_refs: Vec<&Path> = files.iter().map(|p| p.as_path()).collect();
+        let work_dir = std::env::current_dir().unwrap();
+
+        let result = run_tool_fully(&tool, &file_refs, &options(false, false, &work_dir)).unwrap();
+
+        assert!(result.success);
+        assert_eq!(result.batches[0].retries, 1);
+        assert_eq!(
+            result.batches[0].exit_description(),
+            "exited with code 0 after 1 retry"
+        );
+    }
+
+    #[test]
+    fn test_issues_exit_code_is_not_retried() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = dir.path().join("runs.log");
+        let script = format!("echo run >> '{}'; exit 1", log.display());
+        let mut tool = make_tool("lint", "sh", &["-c", &script]);
+        tool.retries = 3;
+        let files: Vec<PathBuf> = vec!["file.txt".into()];
+        let file_refs: Vec<&Path> = files.iter().map(|p| p.as_path()).collect();
+        let work_dir = std::env::current_dir().unwrap();
+
+        let result = run_tool_fully(&tool, &file_refs, &options(false, true, &work_dir)).unwrap();
+
+        assert!(result.issues_only());
+        assert_eq!(result.batches[0].retries, 0);
+        assert_eq!(std::fs::read_to_string(&log).unwrap().lines().count(), 1);
+    }
+
+    #[test]
+    fn test_timeout_kills_long_running_batch() {
+        let mut tool = make_tool("slow", "sh", &["-c", "exec sleep 10"]);
+        tool.timeout = Some(1);
+        let files: Vec<PathBuf> = vec!["file.txt".into()];
+        let file_refs: Vec<&Path> = files.iter().map(|p| p.as_path()).collect();
+        let work_dir = std::env::current_dir().unwrap();
+
+        let start = Instant::now();
+        let result = run_tool_fully(&tool, &file_refs, &options(false, false, &work_dir)).unwrap();
+
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert!(!result.success);
+        assert!(!result.batches[0].issues_found);
+        assert_eq!(result.batches[0].exit_code, None);
+        assert!(result.batches[0].stderr.contains("timed out after 1s"));
+    }
+
+    #[test]
+    fn test_timeout_kills_processes_spawned_by_the_tool() {
+        // Without exec, sh forks sleep, which would hold the output pipes open
+        let mut tool = make_tool("wrapped", "sh", &["-c", "sleep 10; true", "sh"]);
+        tool.timeout = Some(1);
+        let files: Vec<PathBuf> = vec!["file.txt".into()];
+        let file_refs: Vec<&Path> = files.iter().map(|p| p.as_path()).collect();
+        let work_dir = std::env::current_dir().unwrap();
+
+        let start = Instant::now();
+        let result = run_tool_fully(&tool, &file_refs, &options(false, false, &work_dir)).unwrap();
+
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert!(!result.success);
+        assert!(result.batches[0].stderr.contains("timed out after 1s"));
+    }
+
+    #[test]
+    fn test_verbose_streams_and_still_captures_stderr() {
+        let tool = make_tool(
+            "noisy",
+            "sh",
+            &["-c", "echo one >&2; echo two >&2; echo out"],
+        );
+        let files: Vec<PathBuf> = vec!["file.txt".into()];
+        let file_refs: Vec<&Path> = files.iter().map(|p| p.as_path()).collect();
+        let work_dir = std::env::current_dir().unwrap();
+
+        let result = run_tool_fully(&tool, &file_refs, &options(true, false, &work_dir)).unwrap();
+
+        assert!(result.success);
+        assert_eq!(result.batches[0].stderr, "one\ntwo\n");
+        assert_eq!(result.batches[0].stdout.trim(), "out");
+    }
+
+    #[test]
+    fn test_by_batch_interleaves_batches_across_tools() {
+        // Each batch logs its start, sleeps, then logs its end. With two threads and a
+        // shared queue, a batch of the second tool starts before the first tool's
+        // batches have all finished.
+        let logging = |name: &str| {
+            let mut tool = make_tool(
+                name,
+                "sh",
+                &[
+                    "-c",
+                    &format!("echo '{name} start' >> log; sleep 0.2; echo '{name} end' >> log"),
+                ],
+            );
+            tool.per_file = true;
+            tool
+        };
+        let first = logging("first");
+        let second = logging("second");
+
+        let work_dir = tempfile::tempdir().unwrap();
+        let files: Vec<PathBuf> = vec!["a.txt".into(), "b.txt".into()];
+        let file_refs: Vec<&Path> = files.iter().map(|p| p.as_path()).collect();
+        let jobs: Vec<(&Tool, &[&Path])> = vec![(&first, &file_refs), (&second, &file_refs)];
+
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(2)
+            .build()
+            .unwrap();
+        pool.install(|| {
+            run_tools(
+                &jobs,
+                Strategy::ByBatch,
+                &options(false, false, work_dir.path()),
+                || false,
+                |_, result| assert!(result.unwrap().unwrap().success),
+            )
+        });
+
+        let log = std::fs::read_to_string(work_dir.path().join("log")).unwrap();
+        let first_end = log.find("first end").unwrap();
+        let second_start = log.find("second start").unwrap();
+        assert!(
+            second_start < first_end,
+            "expected interleaved batches:\n{log}"
+        );
+    }
+
+    #[test]
+    fn test_serial_tools_never_overlap() {
+        // Each serial run holds a lock directory; a concurrent run would fail to create it
+        let locking = "mkdir lock || exit 1; sleep 0.1; rmdir lock";
+        let mut first = make_tool("first", "sh", &["-c", locking]);
+        first.serial = true;
+        let mut second = make_tool("second", "sh", &["-c", locking]);
+        second.serial = true;
+        let parallel = make_tool("parallel", "echo", &[]);
+
+        let work_dir = tempfile::tempdir().unwrap();
+        let files: Vec<PathBuf> = vec!["file.txt".into()];
+        let file_refs: Vec<&Path> = files.iter().map(|p| p.as_path()).collect();
+        let jobs: Vec<(&Tool, &[&Path])> = vec![
+            (&first, &file_refs),
+            (&parallel, &file_refs),
+            (&second, &file_refs),
+        ];
+
+        for strategy in [Strategy::ByTool, Strategy::ByBatch] {
+            let order = Mutex::new(Vec::new());
+            run_tools(
+                &jobs,
+                strategy,
+                &options(false, false, work_dir.path()),
+                || false,
+                |idx, result| {
+                    assert!(result.unwrap().unwrap().success);
+                    order.lock().unwrap().push(idx);
+                },
+            );
+
+            assert_eq!(order.into_inner().unwrap(), vec![1, 0, 2]);
+        }
+    }
+
+    #[test]
+    fn test_timeout_captures_output_of_fast_command() {
+        let mut tool = make_tool("fast", "echo", &["hello"]);
+        tool.timeout = Some(5);
+        let files: Vec<PathBuf> = vec!["file.txt".into()];
+        let file_refs: Vec<&Path> = files.iter().map(|p| p.as_path()).collect();
+        let work_dir = std::env::current_dir().unwrap();
+
+        let result = run_tool_fully(&tool, &file_refs, &options(false, false, &work_dir)).unwrap();
+
+        assert!(result.success);
+        assert!(result.batches[0].stdout.contains("hello file.txt"));
+    }
+
+    #[test]
+    fn test_run_stdin_pipes_content_through_tool() {
+        let tool = make_tool("upper", "tr", &["a-z", "A-Z"]);
+        let work_dir = std::env::current_dir().unwrap();
+
+        let result = run_stdin(
+            &tool,
+            Path::new("src/main.rs"),
+            b"fn main() {}\n",
+            &options(false, false, &work_dir),
+        )
+        .unwrap();
+
+        assert!(!result.timed_out);
+        assert!(result.output.status.success());
+        assert_eq!(result.output.stdout, b"FN MAIN() {}\n");
+    }
+
+    #[test]
+    fn test_run_stdin_kills_tool_after_timeout() {
+        // Not exec'd, so sh's child sleep holds the pipes; large input fills stdin's buffer
+        let mut tool = make_tool("slow", "sh", &["-c", "sleep 10; cat", "sh"]);
+        tool.stdin = true;
+        tool.timeout = Some(1);
+        let work_dir = std::env::current_dir().unwrap();
+
+        let start = Instant::now();
+        let result = run_stdin(
+            &tool,
+            Path::new("src/main.rs"),
+            &vec![b'x'; 1024 * 1024],
+            &options(false, false, &work_dir),
+        )
+        .unwrap();
+
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert!(result.timed_out);
+        assert!(result.output.stdout.is_empty());
+    }
+
+    #[test]
+    fn test_run_stdin_passes_path_only_at_placeholder() {
+        let tool = make_tool("named", "sh", &["-c", "cat; echo \"$0\"", "{files}"]);
+        let work_dir = std::env::current_dir().unwrap();
+
+        let result = run_stdin(
+            &tool,
+            Path::new("src/main.rs"),
+            b"content\n",
+            &options(false, false, &work_dir),
+        )
+        .unwrap();
+
+        assert_eq!(result.output.stdout, b"content\nsrc/main.rs\n");
+    }
 }
@@ -7,53 +7,100 @@
 
 use anyhow::{Context, Result};
 use std::collections::BTreeSet;
-use std::path::PathBuf;
-use std::process::Command;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
 
-/// Get the root directory of the git repository.
+/// Run git with `args` from the current directory and return its stdout.
 ///
-/// Used to run formatters from the repo root, ensuring paths resolve correctly
-/// even when ffx is invoked from a subdirectory.
-pub fn repo_root() -> Result<PathBuf> {
+/// Runs with `core.quotePath=false` so non-ASCII paths come through literally
+/// instead of C-escaped and quoted, whatever the user's git config says.
+/// On failure the error names the exact command, e.g.
+/// `git diff --name-only -z --cached --diff-filter=d failed: <stderr>`.
+fn run_git(args: &[&str]) -> Result<String> {
+    let command = format!("git {}", args.join(" "));
     let output = Command::new("git")
-        .args(["rev-parse", "--show-toplevel"])
+        .args(["-c", "core.quotePath=false"])
+        .args(args)
         .output()
-        .context("Failed to run git rev-parse")?;
+        .with_context(|| format!("Failed to run {command}"))?;
 
     if !output.status.success() {
         let stderr = String::from_utf8_lossy(&output.stderr);
-        anyhow::bail!("Not a git repository: {}", stderr.trim());
+        anyhow::bail!("{command} failed: {}", stderr.trim());
     }
 
-    let path = String::from_utf8(output.stdout)
-        .context("Git output was not valid UTF-8")?
-        .trim()
-        .to_string();
+    String::from_utf8(output.stdout).context("Git output was not valid UTF-8")
+}
 
-    Ok(PathBuf::from(path))
+/// Get the root directory of the git repository.
+///
+/// Used to run formatters from the repo root, ensuring paths resolve correctly
+/// even when ffx is invoked from a subdirectory.
+pub fn repo_root() -> Result<PathBuf> {
+    let stdout = run_git(&["rev-parse", "--show-toplevel"])?;
+
+    Ok(PathBuf::from(stdout.trim()))
 }
 
-/// Get the current directory's path relative to the repo root.
+/// Get the absolute path of the repository's git directory (usually `<root>/.git`).
 ///
-/// Returns an empty string if at the repo root, otherwise returns the path
-/// with a trailing slash (e.g., "src/", "src/utils/").
-fn current_prefix() -> Result<String> {
+/// Used for ffx-private state such as the content-hash cache.
+pub fn git_dir() -> Result<PathBuf> {
+    let stdout = run_git(&["rev-parse", "--absolute-git-dir"])?;
+
+    Ok(PathBuf::from(stdout.trim()))
+}
+
+/// Get the directory git runs hooks from for the repository at `root`.
+///
+/// Honors `core.hooksPath` (as set by Husky and similar tools), which git resolves
+/// relative to the repo root; otherwise this is `<git dir>/hooks`.
+pub fn hooks_dir(root: &Path) -> Result<PathBuf> {
+    let stdout = run_git(&[
+        "-C",
+        &root.to_string_lossy(),
+        "rev-parse",
+        "--git-path",
+        "hooks",
+    ])?;
+
+    Ok(root.join(stdout.trim_end_matches('\n')))
+}
+
+/// Resolve the remote's default branch (e.g. "origin/main") from `refs/remotes/origin/HEAD`.
+///
+/// Used by `--base-auto` so callers don't need to know whether the mainline is
+/// `main` or `master`.
+pub fn default_base() -> Result<String> {
     let output = Command::new("git")
-        .args(["rev-parse", "--show-prefix"])
+        .args(["symbolic-ref", "--short", "refs/remotes/origin/HEAD"])
         .output()
-        .context("Failed to run git rev-parse --show-prefix")?;
+        .context("Failed to run git symbolic-ref")?;
 
     if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        anyhow::bail!("git rev-parse --show-prefix failed: {}", stderr.trim());
+        anyhow::bail!(
+            "Could not resolve origin/HEAD (try `git remote set-head origin --auto`): {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
     }
 
-    let prefix = String::from_utf8(output.stdout)
+    let base = String::from_utf8(output.stdout)
         .context("Git output was not valid UTF-8")?
         .trim()
         .to_string();
 
-    Ok(prefix)
+    Ok(base)
+}
+
+/// Get the current directory's path relative to the repo root.
+///
+/// Returns an empty string if at the repo root, otherwise returns the path
+/// with a trailing slash (e.g., "src/", "src/utils/").
+pub fn current_prefix() -> Result<String> {
+    let stdout = run_git(&["rev-parse", "--show-prefix"])?;
+
+    Ok(stdout.trim().to_string())
 }
 
 /// Prepend the current directory prefix to file paths.
@@ -86,29 +133,28 @@ fn filter_by_prefix(files: Vec<PathBuf>, prefix: &str) -> Vec<PathBuf> {
 /// Get all tracked files in the current directory (and subdirectories).
 ///
 /// Uses `git ls-files` to list all files tracked by git.
-/// This respects .gitignore and excludes untracked files.
+/// With `include_untracked`, untracked files that aren't gitignored are added too
+/// (`--others --exclude-standard`); otherwise untracked files are excluded.
 /// When run from a subdirectory, only returns files in that subdirectory.
-/// Returns paths relative to the repo root.
-pub fn all_files() -> Result<Vec<PathBuf>> {
+/// Returns sorted paths relative to the repo root.
+pub fn all_files(include_untracked: bool) -> Result<Vec<PathBuf>> {
     let prefix = current_prefix()?;
 
-    let output = Command::new("git")
-        .args(["ls-files"])
-        .output()
-        .context("Failed to run git ls-files")?;
-
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        anyhow::bail!("git ls-files failed: {}", stderr.trim());
-    }
-
-    let stdout = String::from_utf8(output.stdout).context("Git output was not valid UTF-8")?;
+    let stdout = if include_untracked {
+        run_git(&[
+            "ls-files",
+            "-z",
+            "--cached",
+            "--others",
+            "--exclude-standard",
+        ])?
+    } else {
+        run_git(&["ls-files", "-z"])?
+    };
 
-    let files: Vec<PathBuf> = stdout
-        .lines()
-        .filter(|line| !line.is_empty())
-        .map(PathBuf::from)
-        .collect();
+    let mut files = split_nul(&stdout);
+    files.sort();
+    files.dedup();
 
     Ok(prepend_prefix(files, &prefix))
 }
@@ -123,23 +169,9 @@ pub fn staged_files() -> Result<Vec<PathBuf>> {
     // --diff-filter=d excludes deleted files
     // --name-only shows only file paths
     // --cached shows staged (index) changes
-    let output = Command::new("git")
-        .args(["diff", "--name-only", "--cached", "--diff-filter=d"])
-        .output()
-        .context("Failed to run git diff")?;
+    let stdout = run_git(&["diff", "--name-only", "-z", "--cached", "--diff-filter=d"])?;
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        anyhow::bail!("git diff failed: {}", stderr.trim());
-    }
-
-    let stdout = String::from_utf8(output.stdout).context("Git output was not valid UTF-8")?;
-
-    let files: Vec<PathBuf> = stdout
-        .lines()
-        .filter(|line| !line.is_empty())
-        .map(PathBuf::from)
-        .collect();
+    let files = split_nul(&stdout);
 
     Ok(filter_by_prefix(files, &prefix))
 }
@@ -152,93 +184,250 @@ pub fn staged_files() -> Result<Vec<PathBuf>> {
 pub fn changed_files() -> Result<Vec<PathBuf>> {
     let prefix = current_prefix()?;
 
-    let output = Command::new("git")
-        .args(["status", "--porcelain=v1", "--untracked-files=normal"])
-        .output()
-        .context("Failed to run git status")?;
+    let stdout = run_git(&["status", "--porcelain=v1", "-z", "--untracked-files=normal"])?;
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        anyhow::bail!("git status failed: {}", stderr.trim());
-    }
+    let files = parse_porcelain_z(&stdout);
 
-    let stdout = String::from_utf8(output.stdout).context("Git output was not valid UTF-8")?;
-
-    // Use BTreeSet for deterministic ordering and deduplication
-    let mut files: BTreeSet<PathBuf> = BTreeSet::new();
-
-    for line in stdout.lines() {
-        if line.len() < 3 {
-            continue;
-        }
-
-        let status = &line[..2];
-        // Skip deleted files (either staged or unstaged)
-        if status.contains('D') {
-            continue;
-        }
-
-        let path_part = line[3..].trim();
+    Ok(filter_by_prefix(files, &prefix))
+}
 
-        // For renames, git status outputs "old -> new"; take the new path
-        let path_str = if let Some(idx) = path_part.rfind(" -> ") {
-            &path_part[idx + 4..]
-        } else {
-            path_part
-        };
+/// How `diff_files` compares a base ref with HEAD.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum DiffRange {
+    /// `<base>..HEAD`: every file that differs between the two commits, including
+    /// files changed on the base since the branch point. Needs no merge base.
+    TwoDot,
+    /// `<base>...HEAD`: only files changed on the current branch since it diverged
+    /// from the base (the merge base), as a pull request shows them
+    #[default]
+    ThreeDot,
+}
 
-        if path_str.is_empty() {
-            continue;
+impl DiffRange {
+    /// The revision range passed to `git diff`, e.g. `main...HEAD`.
+    fn spec(self, base_ref: &str) -> String {
+        match self {
+            DiffRange::TwoDot => format!("{base_ref}..HEAD"),
+            DiffRange::ThreeDot => format!("{base_ref}...HEAD"),
         }
-
-        files.insert(PathBuf::from(path_str));
     }
-
-    Ok(filter_by_prefix(files.into_iter().collect(), &prefix))
 }
 
 /// Get files changed between a base ref and HEAD.
 ///
-/// Uses three-dot diff syntax (`base...HEAD`) which shows changes
-/// introduced on the current branch since it diverged from base.
+/// `range` picks two-dot (`base..HEAD`, every difference between the commits) or
+/// three-dot (`base...HEAD`, changes introduced on the current branch since it
+/// diverged from base) semantics.
 /// Excludes deleted files.
 /// When run from a subdirectory, only returns files in that subdirectory.
 /// Returns paths relative to the repo root.
-pub fn diff_files(base_ref: &str) -> Result<Vec<PathBuf>> {
+pub fn diff_files(base_ref: &str, range: DiffRange) -> Result<Vec<PathBuf>> {
     let prefix = current_prefix()?;
 
-    // Three-dot diff: changes since branching from base
     // --diff-filter=d excludes deleted files
+    let stdout = run_git(&[
+        "diff",
+        "--name-only",
+        "-z",
+        "--diff-filter=d",
+        &range.spec(base_ref),
+    ])?;
+
+    let files = split_nul(&stdout);
+
+    Ok(filter_by_prefix(files, &prefix))
+}
+
+/// Get files changed by a single commit.
+///
+/// Uses `git diff-tree` against the commit's first parent (`--root` so the
+/// initial commit lists its files). Excludes deleted files.
+/// When run from a subdirectory, only returns files in that subdirectory.
+/// Returns paths relative to the repo root.
+pub fn commit_files(commit: &str) -> Result<Vec<PathBuf>> {
+    let prefix = current_prefix()?;
+
+    let stdout = run_git(&[
+        "diff-tree",
+        "--no-commit-id",
+        "--name-only",
+        "-r",
+        "-z",
+        "--root",
+        "--diff-filter=d",
+        commit,
+    ])?;
+
+    let files = split_nul(&stdout);
+
+    Ok(filter_by_prefix(files, &prefix))
+}
+
+/// Get the paths of submodules declared in `.gitmodules` (relative to the repo root).
+///
+/// Returns an empty list when there is no `.gitmodules` file.
+pub fn submodule_paths(root: &Path) -> Result<Vec<PathBuf>> {
+    if !root.join(".gitmodules").exists() {
+        return Ok(Vec::new());
+    }
+
     let output = Command::new("git")
         .args([
-            "diff",
-            "--name-only",
-            "--diff-filter=d",
-            &format!("{}...HEAD", base_ref),
+            "config",
+            "--file",
+            ".gitmodules",
+            "-z",
+            "--get-regexp",
+            r"^submodule\..*\.path$",
         ])
+        .current_dir(root)
         .output()
-        .context("Failed to run git diff")?;
+        .context("Failed to run git config")?;
 
+    // Exit code 1 means no submodule has a path entry
+    if output.status.code() == Some(1) {
+        return Ok(Vec::new());
+    }
     if !output.status.success() {
         let stderr = String::from_utf8_lossy(&output.stderr);
-        anyhow::bail!("git diff failed: {}", stderr.trim());
+        anyhow::bail!("git config failed: {}", stderr.trim());
     }
 
     let stdout = String::from_utf8(output.stdout).context("Git output was not valid UTF-8")?;
 
-    let files: Vec<PathBuf> = stdout
-        .lines()
-        .filter(|line| !line.is_empty())
+    // With -z each entry is `key\nvalue\0`
+    Ok(stdout
+        .split('\0')
+        .filter_map(|entry| entry.split_once('\n'))
+        .map(|(_, path)| PathBuf::from(path))
+        .collect())
+}
+
+/// Drop submodule gitlinks and any files inside submodule directories.
+pub fn exclude_submodules(files: Vec<PathBuf>, submodules: &[PathBuf]) -> Vec<PathBuf> {
+    files
+        .into_iter()
+        .filter(|f| !submodules.iter().any(|sub| f.starts_with(sub)))
+        .collect()
+}
+
+/// Stage the given files (paths relative to the repo root).
+///
+/// Paths are passed NUL-separated on stdin, so names with spaces or newlines
+/// are handled and long lists don't hit argument-length limits.
+pub fn add_files(root: &Path, files: &[&Path]) -> Result<()> {
+    if files.is_empty() {
+        return Ok(());
+    }
+
+    let mut input = Vec::new();
+    for file in files {
+        input.extend_from_slice(file.as_os_str().as_encoded_bytes());
+        input.push(0);
+    }
+
+    let mut child = Command::new("git")
+        .args(["add", "--pathspec-from-file=-", "--pathspec-file-nul"])
+        .current_dir(root)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("Failed to run git add")?;
+
+    child
+        .stdin
+        .take()
+        .context("Failed to open git add stdin")?
+        .write_all(&input)
+        .context("Failed to write paths to git add")?;
+
+    let output = child.wait_with_output().context("Failed to run git add")?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("git add failed: {}", stderr.trim());
+    }
+
+    Ok(())
+}
+
+/// Split NUL-delimited git output (from `-z`) into paths.
+///
+/// Paths are taken verbatim, so spaces, quotes, and newlines in filenames survive.
+fn split_nul(stdout: &str) -> Vec<PathBuf> {
+    stdout
+        .split('\0')
+        .filter(|entry| !entry.is_empty())
         .map(PathBuf::from)
-        .collect();
+        .collect()
+}
 
-    Ok(filter_by_prefix(files, &prefix))
+/// Parse `git status --porcelain=v1 -z` output into changed paths, skipping deletions.
+///
+/// Each record is `XY path`. Renames and copies are followed by an extra record
+/// holding the original path, which is skipped. Returns sorted, deduplicated paths.
+fn parse_porcelain_z(stdout: &str) -> Vec<PathBuf> {
+    // Use BTreeSet for deterministic ordering and deduplication
+    let mut files: BTreeSet<PathBuf> = BTreeSet::new();
+    let mut records = stdout.split('\0');
+
+    while let Some(record) = records.next() {
+        if record.len() < 4 {
+            continue;
+        }
+
+        let status = &record[..2];
+
+        // The original path of a rename or copy is a separate record
+        if status.contains('R') || status.contains('C') {
+            records.next();
+        }
+
+        // Skip deleted files (either staged or unstaged)
+        if status.contains('D') {
+            continue;
+        }
+
+        files.insert(PathBuf::from(&record[3..]));
+    }
+
+    files.into_iter().collect()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_run_git_error_names_command() {
+        let err = run_git(&["rev-parse", "--verify", "--quiet", "no-such-ref"]).unwrap_err();
+        assert!(
+            err.to_string()
+                .starts_with("git rev-parse --verify --quiet no-such-ref failed"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_exclude_submodules() {
+        let files = vec![
+            PathBuf::from("src/main.rs"),
+            PathBuf::from("vendor/lib"),
+            PathBuf::from("vendor/lib/file.rs"),
+            PathBuf::from("vendor/library.rs"),
+        ];
+
+        let result = exclude_submodules(files, &[PathBuf::from("vendor/lib")]);
+
+        assert_eq!(
+            result,
+            vec![
+                PathBuf::from("src/main.rs"),
+                PathBuf::from("vendor/library.rs")
+            ]
+        );
+    }
+
     #[test]
     fn test_repo_root_returns_path() {
         // This test only works when run inside a git repo
@@ -249,6 +438,39 @@ mod tests {
         assert!(path.join(".git").exists(), "Should have .git directory");
     }
 
+    #[test]
+    fn test_git_dir_is_inside_repo_root() {
+        // This test only works when run inside a git repo
+        let git_dir = git_dir().unwrap();
+        assert!(git_dir.is_absolute());
+        assert!(git_dir.ends_with(".git"));
+    }
+
+    #[test]
+    fn test_hooks_dir_honors_core_hooks_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let git = |args: &[&str]| {
+            let status = Command::new("git")
+                .args(args)
+                .current_dir(dir.path())
+                .output()
+                .unwrap()
+                .status;
+            assert!(status.success(), "git {args:?}");
+        };
+        git(&["init", "-q"]);
+        let root = dir.path().canonicalize().unwrap();
+
+        let default = hooks_dir(&root).unwrap();
+        assert_eq!(
+            default.canonicalize().ok(),
+            root.join(".git/hooks").canonicalize().ok()
+        );
+
+        git(&["config", "core.hooksPath", ".husky/_"]);
+        assert_eq!(hooks_dir(&root).unwrap(), root.join(".husky/_"));
+    }
+
     #[test]
     fn test_staged_files_returns_vec() {
         // This test only works when run inside a git repo
@@ -267,7 +489,7 @@ mod tests {
     #[test]
     fn test_all_files_returns_tracked_files() {
         // This test only works when run inside a git repo
-        let result = all_files();
+        let result = all_files(false);
         assert!(result.is_ok(), "Should get all files: {:?}", result);
         let files = result.unwrap();
         // Should have at least some files in a git repo
@@ -329,9 +551,49 @@ mod tests {
         // This test only works when run inside a git repo
         // It should at least not error with HEAD~1 (if commits exist)
         // May fail if repo has only one commit, but that's okay for this basic test
-        let result = diff_files("HEAD~1");
+        let result = diff_files("HEAD~1", DiffRange::ThreeDot);
         // Either succeeds or fails with an error (e.g., only one commit)
         // The important thing is it doesn't panic
         assert!(result.is_ok() || result.is_err());
     }
+
+    #[test]
+    fn test_diff_range_spec() {
+        assert_eq!(DiffRange::TwoDot.spec("main"), "main..HEAD");
+        assert_eq!(
+            DiffRange::ThreeDot.spec("origin/main"),
+            "origin/main...HEAD"
+        );
+    }
+
+    #[test]
+    fn test_split_nul_keeps_spaces_and_newlines() {
+        let files = split_nul("my file.txt\0src/line\nbreak.rs\0");
+        assert_eq!(
+            files,
+            vec![
+                PathBuf::from("my file.txt"),
+                PathBuf::from("src/line\nbreak.rs")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_porcelain_z() {
+        let stdout = concat!(
+            " M my file.txt\0",
+            "R  new name.rs\0old name.rs\0",
+            " D gone.rs\0",
+            "?? untracked.md\0",
+        );
+
+        assert_eq!(
+            parse_porcelain_z(stdout),
+            vec![
+                PathBuf::from("my file.txt"),
+                PathBuf::from("new name.rs"),
+                PathBuf::from("untracked.md"),
+            ]
+        );
+    }
 }
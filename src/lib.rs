@@ -0,0 +1,54 @@
+//! The formatting engine behind the `ffx` command, for embedding in other tools.
+//!
+//! The library provides the building blocks the `ffx` binary is made of:
+//!
+//! - [`config`]: load and validate `.fast-format-x.yaml` ([`config::Config::load`])
+//! - [`git`]: discover the repo root and staged, changed, or diffed files
+//! - [`matcher`]: assign each file to the first tool whose patterns match it
+//! - [`exec`]: run a tool's command on its files in batches ([`exec::run_tool`])
+//! - [`cache`]: skip files whose content was already formatted
+//! - [`report`]: render results as JSON, SARIF, JUnit, or a timing profile
+//! - [`watch`]: re-run on filesystem changes
+//!
+//! The rest of the `ffx` pipeline lives in the binary and is not exposed here:
+//! skipping files over `max_file_bytes`, binary files, and cached files; stdin
+//! formatting; re-staging formatted files; counting changed files; and printing
+//! output and summaries. Callers that need those steps implement them on top of
+//! these modules.
+//!
+//! Paths are repo-root-relative throughout; pass the repo root as `work_dir`.
+//!
+//! ```no_run
+//! use ffx::{config, exec, git, matcher};
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! let root = git::repo_root()?;
+//! let config = config::Config::load(&root.join(".fast-format-x.yaml"), &root)?;
+//! let files = git::staged_files()?;
+//!
+//! let options = exec::RunOptions {
+//!     verbose: false,
+//!     stream_stderr: false,
+//!     mode: config::Mode::Format,
+//!     work_dir: &root,
+//!     max_batch_bytes: exec::MAX_BATCH_BYTES,
+//!     extra_args: &[],
+//!     fail_fast: None,
+//!     lenient: false,
+//! };
+//! for m in matcher::match_files(&files, &config.tools, config.default_excludes)? {
+//!     if let Some(result) = exec::run_tool(m.tool, &m.files, &options) {
+//!         println!("{}: success={}", m.tool.name, result?.success);
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod cache;
+pub mod config;
+pub mod exec;
+pub mod git;
+pub mod matcher;
+pub mod report;
+pub mod watch;
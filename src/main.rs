@@ -1,99 +1,447 @@
-mod config;
-mod exec;
-mod git;
-mod matcher;
+use ffx::{cache, config, exec, git, matcher, report, watch};
 
 use anyhow::{Context, Result};
-use clap::{Parser, Subcommand};
-use colored::Colorize;
+use clap::{CommandFactory, Parser, Subcommand};
+use colored::{ColoredString, Colorize};
 use rayon::prelude::*;
+use regex::Regex;
 use std::collections::HashMap;
 use std::fs::{self, OpenOptions};
-use std::io::{stdout, IsTerminal, Write};
+use std::io::{self, stdout, IsTerminal, Read, Write};
+use std::num::NonZeroUsize;
 use std::path::{Path, PathBuf};
 use std::process::ExitCode;
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::mpsc;
-use std::time::Instant;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use tracing_subscriber::EnvFilter;
 
-use config::Config;
+use config::{Config, Mode};
+use report::ToolRun;
 
-/// Default config file name.
+/// Default config file name, written by `ffx init`.
 const CONFIG_FILE_NAME: &str = ".fast-format-x.yaml";
 
+/// Config file names tried, in order, in each directory when `--config` isn't given.
+const CONFIG_FILE_NAMES: &[&str] = &[CONFIG_FILE_NAME, ".ffx.yaml", ".ffx.yml"];
+
 /// One command to auto-format every changed file
-#[derive(Parser, Debug)]
+#[derive(Parser, Debug, Clone)]
 #[command(name = "ffx")]
 #[command(version)]
+#[command(args_override_self = true)]
+#[command(group(clap::ArgGroup::new("base_ref").args(["base", "base_auto"])))]
 #[command(about = "One command to auto-format every changed file. All formatters run in parallel.")]
 #[command(after_help = "\
 Examples:
   ffx                       Format changed files (uncommitted)
   ffx --staged              Format staged files only
   ffx --base origin/main    Format files changed vs origin/main
+  ffx --base-auto           Format files changed vs origin's default branch
   ffx --all                 Format all matching files
   ffx --all --check         Check all files (CI mode)
   ffx --check --base main   Check files changed vs main branch
+  ffx --diff                Show what formatting would change
   ffx --verbose             Show commands being run
+  ffx --list --staged       Preview which files each tool would format
+  ffx --config-check        Validate the config and list its tools
+  ffx --only prettier       Run a single tool
+  ffx --watch               Re-format changed files on every save
   ffx -j4                   Limit to 4 parallel jobs
+  ffx -j auto               Use one job per CPU core (the default)
 
 Exit codes:
   0  Success
-  1  Formatter failure
+  1  Formatter failure (every tool that ran failed)
   2  Config/general error
-  3  Missing executable")]
+  3  Missing executable
+  4  Partial failure (some tools failed, others passed)")]
 struct Cli {
     /// Initialize git hooks
     #[command(subcommand)]
     command: Option<Command>,
 
-    /// Run on all files matching config patterns
+    /// Run on all tracked files matching config patterns
     #[arg(long)]
     all: bool,
 
+    /// With --all, also include untracked files that aren't gitignored
+    #[arg(long, requires = "all", conflicts_with = "no_git")]
+    include_untracked: bool,
+
     /// Run only on staged files
     #[arg(long, conflicts_with = "base")]
     staged: bool,
 
+    /// After formatting staged files, `git add` the files that were formatted
+    #[arg(long, requires = "staged", conflicts_with_all = ["check", "diff"])]
+    restage: bool,
+
     /// Compare against a base ref (branch, tag, or commit)
-    /// Uses `git diff <base>...HEAD` to find changed files
+    /// Uses `git diff <base>...HEAD` to find changed files (see --diff-range)
     #[arg(long, value_name = "REF", conflicts_with_all = ["all", "staged"])]
     base: Option<String>,
 
+    /// Compare against the remote's default branch (resolved from origin/HEAD)
+    #[arg(long, conflicts_with_all = ["all", "staged", "base"])]
+    base_auto: bool,
+
+    /// How --base compares with HEAD: three-dot (changes on this branch since it
+    /// diverged) or two-dot (every difference between the two commits)
+    #[arg(long, value_enum, default_value_t = git::DiffRange::ThreeDot, requires = "base_ref")]
+    diff_range: git::DiffRange,
+
+    /// Read newline-separated, repo-root-relative paths from a file ("-" for stdin)
+    /// instead of asking git
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["all", "staged", "base", "base_auto", "watch"])]
+    files_from: Option<String>,
+
+    /// Format content read from stdin and write the result to stdout (for editors);
+    /// needs --stdin-filename to pick the tool
+    #[arg(long, requires = "stdin_filename", conflicts_with_all = ["all", "staged", "base", "base_auto", "files_from", "changed_in", "since", "check", "diff", "fix_and_check", "list", "dry_run", "watch"])]
+    stdin: bool,
+
+    /// Path the stdin content belongs to, matched against tool patterns (with --stdin)
+    #[arg(long, value_name = "PATH", requires = "stdin")]
+    stdin_filename: Option<PathBuf>,
+
+    /// Run on the files changed by a single commit (e.g. HEAD)
+    #[arg(long, value_name = "REF", conflicts_with_all = ["all", "staged", "base", "base_auto", "files_from", "watch"])]
+    changed_in: Option<String>,
+
+    /// Run on tracked files modified on disk within this long (e.g. 30m, 2h, 1d), ignoring git state
+    #[arg(long, value_name = "DURATION", value_parser = parse_since, conflicts_with_all = ["all", "staged", "base", "base_auto", "files_from", "changed_in"])]
+    since: Option<Duration>,
+
+    /// Run outside a git repository from the current directory (with --all, --files-from, or --since)
+    #[arg(long, conflicts_with_all = ["staged", "base", "base_auto", "changed_in", "watch"])]
+    no_git: bool,
+
+    /// Also format files inside git submodules (skipped by default)
+    #[arg(long, conflicts_with = "no_git")]
+    include_submodules: bool,
+
     /// Check mode for CI (use check_args instead of args, no file modifications)
     #[arg(long)]
     check: bool,
 
-    /// Path to config file
-    #[arg(long, default_value = CONFIG_FILE_NAME)]
-    config: String,
+    /// Show what formatting would change without writing (uses each tool's diff_args)
+    #[arg(long, conflicts_with = "check")]
+    diff: bool,
+
+    /// Format, then re-run every tool in check mode and fail if anything still needs
+    /// formatting (catches formatters that aren't idempotent)
+    #[arg(long, conflicts_with_all = ["check", "diff", "list", "dry_run", "watch"])]
+    fix_and_check: bool,
+
+    /// In check mode, print GitHub Actions `::error` annotations parsed from failing
+    /// tools' output (tools need an annotation_pattern)
+    #[arg(long, requires = "check")]
+    github_annotations: bool,
+
+    /// How strictly check mode fails: on any issue, or only when a tool errors
+    #[arg(long, value_enum, default_value_t = CheckLevel::Strict, requires = "check")]
+    check_level: CheckLevel,
 
-    /// Max parallel processes (minimum 1)
-    #[arg(long, short = 'j', default_value_t = num_cpus(), value_parser = clap::value_parser!(u64).range(1..))]
-    jobs: u64,
+    /// Path to config file [default: .fast-format-x.yaml, .ffx.yaml, or .ffx.yml]
+    #[arg(long, value_name = "PATH")]
+    config: Option<String>,
 
-    /// Stop on first failure
+    /// Validate the config (options, tool names, glob patterns), list its tools, and
+    /// exit without running anything (exit code 2 if invalid)
+    #[arg(long)]
+    config_check: bool,
+
+    /// Max parallel processes, or "auto"/0 for one per CPU core
+    #[arg(long, short = 'j', default_value = "auto", value_parser = parse_jobs)]
+    jobs: usize,
+
+    /// Run only the named tool (repeatable)
+    #[arg(long, value_name = "NAME", conflicts_with = "skip")]
+    only: Vec<String>,
+
+    /// Skip the named tool (repeatable)
+    #[arg(long, value_name = "NAME")]
+    skip: Vec<String>,
+
+    /// Ignore the content-hash cache and run on every matched file
+    #[arg(long)]
+    no_cache: bool,
+
+    /// Print which files each tool would receive, then exit without running
+    #[arg(long)]
+    list: bool,
+
+    /// Print each batch's full command line (directory, env, args, files) without running it
+    #[arg(long, conflicts_with_all = ["list", "watch"])]
+    dry_run: bool,
+
+    /// With --all, warn about configured tools whose patterns matched no files
+    #[arg(long, requires = "all")]
+    warn_unused_tools: bool,
+
+    /// Print failures as usual but exit 0 (for advisory CI jobs that report without blocking)
+    #[arg(long)]
+    no_fail: bool,
+
+    /// Stop on first failure, killing batches that are still running
     #[arg(long)]
     fail_fast: bool,
 
+    /// Stop starting new tools once this many have failed (running tools finish)
+    #[arg(long, value_name = "N", conflicts_with = "fail_fast")]
+    max_failures: Option<NonZeroUsize>,
+
+    /// Keep running and re-format whenever files change (Ctrl-C to stop)
+    #[arg(long, conflicts_with = "list")]
+    watch: bool,
+
+    /// Maximum bytes of arguments per formatter invocation (raise for fewer, larger batches)
+    #[arg(long, value_name = "BYTES", default_value_t = exec::MAX_BATCH_BYTES)]
+    max_batch_bytes: usize,
+
+    /// How to schedule work: all batches in one shared queue, or per tool
+    #[arg(long, value_enum, default_value_t = exec::Strategy::ByBatch)]
+    strategy: exec::Strategy,
+
+    /// Run tools one at a time in config order, so later tools see earlier tools' writes
+    #[arg(long, conflicts_with = "strategy")]
+    sequential: bool,
+
+    /// Run everything on one thread, tools in config order and batches one at a time,
+    /// for debugging tools that misbehave under concurrency
+    #[arg(long, conflicts_with_all = ["jobs", "strategy", "sequential"])]
+    no_parallel: bool,
+
     /// Show commands and detailed output
     #[arg(long, short = 'v')]
     verbose: bool,
+
+    /// Print nothing unless something fails
+    #[arg(long, short = 'q', conflicts_with = "verbose")]
+    quiet: bool,
+
+    /// When to use colored output
+    #[arg(long, value_enum, default_value_t = ColorChoice::Auto)]
+    color: ColorChoice,
+
+    /// Write each tool's batch count, file count, and duration as JSON to this file
+    #[arg(long, value_name = "PATH")]
+    profile: Option<PathBuf>,
+
+    /// Output format: human-readable text, or a single JSON report on stdout
+    #[arg(long, value_enum, default_value_t = OutputFormat::Human)]
+    format: OutputFormat,
+
+    /// Extra arguments appended to every tool's args, before the file paths
+    /// (e.g. `ffx -- --verbose`; not every tool accepts the same flags)
+    #[arg(last = true, value_name = "TOOL_ARGS")]
+    tool_args: Vec<String>,
+}
+
+impl Cli {
+    /// Which set of tool arguments this run uses.
+    fn mode(&self) -> Mode {
+        if self.diff {
+            Mode::Diff
+        } else if self.check {
+            Mode::Check
+        } else {
+            Mode::Format
+        }
+    }
+
+    /// Size of the thread pool: `--jobs`, or 1 with `--no-parallel`.
+    fn jobs(&self) -> usize {
+        if self.no_parallel {
+            1
+        } else {
+            self.jobs
+        }
+    }
+
+    /// How tools are scheduled: `--strategy`, or in config order with `--sequential`
+    /// or `--no-parallel`.
+    fn strategy(&self) -> exec::Strategy {
+        if self.sequential || self.no_parallel {
+            exec::Strategy::Sequential
+        } else {
+            self.strategy
+        }
+    }
+}
+
+/// How run results are written to stdout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum OutputFormat {
+    /// Status lines and a colored summary
+    Human,
+    /// A single JSON object with per-tool results
+    Json,
+    /// A SARIF 2.1.0 log of failing tools (requires --check)
+    Sarif,
+    /// JUnit XML with one testcase per tool, for CI test-result viewers
+    Junit,
 }
 
-#[derive(Debug, Subcommand)]
+/// When to emit ANSI colors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum ColorChoice {
+    /// Color when stdout is a terminal, unless NO_COLOR or FORCE_COLOR/CLICOLOR_FORCE is set
+    Auto,
+    /// Always color, even when piped
+    Always,
+    /// Never color
+    Never,
+}
+
+/// Exit behavior for check mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum CheckLevel {
+    /// Fail when any file needs formatting
+    Strict,
+    /// Report files needing formatting as warnings; fail only when a tool errors
+    Lenient,
+}
+
+/// Git hook installed by `ffx init`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum Hook {
+    /// Format staged files and re-stage them before each commit
+    PreCommit,
+    /// Check files changed vs the upstream branch before each push
+    PrePush,
+}
+
+impl Hook {
+    /// File name in the hooks directory (`.git/hooks` unless `core.hooksPath` is set).
+    fn file_name(self) -> &'static str {
+        match self {
+            Hook::PreCommit => "pre-commit",
+            Hook::PrePush => "pre-push",
+        }
+    }
+
+    /// Capitalized name for messages.
+    fn label(self) -> &'static str {
+        match self {
+            Hook::PreCommit => "Pre-commit",
+            Hook::PrePush => "Pre-push",
+        }
+    }
+
+    /// Shell script written to the hook file.
+    fn script(self) -> &'static str {
+        match self {
+            Hook::PreCommit => PRE_COMMIT_HOOK,
+            Hook::PrePush => PRE_PUSH_HOOK,
+        }
+    }
+
+    /// The hook script as a marked section for the end of an existing hook (for `--append`).
+    fn appended_section(self) -> String {
+        let body = self
+            .script()
+            .split_once('\n')
+            .map_or(self.script(), |(_, body)| body);
+        format!("\n# {HOOK_SECTION_MARKER}\n{body}")
+    }
+
+    /// What the installed hook does, for the confirmation message.
+    fn summary(self) -> &'static str {
+        match self {
+            Hook::PreCommit => "It will run ffx on staged files before each commit.",
+            Hook::PrePush => "It will check files changed vs the upstream branch before each push.",
+        }
+    }
+}
+
+/// Stack-specific config templates for `ffx init`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum Language {
+    /// rustfmt and clippy
+    Rust,
+    /// Prettier and ESLint
+    Node,
+    /// Black and Ruff
+    Python,
+}
+
+impl Language {
+    /// Detect the project's stack from its manifest in `dir`.
+    fn detect(dir: &Path) -> Option<Self> {
+        [
+            ("Cargo.toml", Self::Rust),
+            ("package.json", Self::Node),
+            ("pyproject.toml", Self::Python),
+        ]
+        .into_iter()
+        .find(|(manifest, _)| dir.join(manifest).exists())
+        .map(|(_, language)| language)
+    }
+
+    fn template(self) -> &'static str {
+        match self {
+            Self::Rust => RUST_CONFIG_TEMPLATE,
+            Self::Node => NODE_CONFIG_TEMPLATE,
+            Self::Python => PYTHON_CONFIG_TEMPLATE,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Subcommand)]
 enum Command {
-    /// Install the pre-commit hook to run ffx automatically
-    Init,
+    /// Install a git hook to run ffx automatically
+    Init {
+        /// Which hook to install: pre-commit formats staged files, pre-push checks the branch
+        #[arg(long, value_enum, default_value_t = Hook::PreCommit)]
+        hook: Hook,
+
+        /// Scaffold a config for this stack (detected from Cargo.toml, package.json,
+        /// or pyproject.toml when omitted)
+        #[arg(long, value_enum)]
+        language: Option<Language>,
+
+        /// Add ffx to the end of an existing hook script instead of refusing to touch it
+        #[arg(long)]
+        append: bool,
+    },
     /// Update ffx to the latest version
     Update {
         /// Check for updates without installing
         #[arg(long)]
         check: bool,
+
+        /// Install this release (e.g. 0.1.22) instead of the latest; with --check,
+        /// only report whether it differs from the current version
+        #[arg(long, value_name = "VERSION", value_parser = parse_pin_version)]
+        pin: Option<String>,
+
+        /// Skip all network access and exit successfully (also set by FFX_OFFLINE=1),
+        /// for air-gapped environments
+        #[arg(long)]
+        offline: bool,
+    },
+    /// Check that every configured tool's command is installed and show its version
+    Doctor,
+    /// Show where the content-hash cache lives and how many files it holds
+    Cache {
+        /// Delete the cache so every file is re-formatted on the next run
+        #[arg(long)]
+        clear: bool,
+    },
+    /// Print shell completions to stdout (e.g. `ffx completions zsh > _ffx`)
+    #[command(hide = true)]
+    Completions {
+        /// Shell to generate completions for
+        shell: clap_complete::Shell,
     },
 }
 
 fn main() -> ExitCode {
+    init_tracing();
+
     match run() {
         Ok(outcome) => exit_code_from_outcome(&outcome),
         Err(e) => {
@@ -103,11 +451,33 @@ fn main() -> ExitCode {
     }
 }
 
+/// Emit diagnostic spans and events to stderr when `RUST_LOG` is set (e.g.
+/// `RUST_LOG=ffx=debug`). Normal output is unaffected.
+fn init_tracing() {
+    if let Some(filter) = log_filter(std::env::var("RUST_LOG").ok().as_deref()) {
+        tracing_subscriber::fmt()
+            .with_env_filter(filter)
+            .with_writer(io::stderr)
+            .with_ansi(io::stderr().is_terminal())
+            .init();
+    }
+}
+
+/// The tracing filter for a `RUST_LOG` value, or `None` to leave tracing off.
+fn log_filter(value: Option<&str>) -> Option<EnvFilter> {
+    value
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(EnvFilter::new)
+}
+
 fn exit_code_from_outcome(outcome: &RunOutcome) -> ExitCode {
     if outcome.success {
         ExitCode::SUCCESS
     } else if outcome.missing_executable {
         ExitCode::from(3)
+    } else if outcome.partial {
+        ExitCode::from(4)
     } else {
         ExitCode::from(1)
     }
@@ -116,6 +486,8 @@ fn exit_code_from_outcome(outcome: &RunOutcome) -> ExitCode {
 struct RunOutcome {
     success: bool,
     missing_executable: bool,
+    /// Some tools failed while others passed (exit code 4 instead of 1)
+    partial: bool,
 }
 
 impl RunOutcome {
@@ -123,6 +495,7 @@ impl RunOutcome {
         Self {
             success: true,
             missing_executable: false,
+            partial: false,
         }
     }
 
@@ -130,6 +503,7 @@ impl RunOutcome {
         Self {
             success: false,
             missing_executable: true,
+            partial: false,
         }
     }
 
@@ -137,70 +511,513 @@ impl RunOutcome {
         Self {
             success,
             missing_executable: false,
+            partial: false,
+        }
+    }
+
+    /// Outcome of a run where `passed` tools passed and `failed` tools failed.
+    fn from_tool_counts(passed: usize, failed: usize) -> Self {
+        Self {
+            success: failed == 0,
+            missing_executable: false,
+            partial: failed > 0 && passed > 0,
         }
     }
 }
 
+/// Whether to force colors on or off, or `None` to leave it to TTY detection.
+///
+/// `--color always/never` wins; otherwise a non-empty `NO_COLOR` disables colors,
+/// and `FORCE_COLOR` or `CLICOLOR_FORCE` (non-empty and not "0") enables them.
+fn color_override(choice: ColorChoice, env: impl Fn(&str) -> Option<String>) -> Option<bool> {
+    let set = |name: &str| env(name).is_some_and(|value| !value.is_empty());
+    let forced = |name: &str| env(name).is_some_and(|value| !value.is_empty() && value != "0");
+
+    match choice {
+        ColorChoice::Always => Some(true),
+        ColorChoice::Never => Some(false),
+        ColorChoice::Auto if set("NO_COLOR") => Some(false),
+        ColorChoice::Auto if forced("FORCE_COLOR") || forced("CLICOLOR_FORCE") => Some(true),
+        ColorChoice::Auto => None,
+    }
+}
+
 fn run() -> Result<RunOutcome> {
     let start = Instant::now();
     let cli = Cli::parse();
 
+    if let Some(enabled) = color_override(cli.color, |name| std::env::var(name).ok()) {
+        colored::control::set_override(enabled);
+    }
+
     match cli.command {
-        Some(Command::Init) => {
-            run_init()?;
+        Some(Command::Init {
+            hook,
+            language,
+            append,
+        }) => {
+            run_init(hook, language, append)?;
+            return Ok(RunOutcome::success());
+        }
+        Some(Command::Update {
+            check,
+            ref pin,
+            offline,
+        }) => {
+            if is_offline(offline, |name| std::env::var(name).ok()) {
+                println!("Update checks disabled in offline mode (--offline or FFX_OFFLINE).");
+                return Ok(RunOutcome::success());
+            }
+            run_update(check, pin.as_deref())?;
             return Ok(RunOutcome::success());
         }
-        Some(Command::Update { check }) => {
-            run_update(check)?;
+        Some(Command::Doctor) => return run_doctor(cli.config.as_deref()),
+        Some(Command::Cache { clear }) => {
+            run_cache(clear)?;
+            return Ok(RunOutcome::success());
+        }
+        Some(Command::Completions { shell }) => {
+            clap_complete::generate(shell, &mut Cli::command(), "ffx", &mut stdout());
             return Ok(RunOutcome::success());
         }
         None => {}
     }
 
+    if cli.config_check {
+        return run_config_check(&cli);
+    }
+
+    if cli.format == OutputFormat::Sarif && !cli.check {
+        anyhow::bail!("--format sarif requires --check");
+    }
+    if cli.fix_and_check && cli.format != OutputFormat::Human {
+        anyhow::bail!("--fix-and-check only supports --format human");
+    }
+
     // Configure parallelism
-    exec::configure_parallelism(cli.jobs as usize)?;
+    exec::configure_parallelism(cli.jobs())?;
 
     // Get repo root to run formatters from (ensures paths resolve correctly from subdirs)
-    let repo_root = git::repo_root().context("Failed to find git repository root")?;
-
-    // Load config - try current directory first, then repo root for default config
-    let config_path = Path::new(&cli.config);
-    let config = if config_path.exists() {
-        Config::load(config_path)
-    } else if cli.config == CONFIG_FILE_NAME {
-        // Default config file - try repo root
-        let repo_config_path = repo_root.join(CONFIG_FILE_NAME);
-        Config::load(&repo_config_path)
+    let repo_root = if cli.no_git {
+        if !cli.all && cli.files_from.is_none() && cli.since.is_none() {
+            anyhow::bail!("--no-git requires --all, --files-from, or --since");
+        }
+        std::env::current_dir().context("Failed to get current directory")?
     } else {
-        // Explicitly specified config file - use as-is (will fail with proper error)
-        Config::load(config_path)
-    }
-    .with_context(|| format!("Failed to load config from {}", cli.config))?;
+        git::repo_root().context("Failed to find git repository root")?
+    };
+
+    let config = load_config(cli.config.as_deref(), &repo_root)?;
+
+    ensure_known_tools(&cli.only, &config)?;
+    ensure_known_tools(&cli.skip, &config)?;
 
     if cli.verbose {
         eprintln!("repo root: {}", repo_root.display());
-        eprintln!("config: {} ({} tools)", cli.config, config.tools.len());
-        eprintln!("jobs: {}", cli.jobs);
-        if cli.check {
-            eprintln!("mode: check (no modifications)");
+        eprintln!(
+            "config: {} ({} tools)",
+            resolve_config_path(cli.config.as_deref(), &repo_root).display(),
+            config.tools.len()
+        );
+        let disabled = config.tools.iter().filter(|t| t.disabled).count();
+        if disabled > 0 {
+            eprintln!(
+                "skipped {} disabled {}",
+                disabled,
+                pluralize_tools(disabled)
+            );
+        }
+        eprintln!("jobs: {}", cli.jobs());
+        match cli.mode() {
+            Mode::Check => eprintln!("mode: check (no modifications)"),
+            Mode::Diff => eprintln!("mode: diff (no modifications)"),
+            Mode::Format => {}
         }
         eprintln!();
     }
 
+    // Compiled once so --watch doesn't rebuild every glob on each change
+    let matchers = matcher::build_matchers(&config.tools, config.default_excludes)
+        .context("Failed to compile tool patterns")?;
+
+    // Watch mode installs its own handler to stop watching
+    if !cli.watch {
+        install_interrupt_handler()?;
+    }
+
+    if let Some(path) = &cli.stdin_filename {
+        return format_stdin(&cli, &matchers, &repo_root, path);
+    }
+
+    if cli.watch {
+        watch::watch(&repo_root, || {
+            format_files(&cli, &config, &matchers, &repo_root, Instant::now()).map(|_| ())
+        })?;
+        return Ok(RunOutcome::success());
+    }
+
+    let outcome = if cli.fix_and_check {
+        fix_and_check(&cli, &config, &matchers, &repo_root, start)?
+    } else {
+        format_files(&cli, &config, &matchers, &repo_root, start)?
+    };
+
+    // --no-fail reports failures without failing; a missing executable is still a setup error
+    if cli.no_fail && !outcome.missing_executable {
+        return Ok(RunOutcome::success());
+    }
+
+    Ok(outcome)
+}
+
+/// On Ctrl-C, interrupt tools running in their own process group (see
+/// `exec::interrupt_running`) before exiting with the conventional 130.
+fn install_interrupt_handler() -> Result<()> {
+    ctrlc::set_handler(|| {
+        exec::interrupt_running();
+        std::process::exit(130);
+    })
+    .context("Failed to install Ctrl-C handler")
+}
+
+/// Format, then re-run every tool in check mode (for --fix-and-check).
+fn fix_and_check(
+    cli: &Cli,
+    config: &Config,
+    matchers: &matcher::ToolMatchers,
+    repo_root: &Path,
+    start: Instant,
+) -> Result<RunOutcome> {
+    let fixed = format_files(cli, config, matchers, repo_root, start)?;
+    if !fixed.success {
+        return Ok(fixed);
+    }
+
+    if !cli.quiet {
+        println!();
+    }
+    let check_cli = Cli {
+        check: true,
+        ..cli.clone()
+    };
+    format_files(&check_cli, config, matchers, repo_root, Instant::now())
+}
+
+/// Format content from stdin as if it were `path` and write the result to stdout (for --stdin).
+///
+/// Content no tool matches is echoed unchanged. When the tool fails, nothing is
+/// written to stdout, so an editor never replaces a buffer with partial output.
+fn format_stdin(
+    cli: &Cli,
+    matchers: &matcher::ToolMatchers,
+    repo_root: &Path,
+    path: &Path,
+) -> Result<RunOutcome> {
+    let mut input = Vec::new();
+    io::stdin()
+        .read_to_end(&mut input)
+        .context("Failed to read stdin")?;
+
+    let file = stdin_file_path(
+        &std::env::current_dir().context("Failed to get current directory")?,
+        repo_root,
+        path,
+    )?;
+    let files = [file];
+    let matches = filter_tools(matcher::match_with(matchers, &files), &cli.only, &cli.skip);
+
+    let Some(m) = matches.first() else {
+        stdout()
+            .write_all(&input)
+            .context("Failed to write stdout")?;
+        return Ok(RunOutcome::success());
+    };
+    if !m.tool.stdin {
+        anyhow::bail!(
+            "Tool '{}' matches {} but isn't configured to read stdin (set `stdin: true` if it can)",
+            m.tool.name,
+            files[0].display()
+        );
+    }
+    if let Some(outcome) = ensure_required_commands(&matches) {
+        return Ok(outcome);
+    }
+
+    let options = exec::RunOptions {
+        verbose: cli.verbose,
+        stream_stderr: false,
+        mode: Mode::Format,
+        work_dir: repo_root,
+        max_batch_bytes: cli.max_batch_bytes,
+        extra_args: &cli.tool_args,
+        fail_fast: None,
+        lenient: false,
+    };
+    let exec::StdinOutput { output, timed_out } =
+        exec::run_stdin(m.tool, &files[0], &input, &options)?;
+
+    // A timed-out tool's partial output is never written, even with ignore_exit_code
+    if timed_out
+        || (!m.tool.is_success_code(output.status.code(), Mode::Format) && !m.tool.ignore_exit_code)
+    {
+        io::stderr().write_all(&output.stderr)?;
+        let exit = match output.status.code() {
+            _ if timed_out => format!("timed out after {}s", m.tool.timeout.unwrap_or_default()),
+            Some(code) => format!("exited with code {code}"),
+            None => "was terminated by a signal".to_string(),
+        };
+        eprintln!("error: tool '{}' {exit}", m.tool.name);
+        return Ok(RunOutcome::from_success(false));
+    }
+    if cli.verbose {
+        io::stderr().write_all(&output.stderr)?;
+    }
+
+    stdout()
+        .write_all(&output.stdout)
+        .context("Failed to write stdout")?;
+    Ok(RunOutcome::success())
+}
+
+/// Resolve `--stdin-filename` (absolute, or relative to `cwd`) to a repo-root-relative path.
+///
+/// `.` and `..` are resolved lexically, so `../src/a.rs` from a subdirectory still
+/// matches the tools for `src/a.rs`; the file itself needn't exist.
+fn stdin_file_path(cwd: &Path, repo_root: &Path, path: &Path) -> Result<PathBuf> {
+    let mut absolute = PathBuf::new();
+    for component in cwd.join(path).components() {
+        match component {
+            std::path::Component::CurDir => {}
+            std::path::Component::ParentDir => {
+                absolute.pop();
+            }
+            other => absolute.push(other),
+        }
+    }
+    absolute
+        .strip_prefix(repo_root)
+        .map(Path::to_path_buf)
+        .with_context(|| {
+            format!(
+                "--stdin-filename {} is outside the repository at {}",
+                path.display(),
+                repo_root.display()
+            )
+        })
+}
+
+/// Path of this repository's cache file, honoring `FFX_CACHE_DIR`.
+fn cache_file_path(repo_root: &Path) -> Result<PathBuf> {
+    let git_dir = git::git_dir().context("Failed to find git directory for cache")?;
+    let cache_dir = std::env::var_os(cache::CACHE_DIR_ENV)
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from);
+
+    Ok(cache::cache_path(cache_dir.as_deref(), &git_dir, repo_root))
+}
+
+/// Print the cache location and size, or delete it with `--clear` (for `ffx cache`).
+fn run_cache(clear: bool) -> Result<()> {
+    let repo_root = git::repo_root().context("Failed to find git repository root")?;
+    let path = cache_file_path(&repo_root)?;
+
+    if !clear {
+        let files = cache::Cache::load(&path).len();
+        println!("Cache: {}", path.display());
+        println!("{} {} recorded", files, pluralize_files(files));
+        return Ok(());
+    }
+
+    match fs::remove_file(&path) {
+        Ok(()) => println!("{} {}", "Cleared cache:".green(), path.display()),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            println!("No cache to clear at {}", path.display())
+        }
+        Err(e) => {
+            return Err(e)
+                .with_context(|| format!("Failed to delete cache file: {}", path.display()))
+        }
+    }
+
+    Ok(())
+}
+
+/// Load and validate the config, then list its tools (for --config-check).
+fn run_config_check(cli: &Cli) -> Result<RunOutcome> {
+    let root = if cli.no_git {
+        std::env::current_dir().context("Failed to get current directory")?
+    } else {
+        git::repo_root().context("Failed to find git repository root")?
+    };
+    let path = resolve_config_path(cli.config.as_deref(), &root);
+    let config = load_config(cli.config.as_deref(), &root)?;
+
+    println!(
+        "{} {} ({} {})",
+        "Valid config:".green(),
+        path.display(),
+        config.tools.len(),
+        pluralize_tools(config.tools.len())
+    );
+    for tool in &config.tools {
+        println!(
+            "  [{}] {}: {}{}",
+            tool.name.cyan(),
+            exec::program(tool),
+            tool.include_patterns().join(", "),
+            if tool.disabled { " (disabled)" } else { "" }
+        );
+    }
+
+    Ok(RunOutcome::success())
+}
+
+/// Load the config from the path chosen by `resolve_config_path`.
+fn load_config(config_arg: Option<&str>, repo_root: &Path) -> Result<Config> {
+    let path = resolve_config_path(config_arg, repo_root);
+    let _span = tracing::info_span!("load_config", path = %path.display()).entered();
+
+    let config = Config::load(&path, repo_root)
+        .with_context(|| format!("Failed to load config from {}", path.display()))?;
+    tracing::debug!(tools = config.tools.len(), "config loaded");
+
+    Ok(config)
+}
+
+/// Find the config file: `--config` as given, otherwise the first of
+/// `CONFIG_FILE_NAMES` in the current directory, then in the repo root, and
+/// finally the user's global config.
+fn resolve_config_path(config_arg: Option<&str>, repo_root: &Path) -> PathBuf {
+    if let Some(config_arg) = config_arg {
+        // Explicitly specified config files are used as-is (missing ones fail with a proper error)
+        return PathBuf::from(config_arg);
+    }
+
+    let found = [Path::new(""), repo_root].into_iter().find_map(|dir| {
+        CONFIG_FILE_NAMES
+            .iter()
+            .map(|name| dir.join(name))
+            .find(|path| path.exists())
+    });
+    if let Some(path) = found {
+        return path;
+    }
+
+    match global_config_path() {
+        Some(global) if global.exists() => global,
+        _ => repo_root.join(CONFIG_FILE_NAME),
+    }
+}
+
+/// User-wide fallback config: `$XDG_CONFIG_HOME/fast-format-x/config.yaml`,
+/// or `~/.config/fast-format-x/config.yaml` when XDG_CONFIG_HOME is unset.
+fn global_config_path() -> Option<PathBuf> {
+    let config_home = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+
+    Some(config_home.join("fast-format-x").join("config.yaml"))
+}
+
+/// Collect target files, match them to tools, run the formatters, and print results.
+fn format_files(
+    cli: &Cli,
+    config: &Config,
+    matchers: &matcher::ToolMatchers,
+    repo_root: &Path,
+    start: Instant,
+) -> Result<RunOutcome> {
     // Get files to format (respects current directory scope, returns repo-root-relative paths)
-    let (files, file_source) = collect_target_files(&cli)?;
+    let (files, file_source) = collect_target_files(cli, repo_root)?;
+    tracing::info!(files = files.len(), source = %file_source, "collected files");
+    if cli.verbose && !cli.no_git && cli.files_from.is_none() {
+        print_scope(files.len())?;
+    }
+    let files = matcher::filter_ignored(repo_root, files)?;
+    // Submodules are formatted from their own repository, not the superproject
+    let files = if cli.no_git || cli.include_submodules {
+        files
+    } else {
+        let submodules =
+            git::submodule_paths(repo_root).context("Failed to read submodule paths")?;
+        git::exclude_submodules(files, &submodules)
+    };
+    let machine_output = cli.format != OutputFormat::Human;
 
     if files.is_empty() {
-        println!("No {file_source}.");
+        print_no_work(&format!("No {file_source}."), cli, start)?;
         return Ok(RunOutcome::success());
     }
 
     // Match files to tools
-    let matches =
-        matcher::match_files(&files, &config.tools).context("Failed to match files to tools")?;
+    let matches = matcher::match_with(matchers, &files);
+    if cli.all {
+        check_unused_tools(config, &matches, cli)?;
+    }
+    let matches = filter_tools(matches, &cli.only, &cli.skip);
+    let mode = cli.mode();
+    let matches = skip_unsupported_tools(matches, mode, machine_output || cli.quiet);
+    let (matches, file_sizes) =
+        skip_large_files(matches, repo_root, config.max_file_bytes, cli.verbose);
+    let matches = skip_binary_files(matches, repo_root, cli.verbose);
+
+    if matches.is_empty() {
+        print_no_work("No files matched any tool patterns.", cli, start)?;
+        return Ok(RunOutcome::success());
+    }
+
+    // Diff output is only useful when every file is re-examined; the cache lives in .git
+    // (or FFX_CACHE_DIR)
+    let mut cache = if config.cache && !cli.no_cache && !cli.no_git && mode != Mode::Diff {
+        Some(cache::Cache::load(&cache_file_path(repo_root)?))
+    } else {
+        None
+    };
+
+    let matches = match &cache {
+        Some(cache) => skip_cached_files(matches, cache, mode, repo_root, cli.verbose),
+        None => matches,
+    };
 
     if matches.is_empty() {
-        println!("No files matched any tool patterns.");
+        print_no_work(
+            "All matched files are unchanged since the last successful run.",
+            cli,
+            start,
+        )?;
+        return Ok(RunOutcome::success());
+    }
+
+    // Input sizes are only reported in verbose and machine-readable output
+    let mut matches = matches;
+    if cli.verbose || machine_output || cli.profile.is_some() {
+        for m in &mut matches {
+            m.measure(repo_root, &file_sizes);
+        }
+    }
+
+    if cli.list {
+        print_plan(&matches);
+        return Ok(RunOutcome::success());
+    }
+
+    if cli.dry_run {
+        let options = exec::RunOptions {
+            verbose: cli.verbose,
+            stream_stderr: false,
+            mode,
+            work_dir: repo_root,
+            max_batch_bytes: cli.max_batch_bytes,
+            extra_args: &cli.tool_args,
+            fail_fast: None,
+            lenient: false,
+        };
+        for m in &matches {
+            for command in exec::dry_run_commands(m.tool, &m.files, &options)? {
+                println!("[{}] {}", m.tool.name.cyan(), command);
+            }
+        }
         return Ok(RunOutcome::success());
     }
 
@@ -211,116 +1028,237 @@ fn run() -> Result<RunOutcome> {
 
     // Show planned work - verbose shows file list, non-verbose shows running indicators
     let is_tty = stdout().is_terminal();
-    let action = if cli.check { "Checking" } else { "Running" };
-    println!("{action} formatters:");
-
-    let indicator_positions = print_planned_work(&matches, cli.verbose, is_tty);
+    let indicator_positions = if machine_output || cli.quiet {
+        None
+    } else {
+        let action = match mode {
+            Mode::Format => "Running",
+            Mode::Check => "Checking",
+            Mode::Diff => "Diffing",
+        };
+        println!("{action} formatters:");
+        print_planned_work(&matches, cli.verbose, is_tty)
+    };
+    // Issues-found exits don't fail the run in lenient check mode, or in diff
+    // mode where they just mean the tool printed a diff
+    let lenient = (cli.check && cli.check_level == CheckLevel::Lenient) || mode == Mode::Diff;
 
-    // Track if we should stop early (for --fail-fast)
+    // Track if we should stop early (for --fail-fast and --max-failures)
     let should_stop = AtomicBool::new(false);
+    let failures = AtomicUsize::new(0);
+    let too_many_failures = || {
+        cli.max_failures
+            .is_some_and(|max| failures.load(Ordering::Relaxed) >= max.get())
+    };
 
-    // Run formatters in parallel and stream results as they complete
-    let (tx, rx) = mpsc::channel();
-
-    matches.par_iter().for_each(|m| {
-        if cli.fail_fast && should_stop.load(Ordering::Relaxed) {
-            let _ = tx.send((m.tool.name.clone(), m.files.len(), None));
-            return;
-        }
+    // Animate the running indicators until each tool's result arrives
+    let spinner = indicator_positions.as_ref().map(|_| {
+        Spinner::start(
+            matches
+                .iter()
+                .map(|m| {
+                    format!(
+                        "[{}] {} {}",
+                        m.tool.name.cyan(),
+                        m.files.len(),
+                        pluralize_files(m.files.len())
+                    )
+                })
+                .collect(),
+        )
+    });
 
-        let result = exec::run_tool(m.tool, &m.files, cli.verbose, cli.check, &repo_root);
+    // Remember file contents to report how many files each tool actually changed
+    let hashes_before = (mode == Mode::Format).then(|| content_hashes(&matches, repo_root));
 
-        if let Ok(ref r) = result {
-            if !r.success {
-                should_stop.store(true, Ordering::Relaxed);
+    // Run formatters in parallel and stream results as they complete
+    let (tx, rx) = mpsc::channel();
+    let stream_stderr = cli.verbose && io::stderr().is_terminal();
+    let options = exec::RunOptions {
+        verbose: cli.verbose,
+        stream_stderr,
+        mode,
+        work_dir: repo_root,
+        max_batch_bytes: cli.max_batch_bytes,
+        extra_args: &cli.tool_args,
+        fail_fast: cli.fail_fast.then_some(&should_stop),
+        lenient,
+    };
+    let jobs: Vec<(&config::Tool, &[&Path])> = matches
+        .iter()
+        .map(|m| (m.tool, m.files.as_slice()))
+        .collect();
+
+    exec::run_tools(
+        &jobs,
+        cli.strategy(),
+        &options,
+        || (cli.fail_fast && should_stop.load(Ordering::Relaxed)) || too_many_failures(),
+        |idx, result| {
+            let m = &matches[idx];
+            if let Some(result) = &result {
+                if !result_passed(result, m.tool.allow_failure, lenient) {
+                    failures.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+            if let (Some(spinner), Some(map)) = (&spinner, &indicator_positions) {
+                if let Some(&line_idx) = map.get(&m.tool.name) {
+                    spinner.finish(
+                        line_idx,
+                        finished_status_line(&m.tool.name, m.files.len(), result.as_ref(), lenient),
+                    );
+                }
             }
-        }
 
-        let _ = tx.send((m.tool.name.clone(), m.files.len(), Some(result)));
-    });
+            let _ = tx.send((idx, result));
+        },
+    );
+    drop(spinner);
 
     let mut results = Vec::with_capacity(matches.len());
 
+    // Tools skipped by --fail-fast or --max-failures send no result
     for _ in 0..matches.len() {
-        if let Ok((name, file_count, maybe_result)) = rx.recv() {
-            if let Some(map) = &indicator_positions {
-                if let Some(&line_idx) = map.get(&name) {
-                    let total_lines = matches.len();
-                    match &maybe_result {
-                        Some(Ok(tool_result)) => {
-                            let status = if tool_result.success {
-                                "✓".green()
-                            } else {
-                                "✗".red()
-                            };
-                            update_status_line(
-                                line_idx,
-                                total_lines,
-                                format!(
-                                    "{} [{}] {} {}",
-                                    status,
-                                    name.cyan(),
-                                    file_count,
-                                    pluralize_files(file_count)
-                                ),
-                            );
-                        }
-                        Some(Err(_)) => {
-                            update_status_line(
-                                line_idx,
-                                total_lines,
-                                format!("{} [{}] error", "✗".red(), name.cyan()),
-                            );
-                        }
-                        None => {
-                            update_status_line(
-                                line_idx,
-                                total_lines,
-                                format!("{} [{}] skipped", "✗".red(), name.cyan()),
-                            );
-                        }
-                    }
-                }
-            }
-
-            if let Some(result) = maybe_result {
-                results.push((name, file_count, result));
-            }
+        if let Ok((idx, Some(result))) = rx.recv() {
+            let m = &matches[idx];
+            results.push(ToolRun {
+                name: m.tool.name.clone(),
+                file_count: m.files.len(),
+                total_bytes: m.total_bytes,
+                changed_count: hashes_before
+                    .as_ref()
+                    .map(|before| count_changed_files(&m.files, before, repo_root)),
+                allow_failure: m.tool.allow_failure,
+                working_dir: m.tool.working_dir.clone(),
+                result,
+            });
         }
     }
 
+    if let Some(cache) = &mut cache {
+        update_cache(cache, &matches, &results, mode, repo_root);
+    }
+
+    if cli.restage {
+        restage_files(&matches, &results, repo_root)?;
+    }
+
+    // Only report --max-failures when it actually kept a tool from running
+    let stopped_early = too_many_failures() && results.len() < matches.len();
+    let mut skipped_tools: Vec<&str> = matches
+        .iter()
+        .map(|m| m.tool.name.as_str())
+        .filter(|name| !results.iter().any(|run| run.name == *name))
+        .collect();
+    skipped_tools.sort_unstable();
+
     // Sort results by tool name for deterministic output
     let mut sorted_results = results;
-    sorted_results.sort_by(|a, b| a.0.cmp(&b.0));
+    sorted_results.sort_by(|a, b| a.name.cmp(&b.name));
+
+    if let Some(path) = &cli.profile {
+        fs::write(path, report::render_profile(&sorted_results)?)
+            .with_context(|| format!("Failed to write profile: {}", path.display()))?;
+    }
+
+    let failed_tools = sorted_results
+        .iter()
+        .filter(|run| !tool_passed(run, lenient))
+        .count();
+    let outcome = RunOutcome::from_tool_counts(sorted_results.len() - failed_tools, failed_tools);
+
+    if machine_output {
+        let all_success = outcome.success;
+        let rendered = match cli.format {
+            OutputFormat::Sarif => {
+                report::render_sarif(&sorted_results, &annotation_patterns(&matches))?
+            }
+            OutputFormat::Junit => report::render_junit(&sorted_results, start.elapsed()),
+            _ => report::render_json(&sorted_results, all_success, cli.check, start.elapsed())?,
+        };
+        println!("{rendered}");
+        return Ok(outcome);
+    }
+
+    let annotations = if cli.github_annotations {
+        collect_annotations(&sorted_results, &annotation_patterns(&matches))
+    } else {
+        Vec::new()
+    };
 
     let mut all_success = true;
+    let mut issue_warnings = 0;
+    let mut allowed_failures = 0;
     let mut total_files = 0;
-    // Collect failure details for check mode (shown after summary)
+    let mut total_changed = 0;
+    // Collect failure details for check mode and diffs (shown after summary)
     let mut failure_details: Vec<(String, Vec<exec::BatchResult>)> = Vec::new();
+    let mut diff_details: Vec<(String, Vec<exec::BatchResult>)> = Vec::new();
 
-    for (name, file_count, result) in sorted_results {
+    // --fix-and-check lists what the format pass changed, per tool
+    let changed_by_tool: Option<Vec<(String, usize)>> =
+        (cli.fix_and_check && mode == Mode::Format && !cli.quiet).then(|| {
+            sorted_results
+                .iter()
+                .filter_map(|run| Some((run.name.clone(), run.changed_count?)))
+                .filter(|&(_, count)| count > 0)
+                .collect()
+        });
+
+    for ToolRun {
+        name,
+        file_count,
+        changed_count,
+        allow_failure,
+        result,
+        ..
+    } in sorted_results
+    {
         total_files += file_count;
+        total_changed += changed_count.unwrap_or_default();
 
         match result {
             Ok(tool_result) => {
-                let status = if tool_result.success {
-                    "✓".green()
-                } else {
-                    "✗".red()
-                };
-
-                if cli.verbose || !is_tty {
+                let status = tool_status(&tool_result, lenient);
+                let advisory = lenient && tool_result.issues_only();
+                let failed = !tool_result.success && !advisory;
+
+                // In quiet mode only failing tools get a status line
+                if (cli.quiet && failed) || (!cli.quiet && (cli.verbose || !is_tty)) {
+                    // Verbose runs show each tool's wall-clock time to spot slow formatters
+                    let timing = if cli.verbose {
+                        format!(" ({:.1}s)", tool_result.duration.as_secs_f64())
+                    } else {
+                        String::new()
+                    };
                     println!(
-                        "{} [{}] {} {}",
+                        "{} [{}] {} {}{}",
                         status,
                         name.cyan(),
                         file_count,
-                        pluralize_files(file_count)
+                        pluralize_files(file_count),
+                        timing.dimmed()
                     );
                 }
 
+                if failed && allow_failure {
+                    allowed_failures += 1;
+                } else if failed {
+                    all_success = false;
+                }
+
+                // In diff mode, collect proposed changes to show after the summary
+                if mode == Mode::Diff {
+                    let diff_batches: Vec<exec::BatchResult> = tool_result
+                        .batches
+                        .into_iter()
+                        .filter(|b| !b.stdout.is_empty() || !b.stderr.is_empty())
+                        .collect();
+                    if !diff_batches.is_empty() {
+                        diff_details.push((name.clone(), diff_batches));
+                    }
                 // In check mode, defer output to after summary; otherwise show inline
-                if cli.check && !tool_result.success {
+                } else if cli.check && !tool_result.success {
                     // Collect failed batches for later display
                     let failed_batches: Vec<exec::BatchResult> = tool_result
                         .batches
@@ -330,31 +1268,15 @@ fn run() -> Result<RunOutcome> {
                     if !failed_batches.is_empty() {
                         failure_details.push((name.clone(), failed_batches));
                     }
-                    all_success = false;
-                } else {
-                    for batch in &tool_result.batches {
-                        if cli.verbose {
-                            eprintln!("  $ {}", batch.command);
-                        }
-                        if !batch.stdout.is_empty() && (cli.verbose || !batch.success) {
-                            for line in batch.stdout.lines() {
-                                println!("  {}", line);
-                            }
-                        }
-                        if !batch.stderr.is_empty() && (cli.verbose || !batch.success) {
-                            for line in batch.stderr.lines() {
-                                eprintln!("  {}", line);
-                            }
-                        }
-                    }
-
-                    if !tool_result.success {
-                        all_success = false;
+                    if advisory {
+                        issue_warnings += 1;
                     }
+                } else {
+                    print_tool_output(&tool_result.batches, cli.verbose, stream_stderr);
                 }
             }
             Err(e) => {
-                if cli.verbose || !is_tty {
+                if cli.quiet || cli.verbose || !is_tty {
                     println!("{} [{}] error", "✗".red(), name.cyan());
                 }
                 eprintln!("  {e:#}");
@@ -365,67 +1287,554 @@ fn run() -> Result<RunOutcome> {
 
     let elapsed = start.elapsed();
 
-    println!();
-    if all_success {
-        let done_msg = if cli.check { "Checked" } else { "Formatted" };
-        println!(
-            "{} {} {} in {:.2}s",
-            done_msg.green(),
-            total_files,
-            pluralize_files(total_files),
-            elapsed.as_secs_f64()
-        );
-    } else {
-        let fail_msg = if cli.check {
-            "Some checks failed"
+    // Quiet runs only print a summary when something failed
+    if !cli.quiet || !all_success {
+        println!();
+        if all_success {
+            let done_msg = match mode {
+                Mode::Format => "Formatted",
+                Mode::Check => "Checked",
+                Mode::Diff => "Diffed",
+            };
+            // Format runs say how many files actually changed (0 means the branch was clean)
+            let changed = if mode == Mode::Format {
+                format!(" ({total_changed} changed)")
+            } else {
+                String::new()
+            };
+            println!(
+                "{} {} {}{} in {:.2}s",
+                done_msg.green(),
+                total_files,
+                pluralize_files(total_files),
+                changed,
+                elapsed.as_secs_f64()
+            );
+            if issue_warnings > 0 {
+                println!(
+                    "{}",
+                    format!(
+                        "{} {} reported formatting issues (lenient check)",
+                        issue_warnings,
+                        pluralize_tools(issue_warnings)
+                    )
+                    .yellow()
+                );
+            }
         } else {
-            "Some formatters failed"
-        };
-        println!("{} ({:.2}s)", fail_msg.red(), elapsed.as_secs_f64());
+            let fail_msg = if cli.check {
+                "Some checks failed"
+            } else {
+                "Some formatters failed"
+            };
+            println!("{} ({:.2}s)", fail_msg.red(), elapsed.as_secs_f64());
+            if stopped_early {
+                let count = failures.load(Ordering::Relaxed);
+                println!(
+                    "{}",
+                    format!(
+                        "Stopped after {} {} (--max-failures)",
+                        count,
+                        if count == 1 { "failure" } else { "failures" }
+                    )
+                    .yellow()
+                );
+            }
+        }
+        if !skipped_tools.is_empty() {
+            let reason = if cli.fail_fast {
+                "--fail-fast"
+            } else {
+                "--max-failures"
+            };
+            println!(
+                "{}",
+                format!(
+                    "Skipped {} {} ({reason}): {}",
+                    skipped_tools.len(),
+                    pluralize_tools(skipped_tools.len()),
+                    skipped_tools.join(", ")
+                )
+                .yellow()
+            );
+        }
+        if allowed_failures > 0 {
+            println!(
+                "{}",
+                format!(
+                    "{} advisory {} (allow_failure)",
+                    allowed_failures,
+                    if allowed_failures == 1 {
+                        "failure"
+                    } else {
+                        "failures"
+                    }
+                )
+                .yellow()
+            );
+        }
+    }
+
+    if let Some(changed) = changed_by_tool {
+        print_changed_files(&changed);
     }
 
     // Show failure details after summary in check mode
-    if cli.check && !failure_details.is_empty() {
+    if cli.check {
+        print_details("Details:", failure_details, |line| line.normal());
+    }
+    print_details("Diff:", diff_details, color_diff_line);
+
+    for annotation in annotations {
+        println!("{annotation}");
+    }
+
+    Ok(outcome)
+}
+
+/// Content hash of every matched file, keyed by path.
+fn content_hashes<'a>(
+    matches: &[matcher::MatchResult<'a>],
+    root: &Path,
+) -> HashMap<&'a Path, Option<String>> {
+    matches
+        .iter()
+        .flat_map(|m| m.files.iter())
+        .par_bridge()
+        .map(|&file| (file, cache::hash_file(&root.join(file))))
+        .collect()
+}
+
+/// Count `files` whose content differs from the hashes taken before the run.
+fn count_changed_files(
+    files: &[&Path],
+    before: &HashMap<&Path, Option<String>>,
+    root: &Path,
+) -> usize {
+    files
+        .par_iter()
+        .filter(|&&file| before.get(file) != Some(&cache::hash_file(&root.join(file))))
+        .count()
+}
+
+/// List how many files each tool changed (tools that changed none are omitted).
+fn print_changed_files(changed: &[(String, usize)]) {
+    if changed.is_empty() {
+        println!("No files were changed by formatters");
+        return;
+    }
+
+    println!("Changed by formatters:");
+    for (name, count) in changed {
+        println!("  [{}] {} {}", name.cyan(), count, pluralize_files(*count));
+    }
+}
+
+/// GitHub Actions annotations for every failing tool that defines an annotation_pattern.
+fn collect_annotations(runs: &[ToolRun], patterns: &HashMap<String, Regex>) -> Vec<String> {
+    let mut annotations = Vec::new();
+
+    for run in runs {
+        let Ok(result) = &run.result else { continue };
+        if result.success {
+            continue;
+        }
+        let Some(regex) = patterns.get(&run.name) else {
+            continue;
+        };
+
+        for batch in &result.batches {
+            let dir = run.working_dir.as_deref();
+            annotations.extend(report::github_annotations(regex, &batch.stdout, dir));
+            annotations.extend(report::github_annotations(regex, &batch.stderr, dir));
+        }
+    }
+
+    annotations
+}
+
+/// Compile each matched tool's `annotation_pattern`, keyed by tool name.
+fn annotation_patterns(matches: &[matcher::MatchResult]) -> HashMap<String, Regex> {
+    matches
+        .iter()
+        .filter_map(|m| {
+            // Patterns were validated when the config was loaded
+            let regex = Regex::new(m.tool.annotation_pattern.as_deref()?).ok()?;
+            Some((m.tool.name.clone(), regex))
+        })
+        .collect()
+}
+
+/// Print a tool's batch output inline, holding both stdout and stderr so the whole
+/// tool's output never interleaves with another tool's.
+///
+/// Each batch's command, stdout, and stderr are written in that order, flushing
+/// between streams, so a merged log (`2>&1`) keeps every batch's lines together.
+/// Verbose runs show every command and its output; otherwise only failing batches
+/// print. Stderr that was already streamed live is not repeated.
+fn print_tool_output(batches: &[exec::BatchResult], verbose: bool, streamed_stderr: bool) {
+    let mut out = stdout().lock();
+    let mut err = io::stderr().lock();
+
+    for batch in batches {
+        if verbose {
+            let _ = writeln!(err, "  $ {}", batch.command);
+            let _ = writeln!(err, "  {}", batch.exit_description());
+        }
+        if verbose || !batch.success {
+            for line in batch.stdout.lines() {
+                let _ = writeln!(out, "  {line}");
+            }
+            let _ = out.flush();
+            if !streamed_stderr {
+                for line in batch.stderr.lines() {
+                    let _ = writeln!(err, "  {line}");
+                }
+            }
+        }
+    }
+}
+
+/// Print captured output per tool under a bold heading, if there is any.
+///
+/// Stdout lines are styled with `style`.
+fn print_details(
+    heading: &str,
+    details: Vec<(String, Vec<exec::BatchResult>)>,
+    style: fn(&str) -> ColoredString,
+) {
+    if details.is_empty() {
+        return;
+    }
+
+    println!();
+    println!("{}", heading.bold());
+    for (tool_name, batches) in details {
         println!();
-        println!("{}", "Details:".bold());
-        for (tool_name, batches) in failure_details {
-            println!();
-            println!("[{}]", tool_name.cyan());
-            for batch in batches {
-                if !batch.command.is_empty() {
-                    println!("  $ {}", batch.command);
+        println!("[{}]", tool_name.cyan());
+        for batch in batches {
+            if !batch.command.is_empty() {
+                println!("  $ {}", batch.command);
+                println!("  {}", batch.exit_description());
+            }
+            if !batch.stdout.is_empty() {
+                for line in batch.stdout.lines() {
+                    println!("  {}", style(line));
                 }
-                if !batch.stdout.is_empty() {
-                    for line in batch.stdout.lines() {
-                        println!("  {}", line);
-                    }
+            }
+            if !batch.stderr.is_empty() {
+                for line in batch.stderr.lines() {
+                    eprintln!("  {}", line);
                 }
-                if !batch.stderr.is_empty() {
-                    for line in batch.stderr.lines() {
-                        eprintln!("  {}", line);
+            }
+        }
+    }
+}
+
+/// Color a unified diff line: additions green, removals red, hunk headers cyan.
+fn color_diff_line(line: &str) -> ColoredString {
+    if line.starts_with("+++") || line.starts_with("---") {
+        line.bold()
+    } else if line.starts_with('+') {
+        line.green()
+    } else if line.starts_with('-') {
+        line.red()
+    } else if line.starts_with("@@") {
+        line.cyan()
+    } else {
+        line.normal()
+    }
+}
+
+/// Bail if any tool name passed on the command line is not defined in the config.
+fn ensure_known_tools(names: &[String], config: &Config) -> Result<()> {
+    for name in names {
+        if !config.tools.iter().any(|t| &t.name == name) {
+            anyhow::bail!("Unknown tool '{}' (not defined in config)", name);
+        }
+    }
+
+    Ok(())
+}
+
+/// Report configured tools that matched no files in an `--all` run: a warning
+/// with --warn-unused-tools, an error for tools with `require_match`.
+fn check_unused_tools(config: &Config, matches: &[matcher::MatchResult], cli: &Cli) -> Result<()> {
+    let unused: Vec<&config::Tool> = config
+        .tools
+        .iter()
+        .filter(|tool| !tool.disabled)
+        .filter(|tool| !matches.iter().any(|m| m.tool.name == tool.name))
+        .filter(|tool| cli.only.is_empty() || cli.only.contains(&tool.name))
+        .filter(|tool| !cli.skip.contains(&tool.name))
+        .collect();
+
+    if cli.warn_unused_tools {
+        for tool in &unused {
+            eprintln!(
+                "warning: tool '{}' matched no files (include: {})",
+                tool.name,
+                tool.include_patterns().join(", ")
+            );
+        }
+    }
+
+    if let Some(tool) = unused.iter().find(|tool| tool.require_match) {
+        anyhow::bail!(
+            "Tool '{}' matched no files (require_match is set; check its include patterns)",
+            tool.name
+        );
+    }
+
+    Ok(())
+}
+
+/// Keep only the tools selected by --only, minus any named by --skip.
+fn filter_tools<'a>(
+    matches: Vec<matcher::MatchResult<'a>>,
+    only: &[String],
+    skip: &[String],
+) -> Vec<matcher::MatchResult<'a>> {
+    matches
+        .into_iter()
+        .filter(|m| only.is_empty() || only.contains(&m.tool.name))
+        .filter(|m| !skip.contains(&m.tool.name))
+        .collect()
+}
+
+/// Drop tools that can't run in `mode` (diff mode needs diff_args), noting each one.
+fn skip_unsupported_tools(
+    matches: Vec<matcher::MatchResult>,
+    mode: Mode,
+    silent: bool,
+) -> Vec<matcher::MatchResult> {
+    let (supported, unsupported): (Vec<_>, Vec<_>) =
+        matches.into_iter().partition(|m| m.tool.supports(mode));
+
+    if !silent {
+        for m in &unsupported {
+            println!(
+                "{} [{}] skipped: no diff_args configured",
+                "-".dimmed(),
+                m.tool.name.cyan()
+            );
+        }
+    }
+
+    supported
+}
+
+/// Drop files larger than each tool's `max_file_bytes` (or the config-wide `default_limit`).
+///
+/// Also returns the sizes it read, so `MatchResult::measure` doesn't stat those files again.
+fn skip_large_files<'a>(
+    matches: Vec<matcher::MatchResult<'a>>,
+    root: &Path,
+    default_limit: Option<u64>,
+    verbose: bool,
+) -> (Vec<matcher::MatchResult<'a>>, HashMap<&'a Path, u64>) {
+    let mut skipped = 0;
+    let mut sizes = HashMap::new();
+
+    let remaining: Vec<matcher::MatchResult<'a>> = matches
+        .into_iter()
+        .filter_map(|mut m| {
+            let Some(limit) = m.tool.max_file_bytes.or(default_limit) else {
+                return Some(m);
+            };
+            let before = m.files.len();
+            // Files that can't be stat'ed are kept so the tool reports the real error
+            m.files
+                .retain(|file| match matcher::file_size(&root.join(file)) {
+                    Some(len) => {
+                        sizes.insert(*file, len);
+                        len <= limit
                     }
-                }
+                    None => true,
+                });
+            skipped += before - m.files.len();
+            (!m.files.is_empty()).then_some(m)
+        })
+        .collect();
+
+    if verbose && skipped > 0 {
+        eprintln!(
+            "max_file_bytes: skipped {} large {}",
+            skipped,
+            pluralize_files(skipped)
+        );
+    }
+
+    (remaining, sizes)
+}
+
+/// Drop binary files from tools with `skip_binary`.
+fn skip_binary_files<'a>(
+    matches: Vec<matcher::MatchResult<'a>>,
+    root: &Path,
+    verbose: bool,
+) -> Vec<matcher::MatchResult<'a>> {
+    let mut skipped = 0;
+
+    let remaining: Vec<matcher::MatchResult<'a>> = matches
+        .into_iter()
+        .filter_map(|mut m| {
+            if !m.tool.skip_binary {
+                return Some(m);
             }
+            let before = m.files.len();
+            m.files.retain(|file| !matcher::is_binary(&root.join(file)));
+            skipped += before - m.files.len();
+            (!m.files.is_empty()).then_some(m)
+        })
+        .collect();
+
+    if verbose && skipped > 0 {
+        eprintln!(
+            "skip_binary: skipped {} binary {}",
+            skipped,
+            pluralize_files(skipped)
+        );
+    }
+
+    remaining
+}
+
+/// Drop files the cache says are unchanged since each tool last succeeded on them.
+fn skip_cached_files<'a>(
+    matches: Vec<matcher::MatchResult<'a>>,
+    cache: &cache::Cache,
+    mode: Mode,
+    root: &Path,
+    verbose: bool,
+) -> Vec<matcher::MatchResult<'a>> {
+    let mut skipped = 0;
+
+    let remaining: Vec<matcher::MatchResult<'a>> = matches
+        .into_iter()
+        .filter_map(|mut m| {
+            let before = m.files.len();
+            m.files
+                .retain(|file| !cache.is_fresh(m.tool, mode, root, file));
+            skipped += before - m.files.len();
+            (!m.files.is_empty()).then_some(m)
+        })
+        .collect();
+
+    if verbose && skipped > 0 {
+        eprintln!(
+            "cache: skipped {} unchanged {}",
+            skipped,
+            pluralize_files(skipped)
+        );
+    }
+
+    remaining
+}
+
+/// Record file hashes for every tool that succeeded, then persist the cache.
+fn update_cache(
+    cache: &mut cache::Cache,
+    matches: &[matcher::MatchResult],
+    results: &[ToolRun],
+    mode: Mode,
+    root: &Path,
+) {
+    for m in matches {
+        let succeeded = results
+            .iter()
+            .any(|run| run.name == m.tool.name && matches!(&run.result, Ok(r) if r.success));
+        if succeeded {
+            cache.record(m.tool, mode, root, &m.files);
         }
     }
 
-    Ok(RunOutcome::from_success(all_success))
+    if let Err(e) = cache.save() {
+        eprintln!("warning: {e:#}");
+    }
+}
+
+/// Re-stage the files of every tool that succeeded, so formatter edits land in the commit.
+fn restage_files(matches: &[matcher::MatchResult], results: &[ToolRun], root: &Path) -> Result<()> {
+    let mut files: Vec<&Path> = matches
+        .iter()
+        .filter(|m| {
+            results
+                .iter()
+                .any(|run| run.name == m.tool.name && matches!(&run.result, Ok(r) if r.success))
+        })
+        .flat_map(|m| m.files.iter().copied())
+        .collect();
+    files.sort();
+    files.dedup();
+
+    git::add_files(root, &files)
+}
+
+/// Report that there is nothing to do, as plain text (unless quiet) or an empty machine-readable report.
+fn print_no_work(message: &str, cli: &Cli, start: Instant) -> Result<()> {
+    match cli.format {
+        OutputFormat::Json => println!(
+            "{}",
+            report::render_json(&[], true, cli.check, start.elapsed())?
+        ),
+        OutputFormat::Sarif => println!("{}", report::render_sarif(&[], &HashMap::new())?),
+        OutputFormat::Junit => println!("{}", report::render_junit(&[], start.elapsed())),
+        OutputFormat::Human if !cli.quiet => println!("{message}"),
+        OutputFormat::Human => {}
+    }
+
+    Ok(())
 }
 
-fn collect_target_files(cli: &Cli) -> Result<(Vec<PathBuf>, String)> {
-    if cli.all {
+#[tracing::instrument(skip_all)]
+fn collect_target_files(cli: &Cli, repo_root: &Path) -> Result<(Vec<PathBuf>, String)> {
+    if let Some(source) = &cli.files_from {
+        let contents = if source == "-" {
+            io::read_to_string(io::stdin()).context("Failed to read file list from stdin")?
+        } else {
+            fs::read_to_string(source)
+                .with_context(|| format!("Failed to read file list: {}", source))?
+        };
+        Ok((parse_file_list(&contents), "files listed".to_string()))
+    } else if let Some(commit) = &cli.changed_in {
+        Ok((
+            git::commit_files(commit)
+                .with_context(|| format!("Failed to get files changed in {}", commit))?,
+            format!("files changed in {}", commit),
+        ))
+    } else if let Some(window) = cli.since {
+        let files = if cli.no_git {
+            matcher::walk_files(Path::new(".")).context("Failed to list files")?
+        } else {
+            git::all_files(false).context("Failed to get all files")?
+        };
+        Ok((
+            matcher::modified_within(repo_root, files, window),
+            "recently modified files".to_string(),
+        ))
+    } else if cli.all && cli.no_git {
         Ok((
-            git::all_files().context("Failed to get all files")?,
-            "all tracked files".to_string(),
+            matcher::walk_files(Path::new(".")).context("Failed to list files")?,
+            "files found".to_string(),
+        ))
+    } else if cli.all {
+        Ok((
+            git::all_files(cli.include_untracked).context("Failed to get all files")?,
+            if cli.include_untracked {
+                "tracked or untracked files".to_string()
+            } else {
+                "all tracked files".to_string()
+            },
         ))
     } else if cli.staged {
         Ok((
             git::staged_files().context("Failed to get staged files")?,
             "staged files".to_string(),
         ))
-    } else if let Some(base_ref) = &cli.base {
+    } else if let Some(base_ref) = resolve_base(cli)? {
         Ok((
-            git::diff_files(base_ref)
+            git::diff_files(&base_ref, cli.diff_range)
                 .with_context(|| format!("Failed to get files changed vs {}", base_ref))?,
             format!("files changed vs {}", base_ref),
         ))
@@ -437,12 +1846,47 @@ fn collect_target_files(cli: &Cli) -> Result<(Vec<PathBuf>, String)> {
     }
 }
 
+/// The ref to diff against: `--base`, or origin's default branch with `--base-auto`.
+fn resolve_base(cli: &Cli) -> Result<Option<String>> {
+    if !cli.base_auto {
+        return Ok(cli.base.clone());
+    }
+
+    let base_ref = git::default_base().context("Failed to detect base for --base-auto")?;
+    if cli.verbose {
+        eprintln!("base: {base_ref} (from origin/HEAD)");
+    }
+
+    Ok(Some(base_ref))
+}
+
+/// Explain that git file lists are limited to the current subdirectory (for --verbose).
+fn print_scope(count: usize) -> Result<()> {
+    let prefix = git::current_prefix().context("Failed to get current directory prefix")?;
+    if !prefix.is_empty() {
+        eprintln!("scope: {prefix} ({count} {})", pluralize_files(count));
+    }
+
+    Ok(())
+}
+
+/// Parse a newline-separated file list, ignoring blank lines and surrounding whitespace.
+fn parse_file_list(contents: &str) -> Vec<PathBuf> {
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(PathBuf::from)
+        .collect()
+}
+
 fn ensure_required_commands(matches: &[matcher::MatchResult]) -> Option<RunOutcome> {
     for m in matches {
-        if !exec::command_exists(&m.tool.cmd) {
+        let program = exec::program(m.tool);
+        if !exec::command_exists(program) {
             eprintln!(
                 "error: command '{}' not found (required by tool '{}')",
-                m.tool.cmd, m.tool.name
+                program, m.tool.name
             );
             return Some(RunOutcome::missing_executable());
         }
@@ -451,6 +1895,27 @@ fn ensure_required_commands(matches: &[matcher::MatchResult]) -> Option<RunOutco
     None
 }
 
+/// Print each tool with the full list of files it would receive (for --list).
+fn print_plan(matches: &[matcher::MatchResult]) {
+    for (idx, m) in matches.iter().enumerate() {
+        if idx > 0 {
+            println!();
+        }
+        println!(
+            "[{}] {} {}",
+            m.tool.name.cyan(),
+            m.files.len(),
+            pluralize_files(m.files.len())
+        );
+        if let Some(description) = &m.tool.description {
+            println!("  {}", description.dimmed());
+        }
+        for file in &m.files {
+            println!("  {}", file.display());
+        }
+    }
+}
+
 fn print_planned_work(
     matches: &[matcher::MatchResult],
     verbose: bool,
@@ -465,10 +1930,11 @@ fn print_planned_work(
                 .collect::<Vec<_>>()
                 .join(", ");
             println!(
-                "- {} ({} {}): {}",
+                "- {} ({} {}, {}): {}",
                 m.tool.name,
                 m.files.len(),
                 pluralize_files(m.files.len()),
+                format_bytes(m.total_bytes),
                 file_list
             );
         }
@@ -497,26 +1963,137 @@ fn print_planned_work(
     }
 }
 
-fn num_cpus() -> u64 {
+fn num_cpus() -> usize {
     std::thread::available_parallelism()
-        .map(|n| n.get() as u64)
+        .map(|n| n.get())
         .unwrap_or(4)
 }
 
+/// Parse `--since`: a whole number followed by `s`, `m`, `h`, or `d` (e.g. "30m").
+fn parse_since(value: &str) -> Result<Duration, String> {
+    let invalid = || format!("expected a duration like 30m, 2h, or 1d, got '{value}'");
+    let split = value.len().saturating_sub(1);
+    let (amount, unit) = (value.get(..split).ok_or_else(invalid)?, &value[split..]);
+    let amount: u64 = amount.parse().map_err(|_| invalid())?;
+
+    let seconds = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => return Err(invalid()),
+    };
+
+    amount
+        .checked_mul(seconds)
+        .map(Duration::from_secs)
+        .ok_or_else(invalid)
+}
+
+/// Parse `--jobs`: a positive count, or "auto"/0 for one job per CPU core.
+fn parse_jobs(value: &str) -> Result<usize, String> {
+    if value.eq_ignore_ascii_case("auto") {
+        return Ok(num_cpus());
+    }
+
+    match value.parse::<usize>() {
+        Ok(0) => Ok(num_cpus()),
+        Ok(jobs) => Ok(jobs),
+        Err(_) => Err(format!("expected a number or \"auto\", got '{value}'")),
+    }
+}
+
 fn update_status_line(line_idx: usize, total_lines: usize, content: String) {
     let (lines_up, lines_down) = cursor_movements(line_idx, total_lines);
 
+    // Hold the lock so the spinner thread can't interleave cursor movements
+    let mut out = stdout().lock();
+
     if lines_up > 0 {
-        print!("\x1b[{}A", lines_up);
+        let _ = write!(out, "\x1b[{}A", lines_up);
     }
 
-    print!("\r{}\x1b[K\n", content);
+    let _ = write!(out, "\r{}\x1b[K\n", content);
 
     if lines_down > 0 {
-        print!("\x1b[{}B", lines_down);
+        let _ = write!(out, "\x1b[{}B", lines_down);
     }
 
-    let _ = stdout().flush();
+    let _ = out.flush();
+}
+
+const SPINNER_FRAMES: &[&str] = &["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+const SPINNER_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Animates the status lines of tools that are still running.
+///
+/// A background thread redraws each pending line with a rotating frame and the
+/// elapsed seconds until `finish` replaces it with the tool's final status.
+/// Dropping the spinner stops the thread.
+struct Spinner {
+    /// Label for each status line, `None` once the tool has finished
+    lines: Arc<Mutex<Vec<Option<String>>>>,
+    done: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl Spinner {
+    fn start(labels: Vec<String>) -> Self {
+        let lines = Arc::new(Mutex::new(labels.into_iter().map(Some).collect::<Vec<_>>()));
+        let done = Arc::new(AtomicBool::new(false));
+
+        let handle = {
+            let lines = Arc::clone(&lines);
+            let done = Arc::clone(&done);
+            thread::spawn(move || {
+                let start = Instant::now();
+                let mut frame = 0;
+                while !done.load(Ordering::Relaxed) {
+                    thread::sleep(SPINNER_INTERVAL);
+                    frame = (frame + 1) % SPINNER_FRAMES.len();
+
+                    let lines = lines.lock().unwrap_or_else(|e| e.into_inner());
+                    let elapsed = format!("({}s)", start.elapsed().as_secs());
+                    for (idx, label) in lines.iter().enumerate() {
+                        if let Some(label) = label {
+                            update_status_line(
+                                idx,
+                                lines.len(),
+                                format!(
+                                    "{} {} {}",
+                                    SPINNER_FRAMES[frame].yellow(),
+                                    label,
+                                    elapsed.dimmed()
+                                ),
+                            );
+                        }
+                    }
+                }
+            })
+        };
+
+        Self {
+            lines,
+            done,
+            handle: Some(handle),
+        }
+    }
+
+    /// Stop animating `line_idx` and replace it with `content`.
+    fn finish(&self, line_idx: usize, content: String) {
+        let mut lines = self.lines.lock().unwrap_or_else(|e| e.into_inner());
+        lines[line_idx] = None;
+        update_status_line(line_idx, lines.len(), content);
+    }
+}
+
+impl Drop for Spinner {
+    fn drop(&mut self) {
+        self.done.store(true, Ordering::Relaxed);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
 }
 
 fn cursor_movements(line_idx: usize, total_lines: usize) -> (usize, usize) {
@@ -526,6 +2103,81 @@ fn cursor_movements(line_idx: usize, total_lines: usize) -> (usize, usize) {
     (lines_up, lines_down)
 }
 
+/// Status line for a tool once it has finished, errored, or been skipped by
+/// `--fail-fast` or `--max-failures`.
+fn finished_status_line(
+    name: &str,
+    file_count: usize,
+    result: Option<&Result<exec::ToolResult>>,
+    lenient: bool,
+) -> String {
+    match result {
+        Some(Ok(tool_result)) => format!(
+            "{} [{}] {} {}",
+            tool_status(tool_result, lenient),
+            name.cyan(),
+            file_count,
+            pluralize_files(file_count)
+        ),
+        Some(Err(_)) => format!("{} [{}] error", "✗".red(), name.cyan()),
+        None => format!("{} [{}] skipped", "✗".red(), name.cyan()),
+    }
+}
+
+/// Status marker for a finished tool: ✓ on success, ! for lenient-mode
+/// formatting issues, ✗ otherwise.
+fn tool_status(result: &exec::ToolResult, lenient: bool) -> ColoredString {
+    if result.success {
+        "✓".green()
+    } else if lenient && result.issues_only() {
+        "!".yellow()
+    } else {
+        "✗".red()
+    }
+}
+
+/// Whether a tool's result counts as passing for the run's exit code.
+fn tool_passed(run: &ToolRun, lenient: bool) -> bool {
+    result_passed(&run.result, run.allow_failure, lenient)
+}
+
+/// Whether `result` counts as passing for a tool with `allow_failure` set as given.
+fn result_passed(result: &Result<exec::ToolResult>, allow_failure: bool, lenient: bool) -> bool {
+    match result {
+        Ok(r) => r.success || allow_failure || (lenient && r.issues_only()),
+        Err(_) => false,
+    }
+}
+
+/// Return "tool" or "tools" based on count for correct grammar.
+fn pluralize_tools(count: usize) -> &'static str {
+    if count == 1 {
+        "tool"
+    } else {
+        "tools"
+    }
+}
+
+/// Human-readable size, e.g. "512 B", "1.5 KB", "12.0 MB".
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 3] = ["KB", "MB", "GB"];
+
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = UNITS[0];
+    for next in &UNITS[1..] {
+        if size < 1024.0 {
+            break;
+        }
+        size /= 1024.0;
+        unit = next;
+    }
+
+    format!("{size:.1} {unit}")
+}
+
 /// Return "file" or "files" based on count for correct grammar.
 fn pluralize_files(count: usize) -> &'static str {
     if count == 1 {
@@ -535,66 +2187,95 @@ fn pluralize_files(count: usize) -> &'static str {
     }
 }
 
-fn run_init() -> Result<()> {
+fn run_init(hook: Hook, language: Option<Language>, append: bool) -> Result<()> {
     let repo_root = git::repo_root().context("Failed to find git repository root")?;
     // Config file goes in current directory (where user ran ffx init)
     let config_path = Path::new(CONFIG_FILE_NAME);
-    // Hooks go in the git repo root
-    let hooks_dir = repo_root.join(".git/hooks");
-    fs::create_dir_all(&hooks_dir).context("Failed to create .git/hooks directory")?;
-
-    let hook_path = hooks_dir.join("pre-commit");
-
-    if !config_path.exists() {
-        write_config_template(config_path)?;
+    // Hooks go wherever git looks for them (core.hooksPath, else .git/hooks)
+    let hooks_dir = git::hooks_dir(&repo_root).context("Failed to find git hooks directory")?;
+    fs::create_dir_all(&hooks_dir)
+        .with_context(|| format!("Failed to create hooks directory: {}", hooks_dir.display()))?;
+
+    let hook_path = hooks_dir.join(hook.file_name());
+
+    let has_config = CONFIG_FILE_NAMES
+        .iter()
+        .any(|name| Path::new(name).exists());
+    if !has_config {
+        let template = language
+            .or_else(|| Language::detect(Path::new(".")))
+            .map_or(CONFIG_TEMPLATE, Language::template);
+        write_config_template(config_path, template)?;
     }
 
     if hook_path.exists() {
-        let contents = fs::read_to_string(&hook_path).unwrap_or_default();
-        if contents.contains("fast-format-x") || contents.contains("ffx") {
+        let mut contents = fs::read_to_string(&hook_path).unwrap_or_default();
+        if contents.contains(HOOK_SECTION_MARKER) || contents.contains("ffx") {
             println!(
-                "Pre-commit hook already configured for ffx at {}",
+                "{} hook already configured for ffx at {}",
+                hook.label(),
                 hook_path.display()
             );
             return Ok(());
         }
 
-        anyhow::bail!(
-            "A pre-commit hook already exists at {}. Please add ffx manually.",
-            hook_path.display()
+        if !append {
+            anyhow::bail!(
+                "A {} hook already exists at {}. Run `ffx init --append` to add ffx to the end of it, or add ffx manually.",
+                hook.file_name(),
+                hook_path.display()
+            );
+        }
+
+        if !contents.is_empty() && !contents.ends_with('\n') {
+            contents.push('\n');
+        }
+        contents.push_str(&hook.appended_section());
+        fs::write(&hook_path, contents)
+            .with_context(|| format!("Failed to update {} hook", hook.file_name()))?;
+
+        println!(
+            "ffx appended to the existing {} hook at {}. {}",
+            hook.file_name(),
+            hook_path.display(),
+            hook.summary()
         );
+        return Ok(());
     }
 
-    fs::write(&hook_path, PRE_COMMIT_HOOK).context("Failed to write pre-commit hook")?;
+    fs::write(&hook_path, hook.script())
+        .with_context(|| format!("Failed to write {} hook", hook.file_name()))?;
 
     #[cfg(unix)]
     {
         use std::os::unix::fs::PermissionsExt;
 
         let mut permissions = fs::metadata(&hook_path)
-            .context("Failed to read pre-commit hook metadata")?
+            .with_context(|| format!("Failed to read {} hook metadata", hook.file_name()))?
             .permissions();
         permissions.set_mode(0o755);
         fs::set_permissions(&hook_path, permissions)
-            .context("Failed to set pre-commit hook permissions")?;
+            .with_context(|| format!("Failed to set {} hook permissions", hook.file_name()))?;
     }
 
     println!(
-        "Pre-commit hook installed at {}. It will run ffx on staged files before each commit.",
-        hook_path.display()
+        "{} hook installed at {}. {}",
+        hook.label(),
+        hook_path.display(),
+        hook.summary()
     );
 
     Ok(())
 }
 
-fn write_config_template(config_path: &Path) -> Result<()> {
+fn write_config_template(config_path: &Path, template: &str) -> Result<()> {
     let mut file = OpenOptions::new()
         .create_new(true)
         .write(true)
         .open(config_path)
         .with_context(|| format!("Failed to create {}", config_path.display()))?;
 
-    file.write_all(CONFIG_TEMPLATE.as_bytes())
+    file.write_all(template.as_bytes())
         .context("Failed to write config template")?;
 
     println!(
@@ -608,13 +2289,124 @@ fn write_config_template(config_path: &Path) -> Result<()> {
 /// GitHub repository for releases
 const GITHUB_REPO: &str = "BrianSigafoos/fast-format-x";
 
+/// Row in the `ffx doctor` report.
+struct DoctorRow<'a> {
+    tool: &'a str,
+    cmd: &'a str,
+    /// `None` when the command isn't on PATH; otherwise the first line of `--version`
+    version: Option<String>,
+    /// The tool's `description`, printed under its row
+    description: Option<&'a str>,
+}
+
+fn run_doctor(config_arg: Option<&str>) -> Result<RunOutcome> {
+    let repo_root = git::repo_root().context("Failed to find git repository root")?;
+    let config = load_config(config_arg, &repo_root)?;
+
+    // Disabled tools never run, so a missing command isn't a problem
+    let rows: Vec<DoctorRow> = config
+        .tools
+        .par_iter()
+        .filter(|tool| !tool.disabled)
+        .map(|tool| DoctorRow {
+            tool: &tool.name,
+            cmd: exec::program(tool),
+            // Shells don't agree on a version flag, and the snippet's own commands are unknown
+            version: exec::command_exists(exec::program(tool)).then(|| {
+                if tool.shell {
+                    "shell".to_string()
+                } else {
+                    command_version(&tool.cmd)
+                }
+            }),
+            description: tool.description.as_deref(),
+        })
+        .collect();
+
+    let table = doctor_table(&rows);
+    println!("{}", table[0].bold());
+    for (row, line) in rows.iter().zip(&table[1..]) {
+        if row.version.is_some() {
+            println!("{line}");
+        } else {
+            println!("{}", line.red());
+        }
+        if let Some(description) = row.description {
+            println!("  {}", description.dimmed());
+        }
+    }
+
+    let missing = rows.iter().filter(|row| row.version.is_none()).count();
+    println!();
+    if missing == 0 {
+        println!("{}", "All tools found".green());
+        Ok(RunOutcome::success())
+    } else {
+        println!(
+            "{}",
+            format!("{} {} not found", missing, pluralize_tools(missing)).red()
+        );
+        Ok(RunOutcome::missing_executable())
+    }
+}
+
+/// First non-empty line of `<cmd> --version` (stdout, then stderr), or "unknown".
+fn command_version(cmd: &str) -> String {
+    std::process::Command::new(cmd)
+        .arg("--version")
+        .stdin(std::process::Stdio::null())
+        .output()
+        .ok()
+        .and_then(|output| {
+            [output.stdout, output.stderr]
+                .into_iter()
+                .find_map(|bytes| {
+                    String::from_utf8_lossy(&bytes)
+                        .lines()
+                        .map(str::trim)
+                        .find(|line| !line.is_empty())
+                        .map(str::to_string)
+                })
+        })
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// Render doctor rows as aligned columns, preceded by a header line.
+fn doctor_table(rows: &[DoctorRow]) -> Vec<String> {
+    let tool_width = rows.iter().map(|r| r.tool.len()).max().unwrap_or(0).max(4);
+    let cmd_width = rows.iter().map(|r| r.cmd.len()).max().unwrap_or(0).max(7);
+    let status_width = "not found".len();
+
+    let mut lines = vec![format!(
+        "{:<tool_width$}  {:<cmd_width$}  {:<status_width$}  VERSION",
+        "TOOL", "COMMAND", "STATUS"
+    )];
+
+    for row in rows {
+        let (status, version) = match &row.version {
+            Some(version) => ("found", version.as_str()),
+            None => ("not found", "-"),
+        };
+        lines.push(format!(
+            "{:<tool_width$}  {:<cmd_width$}  {:<status_width$}  {}",
+            row.tool, row.cmd, status, version
+        ));
+    }
+
+    lines
+}
+
 /// Install script URL
 const INSTALL_SCRIPT_URL: &str = "https://ffx.bfoos.net/install.sh";
 
-fn run_update(check_only: bool) -> Result<()> {
+fn run_update(check_only: bool, pin: Option<&str>) -> Result<()> {
     let current_version = env!("CARGO_PKG_VERSION");
     println!("Current version: v{}", current_version);
 
+    if let Some(pinned) = pin {
+        return run_pinned_update(check_only, pinned, current_version);
+    }
+
     print!("Checking for updates... ");
     let _ = stdout().flush();
 
@@ -653,6 +2445,57 @@ fn run_update(check_only: bool) -> Result<()> {
     Ok(())
 }
 
+/// Install the `--pin` release, which may be older than the current version.
+fn run_pinned_update(check_only: bool, pinned: &str, current_version: &str) -> Result<()> {
+    println!();
+    if pinned == current_version {
+        println!("{}", format!("Already on pinned v{}.", pinned).green());
+    } else if check_only {
+        println!(
+            "{}",
+            format!("Pinned version differs: v{} → v{}", current_version, pinned).yellow()
+        );
+        println!("Run 'ffx update --pin {}' to install.", pinned);
+    } else {
+        println!(
+            "{}",
+            format!("Installing ffx v{} → v{}", current_version, pinned).green()
+        );
+        println!();
+
+        run_install_script(pinned).context("Failed to run install script")?;
+
+        println!();
+        println!("{}", "Update complete!".green());
+    }
+
+    Ok(())
+}
+
+/// Whether `ffx update` must stay off the network: `--offline`, or `FFX_OFFLINE`
+/// set to anything but empty or "0".
+fn is_offline(flag: bool, env: impl Fn(&str) -> Option<String>) -> bool {
+    flag || env("FFX_OFFLINE").is_some_and(|value| !value.is_empty() && value != "0")
+}
+
+/// Parse `--pin`: a release version like "0.1.22" (a leading "v" is allowed and dropped).
+fn parse_pin_version(value: &str) -> Result<String, String> {
+    let version = value.strip_prefix('v').unwrap_or(value);
+    let parts: Vec<&str> = version.split('.').collect();
+    let valid = parts.len() == 3
+        && parts
+            .iter()
+            .all(|part| !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit()));
+
+    if valid {
+        Ok(version.to_string())
+    } else {
+        Err(format!(
+            "expected a release version like 0.1.22, got '{value}'"
+        ))
+    }
+}
+
 /// Fetch the latest version tag from GitHub releases API.
 fn fetch_latest_version() -> Result<String> {
     let url = format!(
@@ -721,6 +2564,9 @@ fn run_install_script(version: &str) -> Result<()> {
     Ok(())
 }
 
+/// Comment that starts the ffx section of a hook installed with `ffx init --append`.
+const HOOK_SECTION_MARKER: &str = "fast-format-x";
+
 const PRE_COMMIT_HOOK: &str = r#"#!/bin/sh
 set -e
 
@@ -730,23 +2576,114 @@ if ! command -v ffx >/dev/null 2>&1; then
     exit 1
 fi
 
-ffx --staged
+ffx --staged --restage
+"#;
+
+const PRE_PUSH_HOOK: &str = r#"#!/bin/sh
+set -e
+
+if ! command -v ffx >/dev/null 2>&1; then
+    echo "ffx not found. Install it with:"
+    echo "  curl -LsSf https://ffx.bfoos.net/install.sh | bash"
+    exit 1
+fi
+
+# Compare against the upstream branch, falling back to the remote's default branch
+if base=$(git rev-parse --abbrev-ref --symbolic-full-name '@{upstream}' 2>/dev/null); then
+    :
+elif base=$(git symbolic-ref --quiet --short refs/remotes/origin/HEAD 2>/dev/null); then
+    :
+else
+    echo "ffx: no upstream branch to compare against, skipping format check"
+    exit 0
+fi
 
-git diff --name-only | while read -r file; do
-    if git diff --cached --name-only | grep -q "^$file$"; then
-        git add "$file"
-    fi
-done
+ffx --check --base "$base"
 "#;
 
 /// Config template embedded from docs/.fast-format-x.yaml at compile time.
 /// This keeps the template in one place for both `ffx init` and the website.
 const CONFIG_TEMPLATE: &str = include_str!("../docs/.fast-format-x.yaml");
 
+/// Stack-specific templates for `ffx init --language`.
+const RUST_CONFIG_TEMPLATE: &str = include_str!("../docs/templates/rust.yaml");
+const NODE_CONFIG_TEMPLATE: &str = include_str!("../docs/templates/node.yaml");
+const PYTHON_CONFIG_TEMPLATE: &str = include_str!("../docs/templates/python.yaml");
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn log_filter_is_off_unless_rust_log_is_set() {
+        assert!(log_filter(None).is_none());
+        assert!(log_filter(Some("  ")).is_none());
+        assert_eq!(
+            log_filter(Some("ffx=debug")).unwrap().to_string(),
+            "ffx=debug"
+        );
+    }
+
+    #[test]
+    fn color_override_respects_flag_then_env() {
+        let env = |vars: &'static [(&'static str, &'static str)]| {
+            move |name: &str| {
+                vars.iter()
+                    .find(|(key, _)| *key == name)
+                    .map(|(_, value)| value.to_string())
+            }
+        };
+
+        assert_eq!(color_override(ColorChoice::Auto, env(&[])), None);
+        assert_eq!(
+            color_override(ColorChoice::Auto, env(&[("NO_COLOR", "1")])),
+            Some(false)
+        );
+        assert_eq!(
+            color_override(ColorChoice::Auto, env(&[("NO_COLOR", "")])),
+            None
+        );
+        assert_eq!(
+            color_override(ColorChoice::Auto, env(&[("FORCE_COLOR", "1")])),
+            Some(true)
+        );
+        assert_eq!(
+            color_override(ColorChoice::Auto, env(&[("CLICOLOR_FORCE", "0")])),
+            None
+        );
+        assert_eq!(
+            color_override(ColorChoice::Always, env(&[("NO_COLOR", "1")])),
+            Some(true)
+        );
+        assert_eq!(
+            color_override(ColorChoice::Never, env(&[("FORCE_COLOR", "1")])),
+            Some(false)
+        );
+    }
+
+    #[test]
+    fn language_detect_uses_manifest() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(Language::detect(dir.path()), None);
+
+        fs::write(dir.path().join("pyproject.toml"), "").unwrap();
+        assert_eq!(Language::detect(dir.path()), Some(Language::Python));
+
+        fs::write(dir.path().join("Cargo.toml"), "").unwrap();
+        assert_eq!(Language::detect(dir.path()), Some(Language::Rust));
+    }
+
+    #[test]
+    fn language_templates_are_valid_configs() {
+        let dir = tempfile::tempdir().unwrap();
+        for language in [Language::Rust, Language::Node, Language::Python] {
+            let path = dir.path().join(CONFIG_FILE_NAME);
+            fs::write(&path, language.template()).unwrap();
+            Config::load(&path, dir.path())
+                .unwrap_or_else(|e| panic!("{language:?} template: {e:#}"));
+        }
+    }
+
     #[test]
     fn cursor_movement_counts_account_for_position() {
         assert_eq!(cursor_movements(0, 3), (3, 2));
@@ -760,6 +2697,7 @@ mod tests {
         let outcome = RunOutcome {
             success: true,
             missing_executable: false,
+            partial: false,
         };
 
         assert_eq!(exit_code_from_outcome(&outcome), ExitCode::SUCCESS);
@@ -770,6 +2708,7 @@ mod tests {
         let outcome = RunOutcome {
             success: false,
             missing_executable: true,
+            partial: false,
         };
 
         assert_eq!(exit_code_from_outcome(&outcome), ExitCode::from(3));
@@ -780,11 +2719,24 @@ mod tests {
         let outcome = RunOutcome {
             success: false,
             missing_executable: false,
+            partial: false,
         };
 
         assert_eq!(exit_code_from_outcome(&outcome), ExitCode::from(1));
     }
 
+    #[test]
+    fn exit_code_partial_failure_uses_four() {
+        let outcome = RunOutcome::from_tool_counts(2, 1);
+        assert_eq!(exit_code_from_outcome(&outcome), ExitCode::from(4));
+
+        let outcome = RunOutcome::from_tool_counts(0, 3);
+        assert_eq!(exit_code_from_outcome(&outcome), ExitCode::from(1));
+
+        let outcome = RunOutcome::from_tool_counts(3, 0);
+        assert_eq!(exit_code_from_outcome(&outcome), ExitCode::SUCCESS);
+    }
+
     #[test]
     fn run_outcome_convenience_builders_set_flags() {
         assert!(RunOutcome::success().success);
@@ -802,12 +2754,13 @@ mod tests {
             exclude: vec![],
             cmd: "definitely_not_installed".to_string(),
             args: vec![],
-            check_args: None,
+            ..Default::default()
         };
 
         let matches = vec![matcher::MatchResult {
             tool: &missing_tool,
             files: vec![Path::new("file.rs")],
+            total_bytes: 0,
         }];
 
         let outcome = ensure_required_commands(&matches);
@@ -816,6 +2769,45 @@ mod tests {
         assert!(outcome.unwrap().missing_executable);
     }
 
+    #[test]
+    fn skip_large_files_returns_sizes_it_read() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("small.rs"), "fn a() {}").unwrap();
+        fs::write(dir.path().join("big.rs"), "x".repeat(100)).unwrap();
+        let tool = config::Tool {
+            name: "rust".to_string(),
+            cmd: "echo".to_string(),
+            max_file_bytes: Some(50),
+            ..Default::default()
+        };
+        let matches = vec![matcher::MatchResult {
+            tool: &tool,
+            files: vec![Path::new("small.rs"), Path::new("big.rs")],
+            total_bytes: 0,
+        }];
+
+        let (remaining, sizes) = skip_large_files(matches, dir.path(), None, false);
+
+        assert_eq!(remaining[0].files, vec![Path::new("small.rs")]);
+        assert_eq!(sizes.get(Path::new("small.rs")), Some(&9));
+        assert_eq!(sizes.get(Path::new("big.rs")), Some(&100));
+    }
+
+    #[test]
+    fn finished_status_line_covers_each_outcome() {
+        let ok = Ok(exec::ToolResult {
+            success: true,
+            batches: vec![],
+            duration: Duration::ZERO,
+        });
+        let err = Err(anyhow::anyhow!("boom"));
+
+        let done = finished_status_line("fmt", 2, Some(&ok), false);
+        assert!(done.contains("✓") && done.contains("fmt") && done.ends_with("2 files"));
+        assert!(finished_status_line("fmt", 2, Some(&err), false).ends_with("error"));
+        assert!(finished_status_line("fmt", 2, None, false).ends_with("skipped"));
+    }
+
     #[test]
     fn print_planned_work_returns_positions_for_tty() {
         use crate::config::Tool;
@@ -826,12 +2818,13 @@ mod tests {
             exclude: vec![],
             cmd: "echo".to_string(),
             args: vec![],
-            check_args: None,
+            ..Default::default()
         };
 
         let matches = vec![matcher::MatchResult {
             tool: &tool,
             files: vec![Path::new("file.rs")],
+            total_bytes: 0,
         }];
 
         let positions = print_planned_work(&matches, false, true).unwrap();
@@ -839,6 +2832,161 @@ mod tests {
         assert_eq!(positions.get("test"), Some(&0));
     }
 
+    #[test]
+    fn filter_tools_applies_only_and_skip() {
+        use crate::config::Tool;
+
+        let tools: Vec<Tool> = ["rustfmt", "prettier", "gofmt"]
+            .iter()
+            .map(|name| Tool {
+                name: name.to_string(),
+                ..Default::default()
+            })
+            .collect();
+        let make_matches = || -> Vec<matcher::MatchResult> {
+            tools
+                .iter()
+                .map(|tool| matcher::MatchResult {
+                    tool,
+                    files: vec![Path::new("file")],
+                    total_bytes: 0,
+                })
+                .collect()
+        };
+        let names = |matches: Vec<matcher::MatchResult>| -> Vec<String> {
+            matches.iter().map(|m| m.tool.name.clone()).collect()
+        };
+
+        let only = filter_tools(make_matches(), &["prettier".to_string()], &[]);
+        assert_eq!(names(only), vec!["prettier"]);
+
+        let skipped = filter_tools(make_matches(), &[], &["prettier".to_string()]);
+        assert_eq!(names(skipped), vec!["rustfmt", "gofmt"]);
+
+        let all = filter_tools(make_matches(), &[], &[]);
+        assert_eq!(all.len(), 3);
+    }
+
+    #[test]
+    fn color_diff_line_styles_unified_diff() {
+        use colored::Color;
+
+        assert_eq!(color_diff_line("+new").fgcolor, Some(Color::Green));
+        assert_eq!(color_diff_line("-old").fgcolor, Some(Color::Red));
+        assert_eq!(color_diff_line("@@ -1 +1 @@").fgcolor, Some(Color::Cyan));
+        assert_eq!(color_diff_line("+++ b/src/main.rs").fgcolor, None);
+        assert_eq!(color_diff_line(" context").fgcolor, None);
+    }
+
+    #[test]
+    fn format_bytes_picks_unit() {
+        assert_eq!(format_bytes(0), "0 B");
+        assert_eq!(format_bytes(1023), "1023 B");
+        assert_eq!(format_bytes(1536), "1.5 KB");
+        assert_eq!(format_bytes(5 * 1024 * 1024), "5.0 MB");
+        assert_eq!(format_bytes(3 * 1024 * 1024 * 1024), "3.0 GB");
+    }
+
+    #[test]
+    fn doctor_table_aligns_columns() {
+        let rows = vec![
+            DoctorRow {
+                tool: "prettier",
+                cmd: "npx",
+                version: Some("10.2.3".to_string()),
+                description: Some("formats JS"),
+            },
+            DoctorRow {
+                tool: "ktlint",
+                cmd: "ktlint",
+                version: None,
+                description: None,
+            },
+        ];
+
+        assert_eq!(
+            doctor_table(&rows),
+            vec![
+                "TOOL      COMMAND  STATUS     VERSION",
+                "prettier  npx      found      10.2.3",
+                "ktlint    ktlint   not found  -",
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_since_accepts_units() {
+        assert_eq!(parse_since("45s"), Ok(Duration::from_secs(45)));
+        assert_eq!(parse_since("30m"), Ok(Duration::from_secs(30 * 60)));
+        assert_eq!(parse_since("2h"), Ok(Duration::from_secs(2 * 60 * 60)));
+        assert_eq!(parse_since("1d"), Ok(Duration::from_secs(24 * 60 * 60)));
+        assert!(parse_since("30").is_err());
+        assert!(parse_since("m").is_err());
+        assert!(parse_since("1.5h").is_err());
+        assert!(parse_since("").is_err());
+        assert!(parse_since("5é").is_err());
+        assert!(parse_since("99999999999999999d").is_err());
+    }
+
+    #[test]
+    fn is_offline_honors_flag_and_env() {
+        let env = |value: Option<&'static str>| move |_: &str| value.map(String::from);
+        assert!(is_offline(true, env(None)));
+        assert!(is_offline(false, env(Some("1"))));
+        assert!(is_offline(false, env(Some("true"))));
+        assert!(!is_offline(false, env(Some("0"))));
+        assert!(!is_offline(false, env(Some(""))));
+        assert!(!is_offline(false, env(None)));
+    }
+
+    #[test]
+    fn parse_pin_version_accepts_release_versions() {
+        assert_eq!(parse_pin_version("0.1.22"), Ok("0.1.22".to_string()));
+        assert_eq!(parse_pin_version("v1.2.3"), Ok("1.2.3".to_string()));
+        assert!(parse_pin_version("1.2").is_err());
+        assert!(parse_pin_version("1.2.3.4").is_err());
+        assert!(parse_pin_version("1..3").is_err());
+        assert!(parse_pin_version("latest").is_err());
+        assert!(parse_pin_version("1.2.3; rm -rf /").is_err());
+    }
+
+    #[test]
+    fn parse_jobs_accepts_counts_and_auto() {
+        assert_eq!(parse_jobs("3"), Ok(3));
+        assert_eq!(parse_jobs("auto"), Ok(num_cpus()));
+        assert_eq!(parse_jobs("0"), Ok(num_cpus()));
+        assert!(parse_jobs("-1").is_err());
+        assert!(parse_jobs("many").is_err());
+    }
+
+    #[test]
+    fn parse_file_list_skips_blank_lines() {
+        let files = parse_file_list("src/main.rs\n\n  docs/guide.md \r\n");
+        assert_eq!(
+            files,
+            vec![PathBuf::from("src/main.rs"), PathBuf::from("docs/guide.md")]
+        );
+    }
+
+    #[test]
+    fn stdin_file_path_is_relative_to_repo_root() {
+        let root = Path::new("/repo");
+        assert_eq!(
+            stdin_file_path(Path::new("/repo/web"), root, Path::new("src/app.ts")).unwrap(),
+            PathBuf::from("web/src/app.ts")
+        );
+        assert_eq!(
+            stdin_file_path(Path::new("/elsewhere"), root, Path::new("/repo/lib.rs")).unwrap(),
+            PathBuf::from("lib.rs")
+        );
+        assert!(stdin_file_path(Path::new("/elsewhere"), root, Path::new("lib.rs")).is_err());
+        assert_eq!(
+            stdin_file_path(Path::new("/repo/web"), root, Path::new("../src/./a.rs")).unwrap(),
+            PathBuf::from("src/a.rs")
+        );
+        assert!(stdin_file_path(Path::new("/repo"), root, Path::new("../a.rs")).is_err());
+    }
+
     #[test]
     fn is_newer_version_detects_major_upgrade() {
         assert!(is_newer_version("2.0.0", "1.0.0"));
@@ -5,8 +5,26 @@
 
 use crate::config::Tool;
 use anyhow::{Context, Result};
-use globset::{Glob, GlobSet, GlobSetBuilder};
-use std::path::Path;
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use ignore::gitignore::GitignoreBuilder;
+use ignore::WalkBuilder;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Repo-root file with gitignore-syntax exclusions that apply to every tool.
+pub const IGNORE_FILE_NAME: &str = ".ffxignore";
+
+/// Excludes applied to every tool unless the config sets `default_excludes: false`.
+/// These match at any depth so nested build output and dependencies are skipped too.
+pub const DEFAULT_EXCLUDES: &[&str] = &[
+    "**/target/**",
+    "**/node_modules/**",
+    "**/.git/**",
+    "**/vendor/**",
+];
 
 /// A compiled matcher for a single tool.
 struct ToolMatcher {
@@ -15,12 +33,18 @@ struct ToolMatcher {
 }
 
 impl ToolMatcher {
-    /// Create a new matcher from a tool's patterns.
-    fn new(tool: &Tool) -> Result<Self> {
-        let include = build_globset(&tool.include)
+    /// Create a new matcher from a tool's patterns, optionally adding `DEFAULT_EXCLUDES`.
+    fn new(tool: &Tool, default_excludes: bool) -> Result<Self> {
+        let base = tool.base.as_deref();
+        let include = build_globset(&with_base(&tool.include_patterns(), base))
             .with_context(|| format!("Invalid include patterns for tool '{}'", tool.name))?;
 
-        let exclude = build_globset(&tool.exclude)
+        let mut exclude_patterns = with_base(&tool.exclude, base);
+        if default_excludes {
+            exclude_patterns.extend(DEFAULT_EXCLUDES.iter().map(|p| p.to_string()));
+        }
+
+        let exclude = build_globset(&exclude_patterns)
             .with_context(|| format!("Invalid exclude patterns for tool '{}'", tool.name))?;
 
         Ok(Self { include, exclude })
@@ -28,17 +52,58 @@ impl ToolMatcher {
 
     /// Check if a file matches this tool (included and not excluded).
     fn matches(&self, path: &Path) -> bool {
+        let path = path.strip_prefix("./").unwrap_or(path);
         self.include.is_match(path) && !self.exclude.is_match(path)
     }
 }
 
+/// Prefix each pattern with the tool's `base` directory, if it has one.
+///
+/// Patterns with a leading `/` are left as is, so they stay relative to the repo root.
+fn with_base(patterns: &[String], base: Option<&str>) -> Vec<String> {
+    let base = base
+        .map(|b| b.strip_prefix("./").unwrap_or(b).trim_end_matches('/'))
+        .filter(|b| !b.is_empty() && *b != ".");
+
+    match base {
+        Some(base) => patterns
+            .iter()
+            .map(|p| {
+                if p.starts_with('/') {
+                    p.clone()
+                } else {
+                    format!("{}/{}", base, p.strip_prefix("./").unwrap_or(p))
+                }
+            })
+            .collect(),
+        None => patterns.to_vec(),
+    }
+}
+
 /// Build a GlobSet from a list of pattern strings.
-fn build_globset(patterns: &[String]) -> Result<GlobSet> {
+///
+/// Patterns always match whole repo-relative paths, so `src/*.rs` never matches
+/// `lib/src/a.rs`, but `*` may cross directories, so it does match `src/a/b.rs`.
+/// A leading `/` does not anchor anything further; it only keeps `*` within one
+/// directory: `/src/*.rs` matches `src/main.rs` but not `src/a/b.rs`. (With a
+/// tool `base`, it also skips the base prefix; see `with_base`.) Use `**/` to
+/// match at any depth.
+///
+/// Also used by `Config::validate` to reject invalid patterns when the config loads.
+pub fn build_globset(patterns: &[String]) -> Result<GlobSet> {
     let mut builder = GlobSetBuilder::new();
 
     for pattern in patterns {
-        let glob =
-            Glob::new(pattern).with_context(|| format!("Invalid glob pattern: {}", pattern))?;
+        let (normalized, literal_separator) = match pattern.strip_prefix('/') {
+            Some(rest) => (rest, true),
+            // Paths from git have no leading "./", so patterns copied from other tools
+            // like "./src/**/*.rs" would otherwise never match
+            None => (pattern.strip_prefix("./").unwrap_or(pattern), false),
+        };
+        let glob = GlobBuilder::new(normalized)
+            .literal_separator(literal_separator)
+            .build()
+            .with_context(|| format!("Invalid glob pattern: {}", pattern))?;
         builder.add(glob);
     }
 
@@ -51,29 +116,69 @@ pub struct MatchResult<'a> {
     pub tool: &'a Tool,
     /// Files that matched this tool
     pub files: Vec<&'a Path>,
+    /// Total size of `files` in bytes, filled in by `measure`
+    pub total_bytes: u64,
+}
+
+impl MatchResult<'_> {
+    /// Sum the sizes of `files` (relative to `root`) into `total_bytes`.
+    ///
+    /// Sizes already read (e.g. by the `max_file_bytes` filter) are taken from `known`,
+    /// so only the remaining files are stat'ed. Call once the file list is final, after
+    /// any filtering; unreadable files count as 0.
+    pub fn measure(&mut self, root: &Path, known: &HashMap<&Path, u64>) {
+        self.total_bytes = self
+            .files
+            .iter()
+            .filter_map(|file| {
+                known
+                    .get(file)
+                    .copied()
+                    .or_else(|| file_size(&root.join(file)))
+            })
+            .sum();
+    }
+}
+
+/// Compiled matchers for every enabled tool, in config order.
+///
+/// Build once with `build_matchers` and reuse across `match_with` calls (e.g. on
+/// each `--watch` event) to avoid recompiling every glob.
+pub struct ToolMatchers<'a> {
+    tools: Vec<&'a Tool>,
+    matchers: Vec<ToolMatcher>,
+}
+
+/// Compile each tool's include/exclude patterns.
+///
+/// When `default_excludes` is true, `DEFAULT_EXCLUDES` are merged into every tool's excludes.
+/// Disabled tools are left out, so they never claim files.
+pub fn build_matchers(tools: &[Tool], default_excludes: bool) -> Result<ToolMatchers<'_>> {
+    let tools: Vec<&Tool> = tools.iter().filter(|tool| !tool.disabled).collect();
+    let matchers = tools
+        .iter()
+        .map(|tool| ToolMatcher::new(tool, default_excludes))
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(ToolMatchers { tools, matchers })
 }
 
 /// Match files against tools and return which files each tool should process.
 ///
 /// A file is matched to the FIRST tool whose patterns match it.
 /// This ensures each file is only processed once.
-pub fn match_files<'a>(
+#[tracing::instrument(skip_all, fields(files = files.len()))]
+pub fn match_with<'a>(
+    matchers: &ToolMatchers<'a>,
     files: &'a [impl AsRef<Path>],
-    tools: &'a [Tool],
-) -> Result<Vec<MatchResult<'a>>> {
-    // Build matchers for all tools
-    let matchers: Vec<ToolMatcher> = tools
-        .iter()
-        .map(ToolMatcher::new)
-        .collect::<Result<Vec<_>>>()?;
-
+) -> Vec<MatchResult<'a>> {
     // Track which files have been matched
     let mut matched: Vec<bool> = vec![false; files.len()];
 
     // Collect results per tool
     let mut results: Vec<MatchResult<'a>> = Vec::new();
 
-    for (tool, matcher) in tools.iter().zip(matchers.iter()) {
+    for (tool, matcher) in matchers.tools.iter().zip(matchers.matchers.iter()) {
         let mut tool_files: Vec<&Path> = Vec::new();
 
         for (i, file) in files.iter().enumerate() {
@@ -89,20 +194,141 @@ pub fn match_files<'a>(
         }
 
         if !tool_files.is_empty() {
+            tracing::debug!(tool = %tool.name, files = tool_files.len(), "matched files");
             results.push(MatchResult {
                 tool,
                 files: tool_files,
+                total_bytes: 0,
             });
         }
     }
 
-    Ok(results)
+    results
+}
+
+/// Compile `tools`' patterns and match `files` against them in one step.
+///
+/// Convenience for a single pass; build matchers once with `build_matchers` when matching repeatedly.
+pub fn match_files<'a>(
+    files: &'a [impl AsRef<Path>],
+    tools: &'a [Tool],
+    default_excludes: bool,
+) -> Result<Vec<MatchResult<'a>>> {
+    Ok(match_with(&build_matchers(tools, default_excludes)?, files))
+}
+
+/// Drop files matched by the repo-root `.ffxignore` (gitignore syntax), if one exists.
+///
+/// `files` are repo-root-relative. A file is dropped if it or any parent directory
+/// is ignored, so `build/` excludes everything under `build`.
+pub fn filter_ignored(root: &Path, files: Vec<PathBuf>) -> Result<Vec<PathBuf>> {
+    let path = root.join(IGNORE_FILE_NAME);
+    if !path.exists() {
+        return Ok(files);
+    }
+
+    let mut builder = GitignoreBuilder::new(root);
+    if let Some(err) = builder.add(&path) {
+        return Err(err).with_context(|| format!("Failed to read {}", path.display()));
+    }
+    let gitignore = builder
+        .build()
+        .with_context(|| format!("Invalid patterns in {}", path.display()))?;
+
+    Ok(files
+        .into_iter()
+        .filter(|file| {
+            !gitignore
+                .matched_path_or_any_parents(file, false)
+                .is_ignore()
+        })
+        .collect())
+}
+
+/// The size of `path` in bytes, or `None` if it can't be stat'ed.
+pub fn file_size(path: &Path) -> Option<u64> {
+    fs::metadata(path).ok().map(|meta| meta.len())
+}
+
+/// Whether `path` is larger than `limit` bytes.
+///
+/// Files that can't be stat'ed are kept so the tool reports the real error.
+pub fn is_larger_than(path: &Path, limit: u64) -> bool {
+    file_size(path).is_some_and(|len| len > limit)
+}
+
+/// Bytes read from the start of a file when checking whether it's binary.
+const BINARY_SNIFF_BYTES: usize = 8 * 1024;
+
+/// Whether `path` looks binary: a NUL byte in its first `BINARY_SNIFF_BYTES`.
+///
+/// Unreadable files are treated as text so the tool reports the real error.
+pub fn is_binary(path: &Path) -> bool {
+    let Ok(file) = File::open(path) else {
+        return false;
+    };
+
+    let mut head = Vec::with_capacity(BINARY_SNIFF_BYTES);
+    if file
+        .take(BINARY_SNIFF_BYTES as u64)
+        .read_to_end(&mut head)
+        .is_err()
+    {
+        return false;
+    }
+
+    head.contains(&0)
+}
+
+/// List files under `root` without git, for `--no-git --all`.
+///
+/// Respects `.gitignore` files even outside a git repository and includes hidden
+/// files (like `git ls-files` would), but never descends into `.git`. Returns
+/// sorted paths relative to `root`.
+pub fn walk_files(root: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let walker = WalkBuilder::new(root)
+        .hidden(false)
+        .require_git(false)
+        .filter_entry(|entry| entry.file_name() != ".git")
+        .build();
+
+    for entry in walker {
+        let entry = entry.with_context(|| format!("Failed to walk {}", root.display()))?;
+        if !entry.file_type().is_some_and(|t| t.is_file()) {
+            continue;
+        }
+        if let Ok(relative) = entry.path().strip_prefix(root) {
+            files.push(relative.to_path_buf());
+        }
+    }
+
+    files.sort();
+    Ok(files)
+}
+
+/// Keep files whose mtime is within `window` of now, for `--since`.
+///
+/// `files` are relative to `root`. Files that can't be stat'ed (e.g. deleted) are dropped.
+pub fn modified_within(root: &Path, files: Vec<PathBuf>, window: Duration) -> Vec<PathBuf> {
+    let Some(cutoff) = SystemTime::now().checked_sub(window) else {
+        return files;
+    };
+
+    files
+        .into_iter()
+        .filter(|file| {
+            fs::metadata(root.join(file))
+                .and_then(|meta| meta.modified())
+                .is_ok_and(|modified| modified >= cutoff)
+        })
+        .collect()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::path::PathBuf;
+    use std::fs;
 
     fn make_tool(name: &str, include: &[&str], exclude: &[&str]) -> Tool {
         Tool {
@@ -111,7 +337,7 @@ mod tests {
             exclude: exclude.iter().map(|s| s.to_string()).collect(),
             cmd: "echo".to_string(),
             args: vec![],
-            check_args: None,
+            ..Default::default()
         }
     }
 
@@ -129,7 +355,7 @@ mod tests {
             "docs/guide.md".into(),
         ];
 
-        let results = match_files(&files, &tools).unwrap();
+        let results = match_files(&files, &tools, false).unwrap();
 
         assert_eq!(results.len(), 2);
 
@@ -146,13 +372,148 @@ mod tests {
 
         let files: Vec<PathBuf> = vec!["src/main.rs".into(), "target/debug/build.rs".into()];
 
-        let results = match_files(&files, &tools).unwrap();
+        let results = match_files(&files, &tools, false).unwrap();
 
         assert_eq!(results.len(), 1);
         assert_eq!(results[0].files.len(), 1);
         assert_eq!(results[0].files[0], Path::new("src/main.rs"));
     }
 
+    #[test]
+    fn test_dot_slash_prefix_is_ignored() {
+        let tools = vec![make_tool("rust", &["./src/**/*.rs"], &["./src/gen/**"])];
+
+        let files: Vec<PathBuf> = vec![
+            "src/main.rs".into(),
+            "./src/lib.rs".into(),
+            "src/gen/out.rs".into(),
+        ];
+
+        let results = match_files(&files, &tools, false).unwrap();
+
+        assert_eq!(results.len(), 1);
+        assert_eq!(
+            results[0].files,
+            vec![Path::new("src/main.rs"), Path::new("./src/lib.rs")]
+        );
+    }
+
+    #[test]
+    fn test_leading_slash_keeps_star_in_one_directory() {
+        let files: Vec<PathBuf> = vec![
+            "src/main.rs".into(),
+            "src/nested/mod.rs".into(),
+            "lib/src/util.rs".into(),
+        ];
+        let matched = |pattern: &str| {
+            let tools = vec![make_tool("rust", &[pattern], &[])];
+            match_files(&files, &tools, false)
+                .unwrap()
+                .first()
+                .map(|m| m.files.iter().map(|f| f.to_path_buf()).collect())
+                .unwrap_or_else(Vec::<PathBuf>::new)
+        };
+
+        assert_eq!(matched("/src/*.rs"), vec![Path::new("src/main.rs")]);
+        assert_eq!(
+            matched("src/*.rs"),
+            vec![Path::new("src/main.rs"), Path::new("src/nested/mod.rs")]
+        );
+        assert_eq!(matched("**/*.rs").len(), 3);
+        assert_eq!(matched("/src/**/*.rs").len(), 2);
+    }
+
+    #[test]
+    fn test_leading_slash_patterns_ignore_base() {
+        let mut tool = make_tool(
+            "prettier",
+            &["**/*.ts", "/shared/*.ts"],
+            &["/frontend/gen/**"],
+        );
+        tool.base = Some("frontend".to_string());
+        let tools = vec![tool];
+
+        let files: Vec<PathBuf> = vec![
+            "frontend/app.ts".into(),
+            "frontend/gen/api.ts".into(),
+            "shared/types.ts".into(),
+            "frontend/shared/types.ts".into(),
+            "backend/server.ts".into(),
+        ];
+
+        let results = match_files(&files, &tools, false).unwrap();
+
+        assert_eq!(
+            results[0].files,
+            vec![
+                Path::new("frontend/app.ts"),
+                Path::new("shared/types.ts"),
+                Path::new("frontend/shared/types.ts"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_base_prefixes_include_and_exclude() {
+        let mut tool = make_tool("prettier", &["**/*.ts"], &["generated/**"]);
+        tool.base = Some("frontend/".to_string());
+        let tools = vec![tool];
+
+        let files: Vec<PathBuf> = vec![
+            "frontend/src/app.ts".into(),
+            "frontend/generated/api.ts".into(),
+            "backend/server.ts".into(),
+        ];
+
+        let results = match_files(&files, &tools, false).unwrap();
+
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].files, vec![Path::new("frontend/src/app.ts")]);
+    }
+
+    #[test]
+    fn test_extensions_merge_with_include() {
+        let mut tool = make_tool("prettier", &["Dockerfile"], &[]);
+        tool.extensions = vec!["ts".to_string(), ".json".to_string()];
+        let tools = vec![tool];
+
+        let files: Vec<PathBuf> = vec![
+            "Dockerfile".into(),
+            "web/src/app.ts".into(),
+            "package.json".into(),
+            "README.md".into(),
+        ];
+
+        let results = match_files(&files, &tools, false).unwrap();
+
+        assert_eq!(
+            results[0].files,
+            vec![
+                Path::new("Dockerfile"),
+                Path::new("web/src/app.ts"),
+                Path::new("package.json")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_matchers_reused_across_calls() {
+        let tools = vec![make_tool("rust", &["**/*.rs"], &[])];
+        let matchers = build_matchers(&tools, true).unwrap();
+
+        let first: Vec<PathBuf> = vec!["src/main.rs".into()];
+        let second: Vec<PathBuf> = vec!["src/lib.rs".into(), "README.md".into()];
+
+        assert_eq!(
+            match_with(&matchers, &first)[0].files,
+            vec![Path::new("src/main.rs")]
+        );
+        assert_eq!(
+            match_with(&matchers, &second)[0].files,
+            vec![Path::new("src/lib.rs")]
+        );
+    }
+
     #[test]
     fn test_first_match_wins() {
         // Both tools match .rs files, but first tool should win
@@ -163,20 +524,183 @@ mod tests {
 
         let files: Vec<PathBuf> = vec!["src/main.rs".into()];
 
-        let results = match_files(&files, &tools).unwrap();
+        let results = match_files(&files, &tools, false).unwrap();
 
         assert_eq!(results.len(), 1);
         assert_eq!(results[0].tool.name, "first");
     }
 
+    #[test]
+    fn test_disabled_tool_does_not_claim_files() {
+        let mut disabled = make_tool("all-rust", &["**/*.rs"], &[]);
+        disabled.disabled = true;
+        let tools = vec![disabled, make_tool("rustfmt", &["**/*.rs"], &[])];
+        let files: Vec<PathBuf> = vec!["src/main.rs".into()];
+
+        let results = match_files(&files, &tools, false).unwrap();
+
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].tool.name, "rustfmt");
+    }
+
     #[test]
     fn test_no_matches() {
         let tools = vec![make_tool("rust", &["**/*.rs"], &[])];
 
         let files: Vec<PathBuf> = vec!["README.md".into()];
 
-        let results = match_files(&files, &tools).unwrap();
+        let results = match_files(&files, &tools, false).unwrap();
 
         assert!(results.is_empty());
     }
+
+    #[test]
+    fn test_default_excludes_skip_build_and_dependency_dirs() {
+        let tools = vec![make_tool("all", &["**/*.js"], &[])];
+
+        let files: Vec<PathBuf> = vec![
+            "src/app.js".into(),
+            "node_modules/lib/index.js".into(),
+            "packages/web/node_modules/lib/index.js".into(),
+            "target/out.js".into(),
+            "vendor/dep.js".into(),
+        ];
+
+        let results = match_files(&files, &tools, true).unwrap();
+
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].files, vec![Path::new("src/app.js")]);
+    }
+
+    #[test]
+    fn test_default_excludes_can_be_disabled() {
+        let tools = vec![make_tool("all", &["**/*.js"], &[])];
+
+        let files: Vec<PathBuf> = vec!["src/app.js".into(), "vendor/dep.js".into()];
+
+        let results = match_files(&files, &tools, false).unwrap();
+
+        assert_eq!(results[0].files.len(), 2);
+    }
+
+    #[test]
+    fn test_filter_ignored_applies_ffxignore() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join(IGNORE_FILE_NAME),
+            "# generated code\n\ngenerated/\n*.min.js\n!keep.min.js\n",
+        )
+        .unwrap();
+
+        let files: Vec<PathBuf> = vec![
+            "src/app.js".into(),
+            "src/app.min.js".into(),
+            "src/keep.min.js".into(),
+            "generated/api/client.rs".into(),
+        ];
+
+        let kept = filter_ignored(dir.path(), files).unwrap();
+
+        assert_eq!(
+            kept,
+            vec![
+                PathBuf::from("src/app.js"),
+                PathBuf::from("src/keep.min.js")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_filter_ignored_without_file_keeps_everything() {
+        let dir = tempfile::tempdir().unwrap();
+        let files: Vec<PathBuf> = vec!["src/app.js".into()];
+
+        assert_eq!(filter_ignored(dir.path(), files.clone()).unwrap(), files);
+    }
+
+    #[test]
+    fn test_walk_files_respects_gitignore_without_git() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("src")).unwrap();
+        fs::create_dir_all(dir.path().join("build")).unwrap();
+        fs::write(dir.path().join(".gitignore"), "build/\n").unwrap();
+        fs::write(dir.path().join("src/main.rs"), "").unwrap();
+        fs::write(dir.path().join("build/out.rs"), "").unwrap();
+
+        let files = walk_files(dir.path()).unwrap();
+
+        assert_eq!(
+            files,
+            vec![PathBuf::from(".gitignore"), PathBuf::from("src/main.rs")]
+        );
+    }
+
+    #[test]
+    fn test_modified_within_filters_by_mtime() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("new.rs"), "fn main() {}").unwrap();
+        let old = File::create(dir.path().join("old.rs")).unwrap();
+        old.set_modified(SystemTime::now() - Duration::from_secs(2 * 60 * 60))
+            .unwrap();
+
+        let files = vec![
+            PathBuf::from("new.rs"),
+            PathBuf::from("old.rs"),
+            PathBuf::from("deleted.rs"),
+        ];
+        let recent = modified_within(dir.path(), files, Duration::from_secs(60 * 60));
+
+        assert_eq!(recent, vec![PathBuf::from("new.rs")]);
+    }
+
+    #[test]
+    fn test_measure_sums_file_sizes() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("a.rs"), "fn a() {}").unwrap();
+        fs::write(dir.path().join("b.rs"), "fn b() {}\n").unwrap();
+        let tools = vec![make_tool("rust", &["**/*.rs"], &[])];
+        let files: Vec<PathBuf> = vec!["a.rs".into(), "b.rs".into(), "missing.rs".into()];
+
+        let mut results = match_files(&files, &tools, false).unwrap();
+        assert_eq!(results[0].total_bytes, 0);
+        results[0].measure(dir.path(), &HashMap::new());
+
+        assert_eq!(results[0].total_bytes, 19);
+    }
+
+    #[test]
+    fn test_measure_uses_known_sizes_without_stat() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("a.rs"), "fn a() {}").unwrap();
+        let tools = vec![make_tool("rust", &["**/*.rs"], &[])];
+        let files: Vec<PathBuf> = vec!["a.rs".into(), "gone.rs".into()];
+
+        // gone.rs doesn't exist, so its size can only come from `known`
+        let known = HashMap::from([(Path::new("gone.rs"), 100)]);
+        let mut results = match_files(&files, &tools, false).unwrap();
+        results[0].measure(dir.path(), &known);
+
+        assert_eq!(results[0].total_bytes, 109);
+    }
+
+    #[test]
+    fn test_is_larger_than_compares_file_size() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("small.js"), "x".repeat(10)).unwrap();
+
+        assert!(is_larger_than(&dir.path().join("small.js"), 9));
+        assert!(!is_larger_than(&dir.path().join("small.js"), 10));
+        assert!(!is_larger_than(&dir.path().join("missing.js"), 0));
+    }
+
+    #[test]
+    fn test_is_binary_detects_nul_bytes() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("text.txt"), "plain text\n").unwrap();
+        fs::write(dir.path().join("blob.bin"), [0x89, b'P', b'N', b'G', 0, 0]).unwrap();
+
+        assert!(!is_binary(&dir.path().join("text.txt")));
+        assert!(is_binary(&dir.path().join("blob.bin")));
+        assert!(!is_binary(&dir.path().join("missing")));
+    }
 }
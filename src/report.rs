@@ -0,0 +1,643 @@
+//! Machine-readable run reports.
+//!
+//! Serializes per-tool results (`--format json`, `sarif`, `junit`) so CI can
+//! consume them without scraping the human-readable output, writes timing
+//! breakdowns (`--profile`), and turns tool output into GitHub Actions
+//! annotations (`--github-annotations`).
+
+use crate::exec::ToolResult;
+use anyhow::{Context, Result};
+use regex::Regex;
+use serde::Serialize;
+use serde_json::json;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// The outcome of running one tool, as collected by the main loop.
+pub struct ToolRun {
+    /// Tool name from the config
+    pub name: String,
+    /// Number of files handed to the tool
+    pub file_count: usize,
+    /// Total size of those files in bytes
+    pub total_bytes: u64,
+    /// Number of those files whose content the tool changed (format mode only)
+    pub changed_count: Option<usize>,
+    /// Whether the tool's failures are advisory (`allow_failure`)
+    pub allow_failure: bool,
+    /// The tool's `working_dir`, which paths in its output are relative to
+    pub working_dir: Option<String>,
+    /// Result of running the tool (error if it could not be executed)
+    pub result: Result<ToolResult>,
+}
+
+/// Top-level JSON report.
+#[derive(Debug, Serialize)]
+struct JsonReport<'a> {
+    success: bool,
+    check: bool,
+    duration_ms: u128,
+    tools: Vec<JsonTool<'a>>,
+}
+
+/// Per-tool entry in the JSON report.
+#[derive(Debug, Serialize)]
+struct JsonTool<'a> {
+    name: &'a str,
+    file_count: usize,
+    total_bytes: u64,
+    changed_count: Option<usize>,
+    success: bool,
+    allow_failure: bool,
+    duration_ms: u128,
+    error: Option<String>,
+    batches: Vec<JsonBatch<'a>>,
+}
+
+/// Per-batch entry in the JSON report.
+#[derive(Debug, Serialize)]
+struct JsonBatch<'a> {
+    command: &'a str,
+    success: bool,
+    exit_code: Option<i32>,
+    stdout: &'a str,
+    stderr: &'a str,
+}
+
+impl<'a> JsonTool<'a> {
+    fn new(run: &'a ToolRun) -> Self {
+        match &run.result {
+            Ok(result) => Self {
+                name: &run.name,
+                file_count: run.file_count,
+                total_bytes: run.total_bytes,
+                changed_count: run.changed_count,
+                success: result.success,
+                allow_failure: run.allow_failure,
+                duration_ms: result.duration.as_millis(),
+                error: None,
+                batches: result
+                    .batches
+                    .iter()
+                    .map(|b| JsonBatch {
+                        command: &b.command,
+                        success: b.success,
+                        exit_code: b.exit_code,
+                        stdout: &b.stdout,
+                        stderr: &b.stderr,
+                    })
+                    .collect(),
+            },
+            Err(e) => Self {
+                name: &run.name,
+                file_count: run.file_count,
+                total_bytes: run.total_bytes,
+                changed_count: run.changed_count,
+                success: false,
+                allow_failure: run.allow_failure,
+                duration_ms: 0,
+                error: Some(format!("{e:#}")),
+                batches: Vec::new(),
+            },
+        }
+    }
+}
+
+/// Render the results of a run as a pretty-printed JSON document.
+pub fn render_json(
+    runs: &[ToolRun],
+    success: bool,
+    check: bool,
+    elapsed: Duration,
+) -> Result<String> {
+    let report = JsonReport {
+        success,
+        check,
+        duration_ms: elapsed.as_millis(),
+        tools: runs.iter().map(JsonTool::new).collect(),
+    };
+
+    serde_json::to_string_pretty(&report).context("Failed to serialize JSON report")
+}
+
+/// Render a JUnit XML report with one `<testcase>` per tool.
+///
+/// Failing tools get a `<failure>` with each failing batch's command and output;
+/// tools that could not be run get an `<error>`.
+pub fn render_junit(runs: &[ToolRun], elapsed: Duration) -> String {
+    let failures = runs
+        .iter()
+        .filter(|run| matches!(&run.result, Ok(r) if !r.success))
+        .count();
+    let errors = runs.iter().filter(|run| run.result.is_err()).count();
+    let counts = format!(
+        r#"tests="{}" failures="{}" errors="{}" time="{:.3}""#,
+        runs.len(),
+        failures,
+        errors,
+        elapsed.as_secs_f64()
+    );
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str(&format!("<testsuites name=\"ffx\" {counts}>\n"));
+    xml.push_str(&format!("  <testsuite name=\"ffx\" {counts}>\n"));
+
+    for run in runs {
+        let name = escape_xml(&run.name);
+        match &run.result {
+            Ok(result) if result.success => xml.push_str(&format!(
+                "    <testcase name=\"{name}\" classname=\"ffx\" time=\"{:.3}\"/>\n",
+                result.duration.as_secs_f64()
+            )),
+            Ok(result) => {
+                let failed: Vec<_> = result.batches.iter().filter(|b| !b.success).collect();
+                let message = failed
+                    .first()
+                    .map(|b| b.exit_description())
+                    .unwrap_or_default();
+                let output: String = failed
+                    .iter()
+                    .map(|b| format!("$ {}\n{}{}", b.command, b.stdout, b.stderr))
+                    .collect();
+                xml.push_str(&format!(
+                    "    <testcase name=\"{name}\" classname=\"ffx\" time=\"{:.3}\">\n",
+                    result.duration.as_secs_f64()
+                ));
+                xml.push_str(&format!(
+                    "      <failure message=\"{}\">{}</failure>\n",
+                    escape_xml(&message),
+                    escape_xml(&output)
+                ));
+                xml.push_str("    </testcase>\n");
+            }
+            Err(e) => {
+                xml.push_str(&format!(
+                    "    <testcase name=\"{name}\" classname=\"ffx\" time=\"0.000\">\n"
+                ));
+                xml.push_str(&format!(
+                    "      <error message=\"{}\"/>\n",
+                    escape_xml(&format!("{e:#}"))
+                ));
+                xml.push_str("    </testcase>\n");
+            }
+        }
+    }
+
+    xml.push_str("  </testsuite>\n</testsuites>");
+    xml
+}
+
+/// Escape text for an XML attribute or element body.
+fn escape_xml(value: &str) -> String {
+    value
+        .chars()
+        .filter(|&c| c == '\t' || c == '\n' || c == '\r' || c >= ' ')
+        .fold(String::with_capacity(value.len()), |mut out, c| {
+            match c {
+                '&' => out.push_str("&amp;"),
+                '<' => out.push_str("&lt;"),
+                '>' => out.push_str("&gt;"),
+                '"' => out.push_str("&quot;"),
+                '\'' => out.push_str("&apos;"),
+                c => out.push(c),
+            }
+            out
+        })
+}
+
+/// Per-tool entry in a `--profile` timing breakdown.
+#[derive(Debug, Serialize)]
+struct ProfileEntry<'a> {
+    tool: &'a str,
+    batches: usize,
+    files: usize,
+    bytes: u64,
+    duration_ms: u128,
+    success: bool,
+}
+
+/// Render a JSON array with each tool's batch count, file count and size, and duration.
+pub fn render_profile(runs: &[ToolRun]) -> Result<String> {
+    let entries: Vec<ProfileEntry> = runs
+        .iter()
+        .map(|run| {
+            let result = run.result.as_ref().ok();
+            ProfileEntry {
+                tool: &run.name,
+                batches: result.map_or(0, |r| r.batches.len()),
+                files: run.file_count,
+                bytes: run.total_bytes,
+                duration_ms: result.map_or(0, |r| r.duration.as_millis()),
+                success: result.is_some_and(|r| r.success),
+            }
+        })
+        .collect();
+
+    serde_json::to_string_pretty(&entries).context("Failed to serialize profile")
+}
+
+/// Render failing tools as a SARIF 2.1.0 log, one `run` per tool.
+///
+/// Output of tools with an entry in `patterns` (their `annotation_pattern`) becomes one
+/// result per matching line; other failing tools contribute a single result without a
+/// location.
+pub fn render_sarif(runs: &[ToolRun], patterns: &HashMap<String, Regex>) -> Result<String> {
+    let sarif_runs: Vec<serde_json::Value> = runs
+        .iter()
+        .filter_map(|run| {
+            let results = sarif_results(run, patterns.get(&run.name))?;
+            Some(json!({
+                "tool": { "driver": { "name": run.name } },
+                "results": results,
+            }))
+        })
+        .collect();
+
+    let log = json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": sarif_runs,
+    });
+
+    serde_json::to_string_pretty(&log).context("Failed to serialize SARIF report")
+}
+
+/// SARIF results for one tool, or `None` if it succeeded.
+fn sarif_results(run: &ToolRun, pattern: Option<&Regex>) -> Option<Vec<serde_json::Value>> {
+    let result = match &run.result {
+        Ok(result) if result.success => return None,
+        Ok(result) => result,
+        Err(e) => return Some(vec![sarif_result(&format!("{e:#}"), None)]),
+    };
+
+    let mut results: Vec<serde_json::Value> = pattern
+        .map(|pattern| {
+            result
+                .batches
+                .iter()
+                .flat_map(|b| [b.stdout.as_str(), b.stderr.as_str()])
+                .flat_map(|output| diagnostics(pattern, output, run.working_dir.as_deref()))
+                .map(|d| sarif_result(d.message, Some(&d)))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    if results.is_empty() {
+        let exit = result
+            .batches
+            .iter()
+            .find(|b| !b.success)
+            .map(|b| b.exit_description())
+            .unwrap_or_default();
+        results.push(sarif_result(&format!("{} {}", run.name, exit), None));
+    }
+
+    Some(results)
+}
+
+fn sarif_result(message: &str, diagnostic: Option<&Diagnostic>) -> serde_json::Value {
+    let mut result = json!({
+        "level": "error",
+        "message": { "text": message },
+    });
+
+    if let Some(diagnostic) = diagnostic {
+        let mut location = json!({ "artifactLocation": { "uri": &diagnostic.file } });
+        if let Some(line) = diagnostic.line.and_then(|l| l.parse::<u64>().ok()) {
+            location["region"] = json!({ "startLine": line });
+        }
+        result["locations"] = json!([{ "physicalLocation": location }]);
+    }
+
+    result
+}
+
+/// A diagnostic extracted from one line of tool output by an `annotation_pattern`.
+struct Diagnostic<'a> {
+    /// Repo-relative path (or absolute, if the tool printed one)
+    file: Cow<'a, str>,
+    line: Option<&'a str>,
+    message: &'a str,
+}
+
+/// Extract a diagnostic from each line of `output` matching `pattern`.
+///
+/// `pattern` must have a `file` capture; `line` and `message` are optional, and the
+/// whole line is used as the message when `message` is absent. Relative files are
+/// resolved against `working_dir`, where the tool ran, to make them repo-relative.
+fn diagnostics<'a>(
+    pattern: &Regex,
+    output: &'a str,
+    working_dir: Option<&str>,
+) -> Vec<Diagnostic<'a>> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let caps = pattern.captures(line)?;
+            let file = caps.name("file")?.as_str();
+            let file = match working_dir {
+                Some(dir) if !file.starts_with('/') => {
+                    Cow::Owned(format!("{dir}/{}", file.strip_prefix("./").unwrap_or(file)))
+                }
+                _ => Cow::Borrowed(file),
+            };
+            Some(Diagnostic {
+                file,
+                line: caps.name("line").map(|m| m.as_str()),
+                message: caps.name("message").map_or(line, |m| m.as_str()),
+            })
+        })
+        .collect()
+}
+
+/// Build `::error` workflow commands from each line of `output` matching `pattern`.
+///
+/// `working_dir` is the directory the tool ran in, if not the repo root (see `diagnostics`).
+pub fn github_annotations(pattern: &Regex, output: &str, working_dir: Option<&str>) -> Vec<String> {
+    diagnostics(pattern, output, working_dir)
+        .into_iter()
+        .map(|d| {
+            let mut properties = format!("file={}", escape_property(&d.file));
+            if let Some(line_no) = d.line {
+                properties.push_str(&format!(",line={}", escape_property(line_no)));
+            }
+
+            format!("::error {properties}::{}", escape_data(d.message))
+        })
+        .collect()
+}
+
+/// Escape a workflow command message.
+fn escape_data(value: &str) -> String {
+    value
+        .replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// Escape a workflow command property value.
+fn escape_property(value: &str) -> String {
+    escape_data(value).replace(':', "%3A").replace(',', "%2C")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exec::BatchResult;
+
+    fn batch(success: bool, stdout: &str) -> BatchResult {
+        BatchResult {
+            success,
+            stdout: stdout.to_string(),
+            command: "echo file.txt".to_string(),
+            exit_code: Some(if success { 0 } else { 1 }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_render_json_includes_tool_results() {
+        let runs = vec![ToolRun {
+            name: "prettier".to_string(),
+            file_count: 2,
+            total_bytes: 2048,
+            changed_count: Some(1),
+            allow_failure: false,
+            working_dir: None,
+            result: Ok(ToolResult {
+                success: false,
+                batches: vec![batch(false, "needs formatting")],
+                duration: Duration::from_millis(1500),
+            }),
+        }];
+
+        let json = render_json(&runs, false, true, Duration::from_secs(2)).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(value["success"], false);
+        assert_eq!(value["check"], true);
+        assert_eq!(value["duration_ms"], 2000);
+        assert_eq!(value["tools"][0]["name"], "prettier");
+        assert_eq!(value["tools"][0]["file_count"], 2);
+        assert_eq!(value["tools"][0]["total_bytes"], 2048);
+        assert_eq!(value["tools"][0]["changed_count"], 1);
+        assert_eq!(value["tools"][0]["duration_ms"], 1500);
+        assert_eq!(
+            value["tools"][0]["batches"][0]["stdout"],
+            "needs formatting"
+        );
+        assert_eq!(value["tools"][0]["batches"][0]["exit_code"], 1);
+        assert!(value["tools"][0]["error"].is_null());
+    }
+
+    #[test]
+    fn test_render_json_reports_execution_errors() {
+        let runs = vec![ToolRun {
+            name: "broken".to_string(),
+            file_count: 1,
+            total_bytes: 0,
+            changed_count: None,
+            allow_failure: false,
+            working_dir: None,
+            result: Err(anyhow::anyhow!("Failed to execute 'broken'")),
+        }];
+
+        let json = render_json(&runs, false, false, Duration::ZERO).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(value["tools"][0]["success"], false);
+        assert_eq!(value["tools"][0]["error"], "Failed to execute 'broken'");
+    }
+
+    #[test]
+    fn test_github_annotations_from_matching_lines() {
+        let pattern = Regex::new(r"^(?P<file>[^:]+):(?P<line>\d+): (?P<message>.*)$").unwrap();
+        let output = "src/a.rs:12: trailing whitespace\nnot a match\nsrc/b,c.rs:3: 100% off\n";
+
+        assert_eq!(
+            github_annotations(&pattern, output, None),
+            vec![
+                "::error file=src/a.rs,line=12::trailing whitespace",
+                "::error file=src/b%2Cc.rs,line=3::100%25 off",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_github_annotations_without_message_uses_line() {
+        let pattern = Regex::new(r"^(?P<file>\S+\.md)$").unwrap();
+
+        assert_eq!(
+            github_annotations(&pattern, "README.md\n", None),
+            vec!["::error file=README.md::README.md"]
+        );
+    }
+
+    #[test]
+    fn test_github_annotations_resolve_paths_from_working_dir() {
+        let pattern = Regex::new(r"^(?P<file>[^:]+):(?P<line>\d+): (?P<message>.*)$").unwrap();
+        let output = "src/a.ts:1: bad\n./b.ts:2: worse\n/abs/c.ts:3: worst\n";
+
+        assert_eq!(
+            github_annotations(&pattern, output, Some("frontend")),
+            vec![
+                "::error file=frontend/src/a.ts,line=1::bad",
+                "::error file=frontend/b.ts,line=2::worse",
+                "::error file=/abs/c.ts,line=3::worst",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_render_junit_marks_failures_and_errors() {
+        let runs = vec![
+            ToolRun {
+                name: "ok".to_string(),
+                file_count: 1,
+                total_bytes: 0,
+                changed_count: None,
+                allow_failure: false,
+                working_dir: None,
+                result: Ok(ToolResult {
+                    success: true,
+                    batches: vec![batch(true, "")],
+                    duration: Duration::from_millis(500),
+                }),
+            },
+            ToolRun {
+                name: "lint".to_string(),
+                file_count: 1,
+                total_bytes: 0,
+                changed_count: None,
+                allow_failure: false,
+                working_dir: None,
+                result: Ok(ToolResult {
+                    success: false,
+                    batches: vec![batch(false, "a.rs: use <T> & \"more\"\n")],
+                    duration: Duration::ZERO,
+                }),
+            },
+            ToolRun {
+                name: "broken".to_string(),
+                file_count: 1,
+                total_bytes: 0,
+                changed_count: None,
+                allow_failure: false,
+                working_dir: None,
+                result: Err(anyhow::anyhow!("Failed to execute 'broken'")),
+            },
+        ];
+
+        let xml = render_junit(&runs, Duration::from_secs(1));
+
+        assert!(xml
+            .contains(r#"<testsuites name="ffx" tests="3" failures="1" errors="1" time="1.000">"#));
+        assert!(xml.contains(r#"<testcase name="ok" classname="ffx" time="0.500"/>"#));
+        assert!(xml.contains(r#"<failure message="exited with code 1">$ echo file.txt"#));
+        assert!(xml.contains("a.rs: use &lt;T&gt; &amp; &quot;more&quot;"));
+        assert!(xml.contains(r#"<error message="Failed to execute &apos;broken&apos;"/>"#));
+    }
+
+    #[test]
+    fn test_render_profile_lists_tool_timings() {
+        let runs = vec![ToolRun {
+            name: "prettier".to_string(),
+            file_count: 3,
+            total_bytes: 4096,
+            changed_count: None,
+            allow_failure: false,
+            working_dir: None,
+            result: Ok(ToolResult {
+                success: true,
+                batches: vec![batch(true, ""), batch(true, "")],
+                duration: Duration::from_millis(250),
+            }),
+        }];
+
+        let profile = render_profile(&runs).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&profile).unwrap();
+
+        assert_eq!(
+            value,
+            serde_json::json!([{
+                "tool": "prettier",
+                "batches": 2,
+                "files": 3,
+                "bytes": 4096,
+                "duration_ms": 250,
+                "success": true,
+            }])
+        );
+    }
+
+    #[test]
+    fn test_render_sarif_reports_failing_tools() {
+        let runs = vec![
+            ToolRun {
+                name: "lint".to_string(),
+                file_count: 1,
+                total_bytes: 0,
+                changed_count: None,
+                allow_failure: false,
+                working_dir: Some("web".to_string()),
+                result: Ok(ToolResult {
+                    success: false,
+                    batches: vec![batch(false, "src/a.rs:7: bad\nnoise\n")],
+                    duration: Duration::ZERO,
+                }),
+            },
+            ToolRun {
+                name: "fmt".to_string(),
+                file_count: 1,
+                total_bytes: 0,
+                changed_count: None,
+                allow_failure: false,
+                working_dir: None,
+                result: Ok(ToolResult {
+                    success: false,
+                    batches: vec![batch(false, "")],
+                    duration: Duration::ZERO,
+                }),
+            },
+            ToolRun {
+                name: "ok".to_string(),
+                file_count: 1,
+                total_bytes: 0,
+                changed_count: None,
+                allow_failure: false,
+                working_dir: None,
+                result: Ok(ToolResult {
+                    success: true,
+                    batches: vec![batch(true, "")],
+                    duration: Duration::ZERO,
+                }),
+            },
+        ];
+        let patterns = HashMap::from([(
+            "lint".to_string(),
+            Regex::new(r"^(?P<file>[^:]+):(?P<line>\d+): (?P<message>.*)$").unwrap(),
+        )]);
+
+        let sarif = render_sarif(&runs, &patterns).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&sarif).unwrap();
+
+        assert_eq!(value["version"], "2.1.0");
+        assert_eq!(value["runs"].as_array().unwrap().len(), 2);
+
+        let lint = &value["runs"][0];
+        assert_eq!(lint["tool"]["driver"]["name"], "lint");
+        assert_eq!(lint["results"].as_array().unwrap().len(), 1);
+        assert_eq!(lint["results"][0]["message"]["text"], "bad");
+        let location = &lint["results"][0]["locations"][0]["physicalLocation"];
+        assert_eq!(location["artifactLocation"]["uri"], "web/src/a.rs");
+        assert_eq!(location["region"]["startLine"], 7);
+
+        let fmt = &value["runs"][1];
+        assert_eq!(
+            fmt["results"][0]["message"]["text"],
+            "fmt exited with code 1"
+        );
+        assert!(fmt["results"][0]["locations"].is_null());
+    }
+}
@@ -0,0 +1,159 @@
+//! Watch mode for ffx.
+//!
+//! Watches the repository for file changes and re-runs the formatters after
+//! events settle. Ctrl-C stops watching and exits cleanly.
+
+use anyhow::{Context, Result};
+use notify::{EventKind, RecursiveMode, Watcher};
+use std::path::{Component, Path};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::time::Duration;
+
+/// How long the filesystem must stay quiet before re-running.
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// Messages delivered to the watch loop.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Signal {
+    /// A relevant file changed
+    Changed,
+    /// Ctrl-C was pressed
+    Stop,
+}
+
+/// Run `on_change` once, then again after every settled batch of file changes under `root`.
+///
+/// Errors from `on_change` are printed and watching continues. Events caused by the
+/// formatters themselves (written while `on_change` runs) are discarded so a run
+/// doesn't retrigger itself.
+pub fn watch<F>(root: &Path, mut on_change: F) -> Result<()>
+where
+    F: FnMut() -> Result<()>,
+{
+    let (tx, rx) = mpsc::channel();
+
+    let stop_tx = tx.clone();
+    ctrlc::set_handler(move || {
+        crate::exec::interrupt_running();
+        let _ = stop_tx.send(Signal::Stop);
+    })
+    .context("Failed to install Ctrl-C handler")?;
+
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+        if let Ok(event) = res {
+            if is_relevant(&event) {
+                let _ = tx.send(Signal::Changed);
+            }
+        }
+    })
+    .context("Failed to create file watcher")?;
+
+    watcher
+        .watch(root, RecursiveMode::Recursive)
+        .with_context(|| format!("Failed to watch {}", root.display()))?;
+
+    loop {
+        if let Err(e) = on_change() {
+            eprintln!("error: {e:#}");
+        }
+
+        // Drop events produced by the formatters writing files
+        if settle(&rx) == Signal::Stop {
+            break;
+        }
+
+        println!();
+        println!("Watching for changes (Ctrl-C to stop)...");
+
+        match rx.recv() {
+            Ok(Signal::Changed) => {}
+            Ok(Signal::Stop) | Err(_) => break,
+        }
+
+        if settle(&rx) == Signal::Stop {
+            break;
+        }
+    }
+
+    println!();
+    println!("Stopped watching.");
+
+    Ok(())
+}
+
+/// Wait until no events arrive for `DEBOUNCE`, returning `Stop` if Ctrl-C was pressed.
+fn settle(rx: &Receiver<Signal>) -> Signal {
+    loop {
+        match rx.recv_timeout(DEBOUNCE) {
+            Ok(Signal::Changed) => continue,
+            Ok(Signal::Stop) | Err(RecvTimeoutError::Disconnected) => return Signal::Stop,
+            Err(RecvTimeoutError::Timeout) => return Signal::Changed,
+        }
+    }
+}
+
+/// Whether an event should trigger a re-run.
+///
+/// Ignores read-only access events and anything inside `.git`, which git itself
+/// touches when ffx lists changed files.
+fn is_relevant(event: &notify::Event) -> bool {
+    if matches!(event.kind, EventKind::Access(_)) {
+        return false;
+    }
+
+    event.paths.iter().any(|path| !in_git_dir(path))
+}
+
+/// Whether a path lies inside a `.git` directory.
+fn in_git_dir(path: &Path) -> bool {
+    path.components()
+        .any(|c| matches!(c, Component::Normal(name) if name == ".git"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use notify::event::{AccessKind, ModifyKind};
+    use std::path::PathBuf;
+
+    fn event(kind: EventKind, path: &str) -> notify::Event {
+        notify::Event::new(kind).add_path(PathBuf::from(path))
+    }
+
+    #[test]
+    fn test_modify_event_is_relevant() {
+        let e = event(EventKind::Modify(ModifyKind::Any), "/repo/src/main.rs");
+        assert!(is_relevant(&e));
+    }
+
+    #[test]
+    fn test_access_event_is_ignored() {
+        let e = event(EventKind::Access(AccessKind::Any), "/repo/src/main.rs");
+        assert!(!is_relevant(&e));
+    }
+
+    #[test]
+    fn test_git_dir_event_is_ignored() {
+        let e = event(EventKind::Modify(ModifyKind::Any), "/repo/.git/index");
+        assert!(!is_relevant(&e));
+    }
+
+    #[test]
+    fn test_settle_waits_for_quiet_period() {
+        let (tx, rx) = mpsc::channel();
+        tx.send(Signal::Changed).unwrap();
+        tx.send(Signal::Changed).unwrap();
+
+        assert_eq!(settle(&rx), Signal::Changed);
+        assert!(rx.try_recv().is_err(), "queued events should be consumed");
+    }
+
+    #[test]
+    fn test_settle_stops_on_ctrl_c() {
+        let (tx, rx) = mpsc::channel();
+        tx.send(Signal::Changed).unwrap();
+        tx.send(Signal::Stop).unwrap();
+
+        assert_eq!(settle(&rx), Signal::Stop);
+    }
+}
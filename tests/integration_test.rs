@@ -3,6 +3,7 @@
 //! These tests run the actual ffx binary and verify its behavior.
 
 use std::fs;
+use std::io::Write;
 use std::process::Command;
 
 /// Get the path to the ffx binary (built by cargo test).
@@ -23,6 +24,29 @@ fn setup_test_dir(config_content: &str) -> tempfile::TempDir {
     dir
 }
 
+/// Initialize a git repo in `dir`, write each file with placeholder content, and stage everything.
+fn init_repo_with_files(dir: &std::path::Path, files: &[&str]) {
+    Command::new("git")
+        .args(["init"])
+        .current_dir(dir)
+        .output()
+        .unwrap();
+
+    for file in files {
+        let path = dir.join(file);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).unwrap();
+        }
+        fs::write(path, "content").unwrap();
+    }
+
+    Command::new("git")
+        .args(["add", "."])
+        .current_dir(dir)
+        .output()
+        .unwrap();
+}
+
 #[test]
 fn test_help_flag() {
     let output = Command::new(ffx_binary())
@@ -53,6 +77,19 @@ fn test_version_flag() {
     assert!(stdout.contains("ffx"));
 }
 
+#[test]
+fn test_completions_subcommand() {
+    let output = Command::new(ffx_binary())
+        .args(["completions", "zsh"])
+        .output()
+        .expect("Failed to run ffx");
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("#compdef ffx"));
+    assert!(stdout.contains("--staged"));
+}
+
 #[test]
 fn test_missing_config_file() {
     let dir = tempfile::tempdir().unwrap();
@@ -106,7 +143,112 @@ fn test_init_installs_pre_commit_hook() {
 
     let hook_path = dir.path().join(".git/hooks/pre-commit");
     let hook = fs::read_to_string(&hook_path).expect("Hook should be written");
-    assert!(hook.contains("ffx --staged"));
+    assert!(hook.contains("ffx --staged --restage"));
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        let mode = fs::metadata(&hook_path)
+            .expect("Should read hook metadata")
+            .permissions()
+            .mode();
+        assert!(mode & 0o111 != 0, "Hook should be executable");
+    }
+}
+
+#[test]
+fn test_init_respects_core_hooks_path() {
+    let dir = tempfile::tempdir().unwrap();
+
+    for args in [&["init"][..], &["config", "core.hooksPath", ".husky/_"]] {
+        Command::new("git")
+            .args(args)
+            .current_dir(dir.path())
+            .output()
+            .expect("Failed to run git");
+    }
+
+    let output = Command::new(ffx_binary())
+        .current_dir(dir.path())
+        .arg("init")
+        .output()
+        .expect("Failed to run ffx init");
+
+    assert!(output.status.success());
+    let hook = fs::read_to_string(dir.path().join(".husky/_/pre-commit"))
+        .expect("Hook should be written to core.hooksPath");
+    assert!(hook.contains("ffx --staged --restage"));
+    assert!(!dir.path().join(".git/hooks/pre-commit").exists());
+}
+
+#[test]
+fn test_init_append_extends_existing_hook_once() {
+    let dir = tempfile::tempdir().unwrap();
+
+    Command::new("git")
+        .args(["init"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to init git");
+
+    let hook_path = dir.path().join(".git/hooks/pre-commit");
+    fs::create_dir_all(hook_path.parent().unwrap()).unwrap();
+    fs::write(&hook_path, "#!/bin/sh\nnpm run lint").unwrap();
+
+    let init = |args: &[&str]| {
+        Command::new(ffx_binary())
+            .current_dir(dir.path())
+            .arg("init")
+            .args(args)
+            .output()
+            .expect("Failed to run ffx init")
+    };
+
+    let output = init(&[]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("ffx init --append"), "stderr: {stderr}");
+
+    assert!(init(&["--append"]).status.success());
+    let hook = fs::read_to_string(&hook_path).unwrap();
+    assert!(hook.starts_with("#!/bin/sh\nnpm run lint\n"), "{hook}");
+    assert!(hook.contains("\n# fast-format-x\n"), "{hook}");
+    assert!(
+        hook.trim_end().ends_with("ffx --staged --restage"),
+        "{hook}"
+    );
+    assert_eq!(hook.matches("#!/bin/sh").count(), 1);
+
+    let output = init(&["--append"]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("already configured"));
+    assert_eq!(fs::read_to_string(&hook_path).unwrap(), hook);
+}
+
+#[test]
+fn test_init_installs_pre_push_hook() {
+    let dir = tempfile::tempdir().unwrap();
+
+    Command::new("git")
+        .args(["init"])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to init git");
+
+    let output = Command::new(ffx_binary())
+        .current_dir(dir.path())
+        .args(["init", "--hook", "pre-push"])
+        .output()
+        .expect("Failed to run ffx init");
+
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Pre-push hook installed"));
+    assert!(!dir.path().join(".git/hooks/pre-commit").exists());
+
+    let hook_path = dir.path().join(".git/hooks/pre-push");
+    let hook = fs::read_to_string(&hook_path).expect("Hook should be written");
+    assert!(hook.contains("ffx --check --base"));
 
     #[cfg(unix)]
     {
@@ -144,6 +286,71 @@ fn test_init_creates_config_template() {
     assert!(config.contains("version: 1"));
 }
 
+#[test]
+fn test_global_config_fallback() {
+    let dir = tempfile::tempdir().unwrap();
+    init_repo_with_files(dir.path(), &["test.txt"]);
+    let config_home = tempfile::tempdir().unwrap();
+    fs::create_dir(config_home.path().join("fast-format-x")).unwrap();
+    fs::write(
+        config_home.path().join("fast-format-x/config.yaml"),
+        r#"
+version: 1
+tools:
+  - name: global-echo
+    include: ["**/*.txt"]
+    cmd: echo
+"#,
+    )
+    .unwrap();
+
+    let output = Command::new(ffx_binary())
+        .current_dir(dir.path())
+        .env("XDG_CONFIG_HOME", config_home.path())
+        .args(["--all"])
+        .output()
+        .expect("Failed to run ffx");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "stdout: {stdout}");
+    assert!(stdout.contains("global-echo"), "stdout: {stdout}");
+}
+
+#[test]
+fn test_init_detects_language_template() {
+    let dir = tempfile::tempdir().unwrap();
+    git(dir.path(), &["init", "-q"]);
+    fs::write(dir.path().join("Cargo.toml"), "[package]\n").unwrap();
+
+    let output = Command::new(ffx_binary())
+        .current_dir(dir.path())
+        .arg("init")
+        .output()
+        .expect("Failed to run ffx init");
+
+    assert!(output.status.success());
+    let config = fs::read_to_string(dir.path().join(".fast-format-x.yaml")).unwrap();
+    assert!(config.contains("name: clippy"), "config: {config}");
+}
+
+#[test]
+fn test_init_language_flag_overrides_detection() {
+    let dir = tempfile::tempdir().unwrap();
+    git(dir.path(), &["init", "-q"]);
+    fs::write(dir.path().join("Cargo.toml"), "[package]\n").unwrap();
+
+    let output = Command::new(ffx_binary())
+        .current_dir(dir.path())
+        .args(["init", "--language", "python"])
+        .output()
+        .expect("Failed to run ffx init");
+
+    assert!(output.status.success());
+    let config = fs::read_to_string(dir.path().join(".fast-format-x.yaml")).unwrap();
+    assert!(config.contains("name: black"), "config: {config}");
+    assert!(!config.contains("clippy"));
+}
+
 #[test]
 fn test_init_from_subdirectory_creates_config_in_current_dir() {
     // Regression test: running `ffx init` from a subdirectory should create
@@ -435,6 +642,113 @@ tools:
     assert!(stderr.contains("echo hello"));
 }
 
+#[test]
+fn test_verbose_shows_tool_duration() {
+    let config = r#"
+version: 1
+tools:
+  - name: timed
+    include: ["**/*.txt"]
+    cmd: "true"
+"#;
+    let dir = setup_test_dir(config);
+    init_repo_with_files(dir.path(), &["test.txt"]);
+
+    let output = Command::new(ffx_binary())
+        .current_dir(dir.path())
+        .args(["--all", "--verbose", "--color", "never"])
+        .output()
+        .expect("Failed to run ffx");
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let line = stdout
+        .lines()
+        .find(|l| l.contains("[timed]"))
+        .expect("missing status line");
+    assert!(line.ends_with("s)"), "no duration in: {line}");
+}
+
+#[test]
+fn test_rust_log_emits_diagnostics_on_stderr() {
+    let config = r#"
+version: 1
+tools:
+  - name: echo-test
+    include: ["**/*.txt"]
+    cmd: echo
+"#;
+    let dir = setup_test_dir(config);
+    init_repo_with_files(dir.path(), &["test.txt"]);
+
+    let run = |rust_log: Option<&str>| {
+        let mut cmd = Command::new(ffx_binary());
+        cmd.current_dir(dir.path())
+            .args(["--all"])
+            .env_remove("RUST_LOG");
+        if let Some(rust_log) = rust_log {
+            cmd.env("RUST_LOG", rust_log);
+        }
+        let output = cmd.output().expect("Failed to run ffx");
+        assert!(output.status.success());
+        (
+            String::from_utf8_lossy(&output.stdout).to_string(),
+            String::from_utf8_lossy(&output.stderr).to_string(),
+        )
+    };
+
+    let (quiet_stdout, quiet_stderr) = run(None);
+    assert!(!quiet_stderr.contains("batch finished"), "{quiet_stderr}");
+
+    let (stdout, stderr) = run(Some("ffx=debug"));
+    assert!(stderr.contains("config loaded"), "{stderr}");
+    assert!(stderr.contains("collected files"), "{stderr}");
+    assert!(stderr.contains("tool=echo-test"), "{stderr}");
+    assert!(stderr.contains("batch finished"), "{stderr}");
+    // Human output is unchanged apart from timings
+    assert_eq!(stdout.lines().count(), quiet_stdout.lines().count());
+}
+
+#[test]
+fn test_no_parallel_runs_tools_and_batches_in_order() {
+    let config = r#"
+version: 1
+tools:
+  - name: slow
+    include: ["**/*.txt"]
+    cmd: sh
+    args: ["-c", "sleep 0.1; echo \"$1\" >> order.log", "sh"]
+    max_files_per_batch: 1
+  - name: fast
+    include: ["**/*.md"]
+    cmd: sh
+    args: ["-c", "echo fast >> order.log"]
+"#;
+    let dir = setup_test_dir(config);
+    init_repo_with_files(dir.path(), &["a.txt", "b.txt", "c.txt", "README.md"]);
+
+    let output = Command::new(ffx_binary())
+        .current_dir(dir.path())
+        .args(["--all", "--no-parallel", "--verbose"])
+        .output()
+        .expect("Failed to run ffx");
+
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("jobs: 1"));
+    assert_eq!(
+        fs::read_to_string(dir.path().join("order.log")).unwrap(),
+        "a.txt\nb.txt\nc.txt\nfast\n"
+    );
+
+    let output = Command::new(ffx_binary())
+        .current_dir(dir.path())
+        .args(["--all", "--no-parallel", "-j", "4"])
+        .output()
+        .expect("Failed to run ffx");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("cannot be used with"));
+}
+
 #[test]
 fn test_missing_command_error() {
     let config = r#"
@@ -617,6 +931,36 @@ tools:
     assert!(stdout.contains("Formatted"));
 }
 
+#[test]
+fn test_verbose_reports_subdirectory_scope() {
+    let config = r#"
+version: 1
+tools:
+  - name: cat-test
+    include: ["**/*.txt"]
+    cmd: cat
+"#;
+    let dir = setup_test_dir(config);
+    init_repo_with_files(dir.path(), &["root.txt", "src/a.txt", "src/b.txt"]);
+
+    let run = |cwd: &std::path::Path| {
+        let output = Command::new(ffx_binary())
+            .current_dir(cwd)
+            .args(["--all", "--verbose", "--config"])
+            .arg(dir.path().join(".fast-format-x.yaml"))
+            .output()
+            .expect("Failed to run ffx");
+        assert!(output.status.success());
+        String::from_utf8_lossy(&output.stderr).to_string()
+    };
+
+    let stderr = run(&dir.path().join("src"));
+    assert!(stderr.contains("scope: src/ (2 files)"), "stderr: {stderr}");
+
+    let stderr = run(dir.path());
+    assert!(!stderr.contains("scope:"), "stderr: {stderr}");
+}
+
 #[test]
 fn test_all_flag_from_subdirectory_excludes_parent_files() {
     // Regression test: running ffx --all from a subdirectory should ONLY format
@@ -957,6 +1301,86 @@ tools:
     );
 }
 
+#[test]
+fn test_diff_range_two_dot_includes_base_changes() {
+    let config = r#"
+version: 1
+tools:
+  - name: touch-test
+    include: ["**/*.txt"]
+    cmd: touch
+"#;
+    let dir = setup_test_dir(config);
+    let git = |args: &[&str]| {
+        let output = Command::new("git")
+            .args([
+                "-c",
+                "user.email=test@example.com",
+                "-c",
+                "user.name=Test User",
+            ])
+            .args(args)
+            .current_dir(dir.path())
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "git {args:?} failed");
+    };
+
+    // main edits shared.txt after feature branches off, so the two ranges differ
+    git(&["init", "-b", "main"]);
+    fs::write(dir.path().join("shared.txt"), "shared content").unwrap();
+    git(&["add", "."]);
+    git(&["commit", "-m", "Initial commit"]);
+    git(&["checkout", "-b", "feature"]);
+    fs::write(dir.path().join("feature.txt"), "feature content").unwrap();
+    git(&["add", "."]);
+    git(&["commit", "-m", "Add feature file"]);
+    git(&["checkout", "main"]);
+    fs::write(dir.path().join("shared.txt"), "edited upstream").unwrap();
+    git(&["commit", "-am", "Edit shared file"]);
+    git(&["checkout", "feature"]);
+
+    let list = |range: &str| {
+        let output = Command::new(ffx_binary())
+            .current_dir(dir.path())
+            .args(["--base", "main", "--diff-range", range, "--list"])
+            .output()
+            .expect("Failed to run ffx");
+        assert!(output.status.success(), "{range}");
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    };
+
+    let three_dot = list("three-dot");
+    assert!(
+        three_dot.contains("[touch-test] 1 file"),
+        "stdout: {three_dot}"
+    );
+    assert!(!three_dot.contains("shared.txt"), "stdout: {three_dot}");
+
+    let two_dot = list("two-dot");
+    assert!(
+        two_dot.contains("[touch-test] 2 files"),
+        "stdout: {two_dot}"
+    );
+    assert!(two_dot.contains("  feature.txt"), "stdout: {two_dot}");
+    assert!(two_dot.contains("  shared.txt"), "stdout: {two_dot}");
+}
+
+#[test]
+fn test_diff_range_requires_base() {
+    let dir = setup_test_dir("version: 1\ntools: []\n");
+
+    let output = Command::new(ffx_binary())
+        .current_dir(dir.path())
+        .args(["--diff-range", "two-dot"])
+        .output()
+        .expect("Failed to run ffx");
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("--diff-range"), "stderr: {stderr}");
+}
+
 #[test]
 fn test_base_flag_shows_correct_message_when_no_changes() {
     // Test that --base flag shows the correct message when no files changed
@@ -1180,3 +1604,2119 @@ fn test_update_subcommand_help() {
         "Should show --check flag. stdout: {stdout}"
     );
 }
+
+#[test]
+fn test_strategy_by_tool_runs_formatter() {
+    let config = r#"
+version: 1
+tools:
+  - name: echo-test
+    include: ["**/*.txt"]
+    cmd: echo
+    args: [formatted]
+"#;
+    let dir = setup_test_dir(config);
+
+    Command::new("git")
+        .args(["init"])
+        .current_dir(dir.path())
+        .output()
+        .unwrap();
+
+    fs::write(dir.path().join("test.txt"), "hello").unwrap();
+
+    Command::new("git")
+        .args(["add", "."])
+        .current_dir(dir.path())
+        .output()
+        .unwrap();
+
+    let output = Command::new(ffx_binary())
+        .current_dir(dir.path())
+        .args(["--all", "--strategy", "by-tool"])
+        .output()
+        .expect("Failed to run ffx");
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("[echo-test]"));
+    assert!(stdout.contains("Formatted"));
+}
+
+/// Config with one tool whose check command exits with `code`.
+fn check_exit_config(code: i32) -> String {
+    format!(
+        r#"
+version: 1
+tools:
+  - name: linter
+    include: ["**/*.txt"]
+    cmd: sh
+    check_args: ["-c", "echo 'needs formatting'; exit {code}"]
+"#
+    )
+}
+
+#[test]
+fn test_check_level_strict_fails_on_issues() {
+    let dir = setup_test_dir(&check_exit_config(1));
+    init_repo_with_files(dir.path(), &["test.txt"]);
+
+    let output = Command::new(ffx_binary())
+        .current_dir(dir.path())
+        .args(["--all", "--check"])
+        .output()
+        .expect("Failed to run ffx");
+
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn test_check_level_lenient_warns_on_issues() {
+    let dir = setup_test_dir(&check_exit_config(1));
+    init_repo_with_files(dir.path(), &["test.txt"]);
+
+    let output = Command::new(ffx_binary())
+        .current_dir(dir.path())
+        .args(["--all", "--check", "--check-level", "lenient"])
+        .output()
+        .expect("Failed to run ffx");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "stdout: {stdout}");
+    assert!(stdout.contains("1 tool reported formatting issues"));
+    assert!(stdout.contains("needs formatting"));
+    assert!(stdout.contains("exited with code 1"));
+}
+
+#[test]
+fn test_check_level_lenient_fails_on_tool_error() {
+    let dir = setup_test_dir(&check_exit_config(2));
+    init_repo_with_files(dir.path(), &["test.txt"]);
+
+    let output = Command::new(ffx_binary())
+        .current_dir(dir.path())
+        .args(["--all", "--check", "--check-level", "lenient"])
+        .output()
+        .expect("Failed to run ffx");
+
+    assert_eq!(output.status.code(), Some(1));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Some checks failed"));
+}
+
+#[test]
+fn test_format_json_emits_report() {
+    let config = r#"
+version: 1
+tools:
+  - name: echo-test
+    include: ["**/*.txt"]
+    cmd: echo
+    args: [formatted]
+  - name: failing
+    include: ["**/*.md"]
+    cmd: "false"
+"#;
+    let dir = setup_test_dir(config);
+    init_repo_with_files(dir.path(), &["test.txt", "README.md"]);
+
+    let output = Command::new(ffx_binary())
+        .current_dir(dir.path())
+        .args(["--all", "--format", "json"])
+        .output()
+        .expect("Failed to run ffx");
+
+    // One tool passed and one failed: partial failure
+    assert_eq!(output.status.code(), Some(4));
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let report: serde_json::Value =
+        serde_json::from_str(&stdout).expect("stdout should be a single JSON document");
+
+    assert_eq!(report["success"], false);
+    let tools = report["tools"].as_array().unwrap();
+    assert_eq!(tools.len(), 2);
+    assert_eq!(tools[0]["name"], "echo-test");
+    assert_eq!(tools[0]["file_count"], 1);
+    assert_eq!(tools[0]["success"], true);
+    assert!(tools[0]["duration_ms"].is_number());
+    assert!(tools[0]["batches"][0]["stdout"]
+        .as_str()
+        .unwrap()
+        .contains("formatted test.txt"));
+    assert_eq!(tools[1]["name"], "failing");
+    assert_eq!(tools[1]["success"], false);
+}
+
+#[test]
+fn test_format_json_with_no_files() {
+    let config = r#"
+version: 1
+tools:
+  - name: markdown
+    include: ["**/*.md"]
+    cmd: echo
+"#;
+    let dir = setup_test_dir(config);
+    init_repo_with_files(dir.path(), &["test.txt"]);
+
+    let output = Command::new(ffx_binary())
+        .current_dir(dir.path())
+        .args(["--all", "--format", "json"])
+        .output()
+        .expect("Failed to run ffx");
+
+    assert!(output.status.success());
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["success"], true);
+    assert!(report["tools"].as_array().unwrap().is_empty());
+}
+
+#[test]
+fn test_list_prints_plan_without_running() {
+    let config = r#"
+version: 1
+tools:
+  - name: marker
+    description: leaves a marker file behind
+    include: ["**/*.txt"]
+    cmd: sh
+    args: ["-c", "touch ran-marker"]
+"#;
+    let dir = setup_test_dir(config);
+    init_repo_with_files(dir.path(), &["a.txt", "sub/b.txt"]);
+
+    let output = Command::new(ffx_binary())
+        .current_dir(dir.path())
+        .args(["--all", "--list"])
+        .output()
+        .expect("Failed to run ffx");
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("[marker] 2 files"), "stdout: {stdout}");
+    assert!(stdout.contains("  leaves a marker file behind"));
+    assert!(stdout.contains("  a.txt"));
+    assert!(stdout.contains("  sub/b.txt"));
+    assert!(
+        !dir.path().join("ran-marker").exists(),
+        "--list should not run tools"
+    );
+}
+
+#[test]
+fn test_non_ascii_filenames_are_not_quoted() {
+    let config = r#"
+version: 1
+tools:
+  - name: text
+    include: ["**/*.txt"]
+    cmd: echo
+"#;
+    let dir = setup_test_dir(config);
+    init_repo_with_files(dir.path(), &["café.txt", "日本/naïve.txt"]);
+    Command::new("git")
+        .args(["config", "core.quotePath", "true"])
+        .current_dir(dir.path())
+        .output()
+        .unwrap();
+
+    for args in [&["--list"][..], &["--staged", "--list"]] {
+        let output = Command::new(ffx_binary())
+            .current_dir(dir.path())
+            .args(args)
+            .output()
+            .expect("Failed to run ffx");
+
+        assert!(output.status.success(), "{args:?}");
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(stdout.contains("[text] 2 files"), "stdout: {stdout}");
+        assert!(stdout.contains("  café.txt"), "stdout: {stdout}");
+        assert!(stdout.contains("  日本/naïve.txt"), "stdout: {stdout}");
+    }
+}
+
+#[test]
+fn test_only_runs_selected_tool() {
+    let config = r#"
+version: 1
+tools:
+  - name: text
+    include: ["**/*.txt"]
+    cmd: echo
+  - name: markdown
+    include: ["**/*.md"]
+    cmd: echo
+"#;
+    let dir = setup_test_dir(config);
+    init_repo_with_files(dir.path(), &["a.txt", "b.md"]);
+
+    let output = Command::new(ffx_binary())
+        .current_dir(dir.path())
+        .args(["--all", "--only", "markdown"])
+        .output()
+        .expect("Failed to run ffx");
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("[markdown]"));
+    assert!(!stdout.contains("[text]"));
+}
+
+#[test]
+fn test_only_unknown_tool_exits_with_config_error() {
+    let config = r#"
+version: 1
+tools:
+  - name: text
+    include: ["**/*.txt"]
+    cmd: echo
+"#;
+    let dir = setup_test_dir(config);
+    init_repo_with_files(dir.path(), &["a.txt"]);
+
+    let output = Command::new(ffx_binary())
+        .current_dir(dir.path())
+        .args(["--all", "--only", "prettier"])
+        .output()
+        .expect("Failed to run ffx");
+
+    assert_eq!(output.status.code(), Some(2));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("Unknown tool 'prettier'"),
+        "stderr: {stderr}"
+    );
+}
+
+#[test]
+fn test_only_conflicts_with_skip() {
+    let output = Command::new(ffx_binary())
+        .args(["--only", "a", "--skip", "b"])
+        .output()
+        .expect("Failed to run ffx");
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("cannot be used with"), "stderr: {stderr}");
+}
+
+#[test]
+fn test_cache_skips_unchanged_files() {
+    let config = r#"
+version: 1
+cache: true
+tools:
+  - name: echo-test
+    include: ["**/*.txt"]
+    cmd: echo
+"#;
+    let dir = setup_test_dir(config);
+    init_repo_with_files(dir.path(), &["a.txt"]);
+
+    let run = |args: &[&str]| {
+        let output = Command::new(ffx_binary())
+            .current_dir(dir.path())
+            .args(args)
+            .output()
+            .expect("Failed to run ffx");
+        assert!(output.status.success());
+        String::from_utf8_lossy(&output.stdout).to_string()
+    };
+
+    assert!(run(&["--all"]).contains("[echo-test] 1 file"));
+    assert!(run(&["--all"]).contains("unchanged since the last successful run"));
+    assert!(run(&["--all", "--no-cache"]).contains("[echo-test] 1 file"));
+
+    fs::write(dir.path().join("a.txt"), "edited").unwrap();
+    assert!(run(&["--all"]).contains("[echo-test] 1 file"));
+}
+
+#[test]
+fn test_cache_dir_env_and_clear() {
+    let config = r#"
+version: 1
+cache: true
+tools:
+  - name: echo-test
+    include: ["**/*.txt"]
+    cmd: echo
+"#;
+    let dir = setup_test_dir(config);
+    init_repo_with_files(dir.path(), &["a.txt"]);
+    let cache_dir = tempfile::tempdir().unwrap();
+
+    let run = |args: &[&str]| {
+        let output = Command::new(ffx_binary())
+            .current_dir(dir.path())
+            .env("FFX_CACHE_DIR", cache_dir.path())
+            .args(args)
+            .output()
+            .expect("Failed to run ffx");
+        assert!(output.status.success());
+        String::from_utf8_lossy(&output.stdout).to_string()
+    };
+
+    run(&["--all"]);
+    assert!(!dir.path().join(".git/ffx-cache.json").exists());
+    assert_eq!(fs::read_dir(cache_dir.path()).unwrap().count(), 1);
+    assert!(run(&["cache"]).contains("1 file recorded"));
+
+    assert!(run(&["cache", "--clear"]).contains("Cleared cache"));
+    assert_eq!(fs::read_dir(cache_dir.path()).unwrap().count(), 0);
+    assert!(run(&["cache", "--clear"]).contains("No cache to clear"));
+    assert!(run(&["--all"]).contains("[echo-test] 1 file"));
+}
+
+#[test]
+fn test_filename_with_space_is_passed_intact() {
+    let dir = setup_test_dir(
+        r#"
+version: 1
+tools:
+  - name: exists
+    include: ["**/*.txt"]
+    cmd: sh
+    args: ["-c", "for f; do test -f \"$f\" || exit 1; done", "sh"]
+"#,
+    );
+    init_repo_with_files(dir.path(), &["my file.txt", "docs/other notes.txt"]);
+
+    for flag in ["--all", "--staged"] {
+        let output = Command::new(ffx_binary())
+            .current_dir(dir.path())
+            .arg(flag)
+            .output()
+            .expect("Failed to run ffx");
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(output.status.success(), "{flag} stdout: {stdout}");
+        assert!(stdout.contains("2 files"), "{flag} stdout: {stdout}");
+    }
+}
+
+#[test]
+fn test_diff_mode_shows_diff_and_skips_tools_without_diff_args() {
+    let dir = setup_test_dir(
+        r#"
+version: 1
+tools:
+  - name: differ
+    include: ["**/*.txt"]
+    cmd: sh
+    args: ["-c", "echo wrote > formatted.out"]
+    diff_args: ["-c", "echo '-old'; echo '+new'; exit 1", "sh"]
+  - name: writer
+    include: ["**/*.md"]
+    cmd: sh
+    args: ["-c", "echo wrote > formatted.out"]
+"#,
+    );
+    init_repo_with_files(dir.path(), &["test.txt", "README.md"]);
+
+    let output = Command::new(ffx_binary())
+        .current_dir(dir.path())
+        .args(["--all", "--diff"])
+        .output()
+        .expect("Failed to run ffx");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "stdout: {stdout}");
+    assert!(stdout.contains("[writer] skipped: no diff_args configured"));
+    assert!(stdout.contains("Diff:"));
+    assert!(stdout.contains("+new"));
+    assert!(!dir.path().join("formatted.out").exists());
+    assert!(!stdout.contains('\x1b'), "stdout: {stdout}");
+
+    let output = Command::new(ffx_binary())
+        .current_dir(dir.path())
+        .args(["--all", "--diff", "--color", "always"])
+        .output()
+        .expect("Failed to run ffx");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("\x1b[32m+new"), "stdout: {stdout}");
+    assert!(stdout.contains("\x1b[31m-old"), "stdout: {stdout}");
+}
+
+#[test]
+fn test_config_expands_environment_variables() {
+    let dir = setup_test_dir(
+        r#"
+version: 1
+tools:
+  - name: writer
+    include: ["**/*.txt"]
+    cmd: sh
+    args: ["-c", "echo \"$0\" > expanded.out", "${FFX_TEST_GREETING}-$FFX_TEST_UNSET"]
+"#,
+    );
+    init_repo_with_files(dir.path(), &["test.txt"]);
+
+    let output = Command::new(ffx_binary())
+        .current_dir(dir.path())
+        .env("FFX_TEST_GREETING", "hello")
+        .env_remove("FFX_TEST_UNSET")
+        .arg("--all")
+        .output()
+        .expect("Failed to run ffx");
+
+    assert!(output.status.success());
+    assert_eq!(
+        fs::read_to_string(dir.path().join("expanded.out")).unwrap(),
+        "hello-$FFX_TEST_UNSET\n"
+    );
+}
+
+#[test]
+fn test_local_overlay_disables_tool() {
+    let dir = setup_test_dir(
+        r#"
+version: 1
+tools:
+  - name: slow-linter
+    include: ["**/*.txt"]
+    cmd: "false"
+  - name: echo-test
+    include: ["**/*.txt"]
+    cmd: echo
+"#,
+    );
+    init_repo_with_files(dir.path(), &["test.txt"]);
+    fs::write(
+        dir.path().join(".fast-format-x.local.yaml"),
+        "tools:\n  - name: slow-linter\n    disabled: true\n",
+    )
+    .unwrap();
+
+    let output = Command::new(ffx_binary())
+        .current_dir(dir.path())
+        .arg("--all")
+        .output()
+        .expect("Failed to run ffx");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "stdout: {stdout}");
+    assert!(stdout.contains("[echo-test] 1 file"), "stdout: {stdout}");
+    assert!(!stdout.contains("slow-linter"), "stdout: {stdout}");
+}
+
+#[test]
+fn test_disabled_tool_is_skipped_everywhere() {
+    let dir = setup_test_dir(
+        r#"
+version: 1
+tools:
+  - name: ghost
+    include: ["**/*.txt"]
+    cmd: ffx-definitely-missing-tool
+    disabled: true
+  - name: echo-test
+    include: ["**/*.txt"]
+    cmd: echo
+"#,
+    );
+    init_repo_with_files(dir.path(), &["test.txt"]);
+
+    let run = |args: &[&str]| {
+        let output = Command::new(ffx_binary())
+            .current_dir(dir.path())
+            .args(args)
+            .output()
+            .expect("Failed to run ffx");
+        assert!(output.status.success(), "{args:?}: {output:?}");
+        (
+            String::from_utf8_lossy(&output.stdout).to_string(),
+            String::from_utf8_lossy(&output.stderr).to_string(),
+        )
+    };
+
+    let (stdout, stderr) = run(&["--all", "--verbose", "--warn-unused-tools"]);
+    assert!(
+        stdout.contains("echo-test (1 file, 7 B)"),
+        "stdout: {stdout}"
+    );
+    assert!(!stdout.contains("ghost"), "stdout: {stdout}");
+    assert!(
+        stderr.contains("skipped 1 disabled tool"),
+        "stderr: {stderr}"
+    );
+    assert!(
+        !stderr.contains("'ghost' matched no files"),
+        "stderr: {stderr}"
+    );
+
+    let (stdout, _) = run(&["doctor"]);
+    assert!(!stdout.contains("ghost"), "stdout: {stdout}");
+
+    let (stdout, _) = run(&["--config-check"]);
+    assert!(stdout.contains("(disabled)"), "stdout: {stdout}");
+}
+
+#[test]
+fn test_diff_conflicts_with_check() {
+    let output = Command::new(ffx_binary())
+        .args(["--diff", "--check"])
+        .output()
+        .expect("Failed to run ffx");
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("cannot be used with"));
+}
+
+#[test]
+fn test_files_from_stdin() {
+    let dir = setup_test_dir(
+        r#"
+version: 1
+tools:
+  - name: echo
+    include: ["**/*.txt"]
+    cmd: echo
+"#,
+    );
+    init_repo_with_files(dir.path(), &["a.txt", "b.txt"]);
+
+    let mut child = Command::new(ffx_binary())
+        .current_dir(dir.path())
+        .args(["--files-from", "-", "--verbose"])
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .expect("Failed to run ffx");
+    child.stdin.take().unwrap().write_all(b"a.txt\n\n").unwrap();
+    let output = child.wait_with_output().unwrap();
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "stdout: {stdout}");
+    assert!(stdout.contains("[echo] 1 file"), "stdout: {stdout}");
+    assert!(stdout.contains("a.txt"));
+    assert!(!stdout.contains("b.txt"));
+}
+
+#[test]
+fn test_files_from_file() {
+    let dir = setup_test_dir(
+        r#"
+version: 1
+tools:
+  - name: echo
+    include: ["**/*.txt"]
+    cmd: echo
+"#,
+    );
+    init_repo_with_files(dir.path(), &["a.txt", "b.txt"]);
+    fs::write(dir.path().join("list"), "a.txt\nb.txt\n").unwrap();
+
+    let output = Command::new(ffx_binary())
+        .current_dir(dir.path())
+        .args(["--files-from", "list"])
+        .output()
+        .expect("Failed to run ffx");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "stdout: {stdout}");
+    assert!(stdout.contains("Formatted 2 files"), "stdout: {stdout}");
+}
+
+#[test]
+fn test_quiet_prints_nothing_on_success() {
+    let dir = setup_test_dir(
+        r#"
+version: 1
+tools:
+  - name: echo
+    include: ["**/*.txt"]
+    cmd: echo
+"#,
+    );
+    init_repo_with_files(dir.path(), &["test.txt"]);
+
+    let output = Command::new(ffx_binary())
+        .current_dir(dir.path())
+        .args(["--all", "--quiet"])
+        .output()
+        .expect("Failed to run ffx");
+
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty(), "stdout: {:?}", output.stdout);
+}
+
+#[test]
+fn test_quiet_still_reports_check_failures() {
+    let dir = setup_test_dir(&check_exit_config(1));
+    init_repo_with_files(dir.path(), &["test.txt"]);
+
+    let output = Command::new(ffx_binary())
+        .current_dir(dir.path())
+        .args(["--all", "--check", "-q"])
+        .output()
+        .expect("Failed to run ffx");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(!stdout.contains("Checking formatters"));
+    assert!(stdout.contains("[linter]"));
+    assert!(stdout.contains("Some checks failed"));
+    assert!(stdout.contains("Details:"));
+}
+
+#[test]
+fn test_color_flag_controls_ansi_output() {
+    let dir = setup_test_dir(
+        r#"
+version: 1
+tools:
+  - name: echo
+    include: ["**/*.txt"]
+    cmd: echo
+"#,
+    );
+    init_repo_with_files(dir.path(), &["test.txt"]);
+
+    let run = |color: &str| {
+        let output = Command::new(ffx_binary())
+            .current_dir(dir.path())
+            .args(["--all", "--color", color])
+            .output()
+            .expect("Failed to run ffx");
+        assert!(output.status.success());
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    };
+
+    assert!(run("always").contains("\x1b["));
+    assert!(!run("never").contains("\x1b["));
+}
+
+/// Run a git command in `dir`, panicking if it fails.
+fn git(dir: &std::path::Path, args: &[&str]) {
+    let output = Command::new("git")
+        .args(["-c", "user.name=ffx", "-c", "user.email=ffx@example.com"])
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "git {args:?} failed: {output:?}");
+}
+
+#[test]
+fn test_base_auto_uses_origin_head() {
+    let dir = setup_test_dir(
+        r#"
+version: 1
+tools:
+  - name: echo
+    include: ["**/*.txt"]
+    cmd: echo
+"#,
+    );
+    init_repo_with_files(dir.path(), &["old.txt"]);
+    git(dir.path(), &["commit", "-qm", "initial"]);
+    git(
+        dir.path(),
+        &["update-ref", "refs/remotes/origin/main", "HEAD"],
+    );
+    git(
+        dir.path(),
+        &[
+            "symbolic-ref",
+            "refs/remotes/origin/HEAD",
+            "refs/remotes/origin/main",
+        ],
+    );
+    fs::write(dir.path().join("new.txt"), "content").unwrap();
+    git(dir.path(), &["add", "new.txt"]);
+    git(dir.path(), &["commit", "-qm", "feature"]);
+
+    let output = Command::new(ffx_binary())
+        .current_dir(dir.path())
+        .args(["--base-auto", "--verbose"])
+        .output()
+        .expect("Failed to run ffx");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "stderr: {stderr}");
+    assert!(stderr.contains("base: origin/main"), "stderr: {stderr}");
+    assert!(stdout.contains("new.txt"));
+    assert!(!stdout.contains("old.txt"));
+}
+
+#[test]
+fn test_changed_in_uses_single_commit() {
+    let dir = setup_test_dir(
+        r#"
+version: 1
+tools:
+  - name: echo
+    include: ["**/*.txt"]
+    cmd: echo
+"#,
+    );
+    init_repo_with_files(dir.path(), &["old.txt"]);
+    git(dir.path(), &["commit", "-qm", "initial"]);
+    fs::write(dir.path().join("new.txt"), "content").unwrap();
+    git(dir.path(), &["add", "new.txt"]);
+    git(dir.path(), &["commit", "-qm", "feature"]);
+
+    let output = Command::new(ffx_binary())
+        .current_dir(dir.path())
+        .args(["--changed-in", "HEAD~1", "--verbose"])
+        .output()
+        .expect("Failed to run ffx");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "stdout: {stdout}");
+    assert!(stdout.contains("old.txt"), "stdout: {stdout}");
+    assert!(!stdout.contains("new.txt"), "stdout: {stdout}");
+}
+
+#[test]
+fn test_changed_in_conflicts_with_all() {
+    let dir = setup_test_dir("version: 1\ntools: []\n");
+
+    let output = Command::new(ffx_binary())
+        .current_dir(dir.path())
+        .args(["--changed-in", "HEAD", "--all"])
+        .output()
+        .expect("Failed to run ffx");
+
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("cannot be used with"));
+}
+
+#[test]
+fn test_no_git_formats_plain_directory() {
+    let dir = setup_test_dir(
+        r#"
+version: 1
+tools:
+  - name: echo
+    include: ["**/*.txt"]
+    cmd: echo
+"#,
+    );
+    fs::write(dir.path().join(".gitignore"), "ignored.txt\n").unwrap();
+    fs::write(dir.path().join("kept.txt"), "content").unwrap();
+    fs::write(dir.path().join("ignored.txt"), "content").unwrap();
+
+    let output = Command::new(ffx_binary())
+        .current_dir(dir.path())
+        .args(["--no-git", "--all", "--verbose"])
+        .output()
+        .expect("Failed to run ffx");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "stderr: {stderr}");
+    assert!(stdout.contains("kept.txt"), "stdout: {stdout}");
+    assert!(!stdout.contains("ignored.txt"), "stdout: {stdout}");
+}
+
+#[test]
+fn test_no_git_requires_file_source() {
+    let dir = setup_test_dir("version: 1\ntools: []\n");
+
+    let output = Command::new(ffx_binary())
+        .current_dir(dir.path())
+        .args(["--no-git"])
+        .output()
+        .expect("Failed to run ffx");
+
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--no-git requires"));
+}
+
+#[test]
+fn test_base_auto_without_origin_head_errors() {
+    let dir = setup_test_dir(
+        r#"
+version: 1
+tools:
+  - name: echo
+    include: ["**/*.txt"]
+    cmd: echo
+"#,
+    );
+    init_repo_with_files(dir.path(), &["a.txt"]);
+
+    let output = Command::new(ffx_binary())
+        .current_dir(dir.path())
+        .arg("--base-auto")
+        .output()
+        .expect("Failed to run ffx");
+
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("origin/HEAD"));
+}
+
+#[test]
+fn test_github_annotations_in_check_mode() {
+    let dir = setup_test_dir(
+        r#"
+version: 1
+tools:
+  - name: linter
+    include: ["**/*.txt"]
+    cmd: sh
+    check_args: ["-c", "echo \"$1:3: needs formatting\"; exit 1", "sh"]
+    annotation_pattern: '^(?P<file>[^:]+):(?P<line>\d+): (?P<message>.*)$'
+"#,
+    );
+    init_repo_with_files(dir.path(), &["test.txt"]);
+
+    let output = Command::new(ffx_binary())
+        .current_dir(dir.path())
+        .args(["--all", "--check", "--github-annotations"])
+        .output()
+        .expect("Failed to run ffx");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(
+        stdout.contains("::error file=test.txt,line=3::needs formatting"),
+        "stdout: {stdout}"
+    );
+}
+
+#[test]
+fn test_sarif_output_in_check_mode() {
+    let dir = setup_test_dir(
+        r#"
+version: 1
+tools:
+  - name: linter
+    include: ["**/*.txt"]
+    cmd: sh
+    check_args: ["-c", "echo \"$1:3: needs formatting\"; exit 1", "sh"]
+    annotation_pattern: '^(?P<file>[^:]+):(?P<line>\d+): (?P<message>.*)$'
+"#,
+    );
+    init_repo_with_files(dir.path(), &["test.txt"]);
+
+    let output = Command::new(ffx_binary())
+        .current_dir(dir.path())
+        .args(["--all", "--check", "--format", "sarif"])
+        .output()
+        .expect("Failed to run ffx");
+
+    assert_eq!(output.status.code(), Some(1));
+    let sarif: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(sarif["version"], "2.1.0");
+    assert_eq!(sarif["runs"][0]["tool"]["driver"]["name"], "linter");
+    let result = &sarif["runs"][0]["results"][0];
+    assert_eq!(result["message"]["text"], "needs formatting");
+    assert_eq!(
+        result["locations"][0]["physicalLocation"]["artifactLocation"]["uri"],
+        "test.txt"
+    );
+}
+
+#[test]
+fn test_junit_output_in_check_mode() {
+    let dir = setup_test_dir(
+        r#"
+version: 1
+tools:
+  - name: linter
+    include: ["**/*.txt"]
+    cmd: sh
+    check_args: ["-c", "echo needs formatting; exit 1", "sh"]
+"#,
+    );
+    init_repo_with_files(dir.path(), &["test.txt"]);
+
+    let output = Command::new(ffx_binary())
+        .current_dir(dir.path())
+        .args(["--all", "--check", "--format", "junit"])
+        .output()
+        .expect("Failed to run ffx");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stdout.starts_with("<?xml"), "stdout: {stdout}");
+    assert!(
+        stdout.contains(r#"<testcase name="linter""#),
+        "stdout: {stdout}"
+    );
+    assert!(
+        stdout.contains("needs formatting\n</failure>"),
+        "stdout: {stdout}"
+    );
+}
+
+#[test]
+fn test_sarif_requires_check() {
+    let dir = setup_test_dir("version: 1\ntools: []\n");
+
+    let output = Command::new(ffx_binary())
+        .current_dir(dir.path())
+        .args(["--all", "--format", "sarif"])
+        .output()
+        .expect("Failed to run ffx");
+
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("requires --check"));
+}
+
+#[test]
+fn test_allow_failure_does_not_fail_run() {
+    let dir = setup_test_dir(
+        r#"
+version: 1
+tools:
+  - name: advisory
+    include: ["**/*.txt"]
+    cmd: sh
+    args: ["-c", "echo consider renaming; exit 1"]
+    allow_failure: true
+  - name: echo
+    include: ["**/*.txt"]
+    cmd: echo
+"#,
+    );
+    init_repo_with_files(dir.path(), &["test.txt"]);
+
+    let output = Command::new(ffx_binary())
+        .current_dir(dir.path())
+        .args(["--all"])
+        .output()
+        .expect("Failed to run ffx");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "stdout: {stdout}");
+    assert!(stdout.contains("✗ [advisory]"), "stdout: {stdout}");
+    assert!(stdout.contains("consider renaming"), "stdout: {stdout}");
+    assert!(stdout.contains("1 advisory failure"), "stdout: {stdout}");
+}
+
+#[test]
+fn test_profile_writes_timings_alongside_output() {
+    let dir = setup_test_dir(
+        r#"
+version: 1
+tools:
+  - name: echo
+    include: ["**/*.txt"]
+    cmd: echo
+"#,
+    );
+    init_repo_with_files(dir.path(), &["a.txt", "b.txt"]);
+    let profile_path = dir.path().join("profile.json");
+
+    let output = Command::new(ffx_binary())
+        .current_dir(dir.path())
+        .args(["--all", "--profile"])
+        .arg(&profile_path)
+        .output()
+        .expect("Failed to run ffx");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "stdout: {stdout}");
+    assert!(stdout.contains("Formatted 2 files"), "stdout: {stdout}");
+
+    let profile: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&profile_path).unwrap()).unwrap();
+    assert_eq!(profile[0]["tool"], "echo");
+    assert_eq!(profile[0]["files"], 2);
+    assert_eq!(profile[0]["batches"], 1);
+    assert_eq!(profile[0]["success"], true);
+    assert!(profile[0]["duration_ms"].is_u64());
+}
+
+#[test]
+fn test_skip_binary_drops_binary_files() {
+    let dir = setup_test_dir(
+        r#"
+version: 1
+tools:
+  - name: echo
+    include: ["data/*"]
+    cmd: echo
+    skip_binary: true
+"#,
+    );
+    fs::create_dir(dir.path().join("data")).unwrap();
+    fs::write(dir.path().join("data/notes.txt"), "hello").unwrap();
+    fs::write(dir.path().join("data/image.png"), [0x89, b'P', 0, 0]).unwrap();
+    init_repo_with_files(dir.path(), &[]);
+
+    let output = Command::new(ffx_binary())
+        .current_dir(dir.path())
+        .args(["--all", "--verbose"])
+        .output()
+        .expect("Failed to run ffx");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "stderr: {stderr}");
+    assert!(stdout.contains("data/notes.txt"), "stdout: {stdout}");
+    assert!(!stdout.contains("image.png"), "stdout: {stdout}");
+    assert!(stderr.contains("skipped 1 binary file"), "stderr: {stderr}");
+}
+
+#[test]
+fn test_max_file_bytes_skips_large_files() {
+    let dir = setup_test_dir(
+        r#"
+version: 1
+max_file_bytes: 100
+tools:
+  - name: echo
+    include: ["*.js"]
+    cmd: echo
+  - name: big-echo
+    include: ["*.json"]
+    cmd: echo
+    max_file_bytes: 10000
+"#,
+    );
+    fs::write(dir.path().join("app.js"), "let x = 1;").unwrap();
+    fs::write(dir.path().join("bundle.min.js"), "x".repeat(1000)).unwrap();
+    fs::write(dir.path().join("data.json"), "x".repeat(1000)).unwrap();
+    init_repo_with_files(dir.path(), &[]);
+
+    let output = Command::new(ffx_binary())
+        .current_dir(dir.path())
+        .args(["--all", "--verbose"])
+        .output()
+        .expect("Failed to run ffx");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "stderr: {stderr}");
+    assert!(stdout.contains("app.js"), "stdout: {stdout}");
+    assert!(stdout.contains("data.json"), "stdout: {stdout}");
+    assert!(!stdout.contains("bundle.min.js"), "stdout: {stdout}");
+    assert!(stderr.contains("skipped 1 large file"), "stderr: {stderr}");
+}
+
+#[test]
+fn test_dry_run_prints_commands_without_running() {
+    let dir = setup_test_dir(
+        r#"
+version: 1
+tools:
+  - name: touch
+    include: ["**/*.txt"]
+    cmd: touch
+    args: [ran.marker]
+"#,
+    );
+    init_repo_with_files(dir.path(), &["test.txt"]);
+
+    let output = Command::new(ffx_binary())
+        .current_dir(dir.path())
+        .args(["--all", "--dry-run", "--color", "never"])
+        .output()
+        .expect("Failed to run ffx");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "stdout: {stdout}");
+    assert!(
+        stdout.contains("[touch] cd ") && stdout.contains(" && touch ran.marker test.txt"),
+        "stdout: {stdout}"
+    );
+    assert!(!dir.path().join("ran.marker").exists());
+}
+
+#[test]
+fn test_jobs_auto_overrides_earlier_value() {
+    let dir = setup_test_dir(
+        r#"
+version: 1
+tools:
+  - name: echo
+    include: ["**/*.txt"]
+    cmd: echo
+"#,
+    );
+    init_repo_with_files(dir.path(), &["test.txt"]);
+
+    let output = Command::new(ffx_binary())
+        .current_dir(dir.path())
+        .args(["--all", "-j2", "-j", "auto", "--verbose"])
+        .output()
+        .expect("Failed to run ffx");
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let cores = std::thread::available_parallelism().unwrap().get();
+    assert!(output.status.success(), "stderr: {stderr}");
+    assert!(
+        stderr.contains(&format!("jobs: {cores}")),
+        "stderr: {stderr}"
+    );
+}
+
+#[test]
+fn test_doctor_reports_missing_tools() {
+    let dir = setup_test_dir(
+        r#"
+version: 1
+tools:
+  - name: shell
+    description: runs shell snippets
+    include: ["**/*.sh"]
+    cmd: sh
+  - name: ghost
+    include: ["**/*.txt"]
+    cmd: ffx-definitely-missing-tool
+"#,
+    );
+    init_repo_with_files(dir.path(), &[]);
+
+    let output = Command::new(ffx_binary())
+        .current_dir(dir.path())
+        .arg("doctor")
+        .output()
+        .expect("Failed to run ffx");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(output.status.code(), Some(3), "stdout: {stdout}");
+    assert!(stdout.contains("TOOL"));
+    assert!(stdout
+        .lines()
+        .any(|l| l.starts_with("shell") && l.contains("found")));
+    assert!(stdout
+        .lines()
+        .any(|l| l.starts_with("ghost") && l.contains("not found")));
+    assert!(stdout.contains("1 tool not found"));
+    assert!(
+        stdout.contains("\n  runs shell snippets\n"),
+        "stdout: {stdout}"
+    );
+}
+
+#[test]
+fn test_restage_adds_formatted_staged_files() {
+    let dir = setup_test_dir(
+        r#"
+version: 1
+tools:
+  - name: append
+    include: ["**/*.txt"]
+    cmd: sh
+    args: ["-c", "for f; do echo formatted >> \"$f\"; done", "sh"]
+"#,
+    );
+    init_repo_with_files(dir.path(), &["has space.txt"]);
+    fs::write(dir.path().join("untracked.txt"), "content").unwrap();
+
+    let output = Command::new(ffx_binary())
+        .current_dir(dir.path())
+        .args(["--staged", "--restage"])
+        .output()
+        .expect("Failed to run ffx");
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let staged = Command::new("git")
+        .args(["show", ":has space.txt"])
+        .current_dir(dir.path())
+        .output()
+        .unwrap();
+    assert_eq!(
+        String::from_utf8_lossy(&staged.stdout),
+        "contentformatted\n"
+    );
+
+    let status = Command::new("git")
+        .args(["status", "--porcelain"])
+        .current_dir(dir.path())
+        .output()
+        .unwrap();
+    let status = String::from_utf8_lossy(&status.stdout);
+    assert!(status.contains("?? untracked.txt"), "status: {status}");
+    assert!(!status.contains(" M "), "status: {status}");
+}
+
+#[test]
+fn test_restage_requires_staged() {
+    let output = Command::new(ffx_binary())
+        .arg("--restage")
+        .output()
+        .expect("Failed to run ffx");
+
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_warn_unused_tools_reports_unmatched_tool() {
+    let dir = setup_test_dir(
+        r#"
+version: 1
+tools:
+  - name: echo
+    include: ["**/*.txt"]
+    cmd: echo
+  - name: typo
+    include: ["**/*.jsx"]
+    cmd: echo
+"#,
+    );
+    init_repo_with_files(dir.path(), &["a.txt", "b.tsx"]);
+
+    let output = Command::new(ffx_binary())
+        .current_dir(dir.path())
+        .args(["--all", "--warn-unused-tools"])
+        .output()
+        .expect("Failed to run ffx");
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "stderr: {stderr}");
+    assert!(
+        stderr.contains("tool 'typo' matched no files (include: **/*.jsx)"),
+        "stderr: {stderr}"
+    );
+    assert!(!stderr.contains("tool 'echo'"), "stderr: {stderr}");
+}
+
+#[test]
+fn test_require_match_fails_unmatched_tool() {
+    let dir = setup_test_dir(
+        r#"
+version: 1
+tools:
+  - name: echo
+    include: ["**/*.txt"]
+    cmd: echo
+  - name: typo
+    include: ["**/*.jsx"]
+    cmd: echo
+    require_match: true
+"#,
+    );
+    init_repo_with_files(dir.path(), &["a.txt"]);
+
+    let output = Command::new(ffx_binary())
+        .current_dir(dir.path())
+        .arg("--all")
+        .output()
+        .expect("Failed to run ffx");
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(2), "stderr: {stderr}");
+    assert!(
+        stderr.contains("Tool 'typo' matched no files"),
+        "stderr: {stderr}"
+    );
+
+    // Only --all sees the whole repo, so other modes don't enforce it
+    let output = Command::new(ffx_binary())
+        .current_dir(dir.path())
+        .arg("--staged")
+        .output()
+        .expect("Failed to run ffx");
+    assert!(output.status.success());
+}
+
+#[test]
+fn test_trailing_args_passed_to_every_tool() {
+    let dir = setup_test_dir(
+        r#"
+version: 1
+tools:
+  - name: echo
+    include: ["**/*.txt"]
+    cmd: echo
+    args: ["first"]
+"#,
+    );
+    init_repo_with_files(dir.path(), &["a.txt"]);
+
+    let output = Command::new(ffx_binary())
+        .current_dir(dir.path())
+        .args(["--all", "--dry-run", "--", "--extra", "flag"])
+        .output()
+        .expect("Failed to run ffx");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    assert!(
+        stdout.contains("echo first --extra flag a.txt"),
+        "stdout: {stdout}"
+    );
+}
+
+#[test]
+fn test_submodules_skipped_unless_included() {
+    let dir = setup_test_dir(
+        r#"
+version: 1
+tools:
+  - name: echo
+    include: ["**/*"]
+    cmd: echo
+"#,
+    );
+    init_repo_with_files(dir.path(), &["a.txt"]);
+    fs::write(
+        dir.path().join(".gitmodules"),
+        "[submodule \"lib\"]\n\tpath = deps/lib\n\turl = https://example.com/lib.git\n",
+    )
+    .unwrap();
+    git(dir.path(), &["add", ".gitmodules"]);
+    git(
+        dir.path(),
+        &[
+            "update-index",
+            "--add",
+            "--cacheinfo",
+            "160000,1111111111111111111111111111111111111111,deps/lib",
+        ],
+    );
+
+    let run = |extra: &[&str]| {
+        let output = Command::new(ffx_binary())
+            .current_dir(dir.path())
+            .args(["--all", "--list"])
+            .args(extra)
+            .output()
+            .expect("Failed to run ffx");
+        assert!(output.status.success());
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    };
+
+    let stdout = run(&[]);
+    assert!(stdout.contains("a.txt"), "stdout: {stdout}");
+    assert!(!stdout.contains("deps/lib"), "stdout: {stdout}");
+
+    let stdout = run(&["--include-submodules"]);
+    assert!(stdout.contains("deps/lib"), "stdout: {stdout}");
+}
+
+#[test]
+fn test_color_env_vars_apply_in_auto_mode() {
+    let dir = setup_test_dir(
+        r#"
+version: 1
+tools:
+  - name: echo
+    include: ["**/*.txt"]
+    cmd: echo
+"#,
+    );
+    init_repo_with_files(dir.path(), &["test.txt"]);
+
+    let run = |var: &str| {
+        let output = Command::new(ffx_binary())
+            .current_dir(dir.path())
+            .arg("--all")
+            .env_remove("NO_COLOR")
+            .env_remove("FORCE_COLOR")
+            .env_remove("CLICOLOR_FORCE")
+            .env(var, "1")
+            .output()
+            .expect("Failed to run ffx");
+        assert!(output.status.success());
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    };
+
+    assert!(run("FORCE_COLOR").contains("\x1b["));
+    assert!(run("CLICOLOR_FORCE").contains("\x1b["));
+    assert!(!run("NO_COLOR").contains("\x1b["));
+}
+
+#[test]
+fn test_since_runs_on_recently_modified_tracked_files() {
+    let dir = setup_test_dir(
+        r#"
+version: 1
+tools:
+  - name: echo
+    include: ["**/*.txt"]
+    cmd: echo
+"#,
+    );
+    init_repo_with_files(dir.path(), &["fresh.txt", "stale.txt"]);
+    fs::File::options()
+        .write(true)
+        .open(dir.path().join("stale.txt"))
+        .unwrap()
+        .set_modified(std::time::SystemTime::now() - std::time::Duration::from_secs(3 * 60 * 60))
+        .unwrap();
+
+    let output = Command::new(ffx_binary())
+        .current_dir(dir.path())
+        .args(["--since", "1h", "--list"])
+        .output()
+        .expect("Failed to run ffx");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    assert!(stdout.contains("fresh.txt"), "stdout: {stdout}");
+    assert!(!stdout.contains("stale.txt"), "stdout: {stdout}");
+}
+
+#[test]
+fn test_fix_and_check_formats_then_verifies() {
+    let dir = setup_test_dir(
+        r#"
+version: 1
+tools:
+  - name: fmt
+    include: ["**/*.txt"]
+    cmd: sh
+    args: ["-c", "for f; do printf x > \"$f\"; done", "sh"]
+    check_args: ["-c", "for f; do [ \"$(cat \"$f\")\" = x ] || exit 1; done", "sh"]
+"#,
+    );
+    init_repo_with_files(dir.path(), &["a.txt"]);
+
+    let output = Command::new(ffx_binary())
+        .current_dir(dir.path())
+        .args(["--all", "--fix-and-check", "--color", "never"])
+        .output()
+        .expect("Failed to run ffx");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "stdout: {stdout}");
+    assert!(
+        stdout.contains("Changed by formatters:\n  [fmt] 1 file"),
+        "stdout: {stdout}"
+    );
+    assert!(stdout.contains("Checked 1 file"), "stdout: {stdout}");
+}
+
+#[test]
+fn test_fix_and_check_fails_when_check_still_fails() {
+    let dir = setup_test_dir(
+        r#"
+version: 1
+tools:
+  - name: flaky
+    include: ["**/*.txt"]
+    cmd: sh
+    args: ["-c", "exit 0"]
+    check_args: ["-c", "echo still unformatted; exit 1"]
+"#,
+    );
+    init_repo_with_files(dir.path(), &["a.txt"]);
+
+    let output = Command::new(ffx_binary())
+        .current_dir(dir.path())
+        .args(["--all", "--fix-and-check", "--color", "never"])
+        .output()
+        .expect("Failed to run ffx");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(output.status.code(), Some(1), "stdout: {stdout}");
+    assert!(
+        stdout.contains("No files were changed by formatters"),
+        "stdout: {stdout}"
+    );
+    assert!(stdout.contains("Some checks failed"), "stdout: {stdout}");
+}
+
+#[test]
+fn test_short_config_names_are_discovered() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(
+        dir.path().join(".ffx.yml"),
+        r#"
+version: 1
+tools:
+  - name: short-name
+    include: ["**/*.txt"]
+    cmd: echo
+"#,
+    )
+    .unwrap();
+    init_repo_with_files(dir.path(), &["a.txt"]);
+
+    let output = Command::new(ffx_binary())
+        .current_dir(dir.path())
+        .args(["--all", "--list"])
+        .output()
+        .expect("Failed to run ffx");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(stdout.contains("short-name"), "stdout: {stdout}");
+}
+
+#[test]
+fn test_no_fail_reports_failures_but_exits_zero() {
+    let dir = setup_test_dir(
+        r#"
+version: 1
+tools:
+  - name: failing
+    include: ["**/*.txt"]
+    cmd: sh
+    args: ["-c", "echo needs formatting; exit 1"]
+"#,
+    );
+    init_repo_with_files(dir.path(), &["a.txt"]);
+
+    let output = Command::new(ffx_binary())
+        .current_dir(dir.path())
+        .args(["--all", "--check", "--no-fail"])
+        .output()
+        .expect("Failed to run ffx");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(output.status.code(), Some(0), "stdout: {stdout}");
+    assert!(stdout.contains("Some checks failed"), "stdout: {stdout}");
+    assert!(stdout.contains("needs formatting"), "stdout: {stdout}");
+}
+
+#[test]
+fn test_verbose_output_is_grouped_per_tool_when_not_a_tty() {
+    let dir = setup_test_dir(
+        r#"
+version: 1
+tools:
+  - name: first
+    include: ["**/*.txt"]
+    cmd: sh
+    args: ["-c", "echo first-1 >&2; sleep 0.2; echo first-2 >&2"]
+  - name: second
+    include: ["**/*.md"]
+    cmd: sh
+    args: ["-c", "sleep 0.1; echo second-1 >&2; sleep 0.2; echo second-2 >&2"]
+"#,
+    );
+    init_repo_with_files(dir.path(), &["a.txt", "b.md"]);
+
+    let output = Command::new(ffx_binary())
+        .current_dir(dir.path())
+        .args(["--all", "--verbose"])
+        .output()
+        .expect("Failed to run ffx");
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "stderr: {stderr}");
+    assert!(
+        stderr.contains("  first-1\n  first-2\n"),
+        "stderr: {stderr}"
+    );
+    assert!(
+        stderr.contains("  second-1\n  second-2\n"),
+        "stderr: {stderr}"
+    );
+    assert!(!stderr.contains("[first] first-1"), "stderr: {stderr}");
+}
+
+#[test]
+fn test_verbose_merged_log_keeps_each_batch_together() {
+    let dir = setup_test_dir(
+        r#"
+version: 1
+tools:
+  - name: each
+    include: ["**/*.txt"]
+    cmd: sh
+    args: ["-c", "echo \"out $1\"", "sh"]
+    per_file: true
+"#,
+    );
+    init_repo_with_files(dir.path(), &["a.txt", "b.txt"]);
+
+    let output = Command::new("sh")
+        .arg("-c")
+        .arg(format!(
+            "'{}' --all --verbose --no-parallel 2>&1",
+            ffx_binary().display()
+        ))
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to run ffx");
+
+    let log = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "log: {log}");
+    for file in ["a.txt", "b.txt"] {
+        // The command line ends with the file, then its exit status and output follow
+        let batch = format!("{file}\n  exited with code 0\n  out {file}\n");
+        assert!(log.contains(&batch), "log: {log}");
+    }
+}
+
+/// Run ffx in `dir` with `args`, writing `input` to its stdin.
+fn run_ffx_with_stdin(dir: &std::path::Path, args: &[&str], input: &[u8]) -> std::process::Output {
+    let mut child = Command::new(ffx_binary())
+        .current_dir(dir)
+        .args(args)
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .expect("Failed to run ffx");
+    child.stdin.take().unwrap().write_all(input).unwrap();
+    child.wait_with_output().unwrap()
+}
+
+#[test]
+fn test_stdin_formats_content_with_matching_tool() {
+    let dir = setup_test_dir(
+        r#"
+version: 1
+tools:
+  - name: upper
+    include: ["**/*.txt"]
+    cmd: tr
+    args: [a-z, A-Z]
+    stdin: true
+"#,
+    );
+    init_repo_with_files(dir.path(), &[]);
+    fs::create_dir(dir.path().join("docs")).unwrap();
+
+    let output = run_ffx_with_stdin(
+        &dir.path().join("docs"),
+        &["--stdin", "--stdin-filename", "notes.txt"],
+        b"hello\n",
+    );
+
+    assert!(output.status.success(), "output: {output:?}");
+    assert_eq!(output.stdout, b"HELLO\n");
+    // The file on disk is never touched (it doesn't even exist)
+    assert!(!dir.path().join("docs/notes.txt").exists());
+}
+
+#[test]
+fn test_stdin_echoes_unmatched_content() {
+    let dir = setup_test_dir(
+        r#"
+version: 1
+tools:
+  - name: upper
+    include: ["**/*.txt"]
+    cmd: tr
+    args: [a-z, A-Z]
+    stdin: true
+"#,
+    );
+    init_repo_with_files(dir.path(), &[]);
+
+    let output = run_ffx_with_stdin(
+        dir.path(),
+        &["--stdin", "--stdin-filename", "main.rs"],
+        b"fn main() {}\n",
+    );
+
+    assert!(output.status.success(), "output: {output:?}");
+    assert_eq!(output.stdout, b"fn main() {}\n");
+}
+
+#[test]
+fn test_stdin_requires_tool_with_stdin_support() {
+    let dir = setup_test_dir(
+        r#"
+version: 1
+tools:
+  - name: upper
+    include: ["**/*.txt"]
+    cmd: tr
+    args: [a-z, A-Z]
+"#,
+    );
+    init_repo_with_files(dir.path(), &[]);
+
+    let output = run_ffx_with_stdin(
+        dir.path(),
+        &["--stdin", "--stdin-filename", "a.txt"],
+        b"hello\n",
+    );
+
+    assert_eq!(output.status.code(), Some(2));
+    assert!(output.stdout.is_empty());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("stdin: true"));
+}
+
+#[test]
+fn test_stdin_tool_failure_writes_nothing_to_stdout() {
+    let dir = setup_test_dir(
+        r#"
+version: 1
+tools:
+  - name: broken
+    include: ["**/*.txt"]
+    cmd: sh
+    args: [-c, "echo partial; echo 'syntax error' >&2; exit 1"]
+    stdin: true
+"#,
+    );
+    init_repo_with_files(dir.path(), &[]);
+
+    let output = run_ffx_with_stdin(
+        dir.path(),
+        &["--stdin", "--stdin-filename", "a.txt"],
+        b"hello\n",
+    );
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(output.stdout.is_empty());
+    assert!(stderr.contains("syntax error"), "stderr: {stderr}");
+}
+
+#[test]
+fn test_stdin_tool_timeout_stops_hanging_tool() {
+    let dir = setup_test_dir(
+        r#"
+version: 1
+tools:
+  - name: hangs
+    include: ["**/*.txt"]
+    cmd: sh
+    args: [-c, "sleep 10; cat"]
+    stdin: true
+    timeout: 1
+"#,
+    );
+    init_repo_with_files(dir.path(), &[]);
+
+    let start = std::time::Instant::now();
+    let output = run_ffx_with_stdin(
+        dir.path(),
+        &["--stdin", "--stdin-filename", "a.txt"],
+        b"hello\n",
+    );
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(start.elapsed() < std::time::Duration::from_secs(5));
+    assert_eq!(output.status.code(), Some(1), "stderr: {stderr}");
+    assert!(output.stdout.is_empty());
+    assert!(
+        stderr.contains("error: tool 'hangs' timed out after 1s"),
+        "stderr: {stderr}"
+    );
+}
+
+#[test]
+fn test_invalid_glob_fails_at_config_load() {
+    let dir = setup_test_dir(
+        r#"
+version: 1
+tools:
+  - name: echo
+    include: ["src/{a,b"]
+    cmd: echo
+"#,
+    );
+    init_repo_with_files(dir.path(), &[]);
+
+    let output = Command::new(ffx_binary())
+        .current_dir(dir.path())
+        .arg("doctor")
+        .output()
+        .expect("Failed to run ffx");
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(2), "stderr: {stderr}");
+    assert!(
+        stderr.contains("Tool 'echo' has an invalid include pattern"),
+        "stderr: {stderr}"
+    );
+    assert!(stderr.contains("src/{a,b"), "stderr: {stderr}");
+}
+
+#[test]
+fn test_update_pin_rejects_invalid_version() {
+    let output = Command::new(ffx_binary())
+        .args(["update", "--pin", "latest"])
+        .output()
+        .expect("Failed to run ffx");
+
+    assert_eq!(output.status.code(), Some(2));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("expected a release version like 0.1.22"),
+        "stderr: {stderr}"
+    );
+}
+
+#[test]
+fn test_update_pin_check_reports_current_version() {
+    let version = env!("CARGO_PKG_VERSION");
+    let output = Command::new(ffx_binary())
+        .args(["update", "--check", "--pin", &format!("v{version}")])
+        .output()
+        .expect("Failed to run ffx");
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains(&format!("Already on pinned v{version}")),
+        "stdout: {stdout}"
+    );
+}
+
+#[test]
+fn test_update_offline_skips_network() {
+    let output = Command::new(ffx_binary())
+        .args(["update", "--check"])
+        .env("FFX_OFFLINE", "1")
+        .output()
+        .expect("Failed to run ffx");
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("Update checks disabled in offline mode"),
+        "stdout: {stdout}"
+    );
+    assert!(!stdout.contains("Checking for updates"));
+}
+
+#[test]
+fn test_summary_counts_changed_files() {
+    let dir = setup_test_dir(
+        r#"
+version: 1
+tools:
+  - name: fmt
+    include: ["**/*.txt"]
+    cmd: sh
+    args: ["-c", "for f; do printf formatted > \"$f\"; done", "sh"]
+"#,
+    );
+    fs::write(dir.path().join("clean.txt"), "formatted").unwrap();
+    init_repo_with_files(dir.path(), &["a.txt", "b.txt"]);
+
+    let output = Command::new(ffx_binary())
+        .current_dir(dir.path())
+        .args(["--all", "--color", "never"])
+        .output()
+        .expect("Failed to run ffx");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "stdout: {stdout}");
+    assert!(
+        stdout.contains("Formatted 3 files (2 changed)"),
+        "stdout: {stdout}"
+    );
+
+    let output = Command::new(ffx_binary())
+        .current_dir(dir.path())
+        .args(["--all", "--format", "json"])
+        .output()
+        .expect("Failed to run ffx");
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["tools"][0]["changed_count"], 0);
+    assert_eq!(report["tools"][0]["total_bytes"], 27);
+
+    let output = Command::new(ffx_binary())
+        .current_dir(dir.path())
+        .args(["--all", "--verbose"])
+        .output()
+        .expect("Failed to run ffx");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("- fmt (3 files, 27 B):"),
+        "stdout: {stdout}"
+    );
+}
+
+#[test]
+fn test_config_check_lists_tools() {
+    let dir = setup_test_dir(
+        r#"
+version: 1
+tools:
+  - name: rustfmt
+    include: ["**/*.rs"]
+    cmd: rustfmt
+  - name: prettier
+    include: ["**/*.js", "**/*.ts"]
+    cmd: npx
+"#,
+    );
+    init_repo_with_files(dir.path(), &[]);
+
+    let output = Command::new(ffx_binary())
+        .current_dir(dir.path())
+        .args(["--config-check", "--color", "never"])
+        .output()
+        .expect("Failed to run ffx");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "stdout: {stdout}");
+    assert!(stdout.contains("Valid config:"), "stdout: {stdout}");
+    assert!(stdout.contains("(2 tools)"), "stdout: {stdout}");
+    assert!(
+        stdout.contains("[prettier] npx: **/*.js, **/*.ts"),
+        "stdout: {stdout}"
+    );
+}
+
+#[test]
+fn test_config_check_rejects_duplicate_tools() {
+    let dir = setup_test_dir(
+        r#"
+version: 1
+tools:
+  - name: fmt
+    include: ["**/*.rs"]
+    cmd: rustfmt
+  - name: fmt
+    include: ["**/*.js"]
+    cmd: prettier
+"#,
+    );
+    init_repo_with_files(dir.path(), &[]);
+
+    let output = Command::new(ffx_binary())
+        .current_dir(dir.path())
+        .arg("--config-check")
+        .output()
+        .expect("Failed to run ffx");
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(2), "stderr: {stderr}");
+    assert!(
+        stderr.contains("Duplicate tool names: fmt"),
+        "stderr: {stderr}"
+    );
+}
+
+#[test]
+fn test_all_include_untracked_adds_unignored_files() {
+    let dir = setup_test_dir(
+        r#"
+version: 1
+tools:
+  - name: echo
+    include: ["**/*.txt"]
+    cmd: echo
+"#,
+    );
+    init_repo_with_files(dir.path(), &["tracked.txt", ".gitignore"]);
+    fs::write(dir.path().join(".gitignore"), "ignored.txt\n").unwrap();
+    fs::write(dir.path().join("new.txt"), "content").unwrap();
+    fs::write(dir.path().join("ignored.txt"), "content").unwrap();
+
+    let run = |args: &[&str]| {
+        let output = Command::new(ffx_binary())
+            .current_dir(dir.path())
+            .args(args)
+            .output()
+            .expect("Failed to run ffx");
+        assert!(output.status.success());
+        String::from_utf8_lossy(&output.stdout).to_string()
+    };
+
+    let tracked = run(&["--all", "--verbose"]);
+    assert!(tracked.contains("tracked.txt"), "stdout: {tracked}");
+    assert!(!tracked.contains("new.txt"), "stdout: {tracked}");
+
+    let all = run(&["--all", "--include-untracked", "--verbose"]);
+    assert!(all.contains("[echo] 2 files"), "stdout: {all}");
+    assert!(all.contains("new.txt"), "stdout: {all}");
+    assert!(!all.contains("ignored.txt"), "stdout: {all}");
+}
+
+#[test]
+fn test_max_failures_stops_starting_tools() {
+    let dir = setup_test_dir(
+        r#"
+version: 1
+tools:
+  - name: first
+    include: ["*.txt"]
+    cmd: sh
+    args: ["-c", "touch ran-first; exit 1"]
+  - name: second
+    include: ["*.md"]
+    cmd: sh
+    args: ["-c", "touch ran-second; exit 1"]
+  - name: third
+    include: ["*.rs"]
+    cmd: sh
+    args: ["-c", "touch ran-third; exit 1"]
+"#,
+    );
+    init_repo_with_files(dir.path(), &["a.txt", "b.md", "c.rs"]);
+
+    let output = Command::new(ffx_binary())
+        .current_dir(dir.path())
+        .args([
+            "--all",
+            "--sequential",
+            "--max-failures",
+            "2",
+            "--color",
+            "never",
+        ])
+        .output()
+        .expect("Failed to run ffx");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(output.status.code(), Some(1), "stdout: {stdout}");
+    assert!(
+        stdout.contains("Stopped after 2 failures (--max-failures)"),
+        "stdout: {stdout}"
+    );
+    assert!(
+        stdout.contains("Skipped 1 tool (--max-failures): third"),
+        "stdout: {stdout}"
+    );
+    assert!(dir.path().join("ran-first").exists());
+    assert!(dir.path().join("ran-second").exists());
+    assert!(!dir.path().join("ran-third").exists());
+}
+
+#[test]
+fn test_fail_fast_reports_skipped_tools_but_not_for_diffs() {
+    let dir = setup_test_dir(
+        r#"
+version: 1
+tools:
+  - name: first
+    include: ["*.txt"]
+    cmd: sh
+    args: ["-c", "exit 2"]
+    diff_args: ["-c", "echo '-old'; exit 1"]
+  - name: second
+    include: ["*.md"]
+    cmd: sh
+    args: ["-c", "touch ran-second"]
+    diff_args: ["-c", "touch ran-second"]
+"#,
+    );
+    init_repo_with_files(dir.path(), &["a.txt", "b.md"]);
+    let run = |extra: &[&str]| {
+        Command::new(ffx_binary())
+            .current_dir(dir.path())
+            .args(["--all", "--sequential", "--fail-fast", "--color", "never"])
+            .args(extra)
+            .output()
+            .expect("Failed to run ffx")
+    };
+
+    // A diff is not a failure, so it doesn't stop the other tools
+    let output = run(&["--diff"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(output.status.code(), Some(0), "stdout: {stdout}");
+    assert!(!stdout.contains("Skipped"), "stdout: {stdout}");
+    assert!(dir.path().join("ran-second").exists());
+
+    fs::remove_file(dir.path().join("ran-second")).unwrap();
+    let output = run(&[]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(output.status.code(), Some(1), "stdout: {stdout}");
+    assert!(
+        stdout.contains("Skipped 1 tool (--fail-fast): second"),
+        "stdout: {stdout}"
+    );
+    assert!(!dir.path().join("ran-second").exists());
+}
+
+#[test]
+fn test_max_failures_rejects_zero() {
+    let output = Command::new(ffx_binary())
+        .args(["--max-failures", "0"])
+        .output()
+        .expect("Failed to run ffx");
+
+    assert_eq!(output.status.code(), Some(2));
+}